@@ -238,6 +238,32 @@ pub async fn handle_slab_command(
             
             spinner.finish_with_message("Slab update not yet implemented");
         }
+
+        SlabCommands::Sweep { slab, max, interval_secs } => {
+            let keypair = get_keypair(keypair_path)?;
+            let slab_pubkey = parse_pubkey(&slab)?;
+
+            loop {
+                let spinner = spinner("Sweeping expired reservations...");
+                let ix = client.build_sweep_expired(&slab_pubkey, &keypair.pubkey(), max);
+                match client.send_transaction(&[ix], &[&keypair], &keypair.pubkey()) {
+                    Ok(sig) => {
+                        spinner.finish_with_message("Sweep sent!");
+                        println!("{}", style(format!("Transaction: {}", sig)).green());
+                    }
+                    // A failed crank is retried on the next tick
+                    Err(e) if interval_secs.is_some() => {
+                        spinner.finish_with_message(&format!("Sweep failed: {}", e));
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+
+                match interval_secs {
+                    Some(secs) => tokio::time::sleep(Duration::from_secs(secs)).await,
+                    None => break,
+                }
+            }
+        }
     }
 
     Ok(())
@@ -475,12 +501,13 @@ pub async fn handle_info_command(rpc_url: &str, command: InfoCommands) -> Result
             print_trades_placeholder(&instrument, count);
         }
 
-        InfoCommands::Funding { instrument } => {
-            let spinner = spinner(&format!("Fetching {} funding rate...", instrument));
-            
-            // Would fetch funding here
+        InfoCommands::Funding { slab, instrument, count } => {
+            let slab_pubkey = parse_pubkey(&slab)?;
+            let spinner = spinner(&format!("Fetching instrument {} funding history...", instrument));
+
+            let history = client.get_funding_history(&slab_pubkey, instrument)?;
             spinner.finish_and_clear();
-            print_funding_placeholder(&instrument);
+            print_funding_history(&slab_pubkey, instrument, &history[..history.len().min(count)]);
         }
 
         InfoCommands::Liquidatable => {
@@ -147,6 +147,17 @@ pub enum SlabCommands {
         #[arg(long)]
         mmr_bps: Option<u64>,
     },
+    /// Release expired reservations (keeper crank)
+    Sweep {
+        /// Slab address
+        slab: String,
+        /// Reservations released per transaction
+        #[arg(long, default_value = "64")]
+        max: u8,
+        /// Keep cranking every N seconds
+        #[arg(long)]
+        interval_secs: Option<u64>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -263,10 +274,16 @@ pub enum InfoCommands {
         #[arg(short, long, default_value = "20")]
         count: usize,
     },
-    /// Show funding rate
+    /// Show funding rate history
     Funding {
-        /// Instrument (e.g., BTC-PERP)
-        instrument: String,
+        /// Slab address
+        slab: String,
+        /// Instrument index on the slab
+        #[arg(short, long, default_value = "0")]
+        instrument: u16,
+        /// Number of funding updates
+        #[arg(short, long, default_value = "24")]
+        count: usize,
     },
     /// Show liquidatable portfolios
     Liquidatable,
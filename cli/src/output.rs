@@ -4,7 +4,7 @@ use console::style;
 use tabled::{Table, Tabled, settings::Style};
 use solana_sdk::pubkey::Pubkey;
 
-use percolator_sdk::{UserPortfolio, PortfolioMarginResult, usdc_from_raw};
+use percolator_sdk::{FundingRecord, UserPortfolio, PortfolioMarginResult, usdc_from_raw};
 
 // ============================================================================
 // PORTFOLIO OUTPUT
@@ -148,17 +148,47 @@ pub fn print_trades_placeholder(instrument: &str, count: usize) {
     println!("{}", style("(No recent trades)").dim());
 }
 
-pub fn print_funding_placeholder(instrument: &str) {
-    println!("\n{} {}", style("═══ Funding Rate:").bold().cyan(), style(instrument).bold());
+pub fn print_funding_history(slab: &Pubkey, instrument: u16, history: &[FundingRecord]) {
+    println!("\n{} {}", style("═══ Funding Rate: instrument").bold().cyan(), style(instrument).bold());
     println!();
-    println!("{:<20} {:>15}", "Current Rate:", "0.0000%");
-    println!("{:<20} {:>15}", "Predicted Rate:", "0.0000%");
-    println!("{:<20} {:>15}", "Next Funding:", "N/A");
+    println!("{:<20} {}", "Slab:", format_pubkey(slab));
+    println!();
+
+    if history.is_empty() {
+        println!("{}", style("(No funding updates yet)").dim());
+        return;
+    }
+
+    println!("{:>20} {:>10} {:>10} {:>12} {:>12} {:>10}",
+        style("Time").dim(),
+        style("Rate").dim(),
+        style("Premium").dim(),
+        style("Mark").dim(),
+        style("Index").dim(),
+        style("Intervals").dim()
+    );
+    println!("{}", "─".repeat(79));
+    for record in history {
+        let time = chrono::DateTime::from_timestamp_millis(record.ts as i64)
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| record.ts.to_string());
+        println!("{:>20} {:>10} {:>10} {:>12} {:>12} {:>10}",
+            time,
+            format!("{:.4}%", record.rate as f64 / 100.0),
+            format!("{:.4}%", record.premium_bps as f64 / 100.0),
+            format!("{:.2}", record.mark_px as f64 / 1_000_000.0),
+            format!("{:.2}", record.index_price as f64 / 1_000_000.0),
+            record.intervals
+        );
+    }
+
+    let rates = history.iter().map(|r| r.rate);
     println!();
-    println!("{}", style("─── 24h History ───").dim());
-    println!("{:<20} {:>15}", "Average Rate:", "0.0000%");
-    println!("{:<20} {:>15}", "Max Rate:", "0.0000%");
-    println!("{:<20} {:>15}", "Min Rate:", "0.0000%");
+    println!("{}", style("─── Summary ───").dim());
+    println!("{:<20} {:>15}", "Average Rate:",
+        format!("{:.4}%", rates.clone().sum::<i64>() as f64 / history.len() as f64 / 100.0));
+    println!("{:<20} {:>15}", "Max Rate:", format!("{:.4}%", rates.clone().max().unwrap_or(0) as f64 / 100.0));
+    println!("{:<20} {:>15}", "Min Rate:", format!("{:.4}%", rates.min().unwrap_or(0) as f64 / 100.0));
     println!();
 }
 
@@ -0,0 +1,94 @@
+//! Clock - millisecond timestamps from the Clock sysvar
+//!
+//! Handlers never trust a timestamp from instruction data; they read the
+//! Clock sysvar instead. Clock only carries a unix timestamp in seconds, so
+//! the millisecond part is estimated from the slots elapsed since the second
+//! was first seen (`MS_PER_SLOT` each), capped below the next second. The
+//! result never goes backwards for the same anchor.
+
+use crate::error::PercolatorError;
+use pinocchio::sysvars::{clock::Clock, Sysvar};
+
+/// Assumed slot duration used to interpolate within a second (ms)
+pub const MS_PER_SLOT: u64 = pinocchio::sysvars::clock::DEFAULT_MS_PER_SLOT;
+
+/// Clock state a program keeps between readings
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClockAnchor {
+    /// First slot seen in the second of `ms`
+    pub slot: u64,
+    /// Timestamp of the last reading (ms)
+    pub ms: u64,
+}
+
+impl ClockAnchor {
+    /// Advance to a new clock reading and return its timestamp (ms)
+    ///
+    /// # Arguments
+    /// * `unix_timestamp` - Clock unix timestamp (seconds)
+    /// * `slot` - Clock slot
+    pub fn advance(&mut self, unix_timestamp: i64, slot: u64) -> u64 {
+        let second = unix_timestamp.max(0) as u64;
+        let second_ms = second.saturating_mul(1_000);
+
+        let now_ms = if second > self.ms / 1_000 {
+            self.slot = slot;
+            second_ms
+        } else {
+            let elapsed_ms = slot.saturating_sub(self.slot).saturating_mul(MS_PER_SLOT);
+            second_ms.saturating_add(elapsed_ms.min(999)).max(self.ms)
+        };
+
+        self.ms = now_ms;
+        now_ms
+    }
+}
+
+/// Read the Clock sysvar
+pub fn read_clock() -> Result<Clock, PercolatorError> {
+    Clock::get().map_err(|_| PercolatorError::InvalidClock)
+}
+
+/// Current timestamp (ms) from the Clock sysvar, advancing `anchor`
+pub fn current_ms(anchor: &mut ClockAnchor) -> Result<u64, PercolatorError> {
+    let clock = read_clock()?;
+    Ok(anchor.advance(clock.unix_timestamp, clock.slot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_second_starts_at_zero_ms() {
+        let mut anchor = ClockAnchor::default();
+        assert_eq!(anchor.advance(1_700_000_000, 500), 1_700_000_000_000);
+        assert_eq!(anchor.slot, 500);
+    }
+
+    #[test]
+    fn test_slots_interpolate_within_second() {
+        let mut anchor = ClockAnchor::default();
+        assert_eq!(anchor.advance(10, 100), 10_000);
+        assert_eq!(anchor.advance(10, 101), 10_000 + MS_PER_SLOT);
+        assert_eq!(anchor.advance(10, 102), 10_000 + 2 * MS_PER_SLOT);
+
+        // Capped below the next second, then reset by it
+        assert_eq!(anchor.advance(10, 110), 10_999);
+        assert_eq!(anchor.advance(11, 111), 11_000);
+        assert_eq!(anchor.advance(11, 112), 11_000 + MS_PER_SLOT);
+        assert_eq!(anchor.advance(13, 113), 13_000);
+    }
+
+    #[test]
+    fn test_never_goes_backwards() {
+        let mut anchor = ClockAnchor::default();
+        anchor.advance(10, 100);
+        let before = anchor.advance(10, 102);
+
+        // Same slot, or a clock that lags the anchor
+        assert_eq!(anchor.advance(10, 102), before);
+        assert_eq!(anchor.advance(9, 103), before);
+    }
+}
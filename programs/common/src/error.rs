@@ -49,6 +49,14 @@ pub enum PercolatorError {
     InvalidOrderState = 303,
     BookCorrupted = 304,
     ReservedQtyExceeded = 305,
+    PostOnlyWouldCross = 306,
+    ReduceOnlyViolation = 307,
+    InvalidSelfTradeMode = 308,
+    QuoteLevelLimitExceeded = 309,
+    DlpNotRegistered = 310,
+    DlpAlreadyRegistered = 311,
+    ReservedSliceMissing = 312,
+    OrderWouldCross = 313,
 
     // Risk errors (400-499)
     InsufficientMargin = 400,
@@ -79,6 +87,15 @@ pub enum PercolatorError {
     WithdrawalLocked = 702,
     InvalidInsuranceRate = 703,
     AdlRequired = 704,
+
+    // Oracle errors (800-899)
+    InvalidOracle = 800,
+    OracleStale = 801,
+    OracleConfidenceTooWide = 802,
+    OraclePriceUnavailable = 803,
+
+    // Clock errors (900-999)
+    InvalidClock = 900,
 }
 
 impl From<PercolatorError> for u64 {
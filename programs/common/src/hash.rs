@@ -0,0 +1,105 @@
+//! Hashing utilities
+//!
+//! SHA-256 goes through the `sol_sha256` syscall on-chain. Host builds
+//! (unit tests, off-chain clients) use the `sha2` crate, which produces
+//! the same digest.
+
+use crate::types::Side;
+
+/// Size of the commit-reveal salt
+pub const SALT_LEN: usize = 16;
+
+/// SHA-256 over the concatenation of `vals`
+#[cfg(target_os = "solana")]
+pub fn sha256(vals: &[&[u8]]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    // SAFETY: `vals` is laid out as the (ptr, len) pairs the syscall expects
+    unsafe {
+        pinocchio::syscalls::sol_sha256(
+            vals as *const _ as *const u8,
+            vals.len() as u64,
+            hash.as_mut_ptr(),
+        );
+    }
+    hash
+}
+
+/// SHA-256 over the concatenation of `vals`
+#[cfg(not(target_os = "solana"))]
+pub fn sha256(vals: &[&[u8]]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    for val in vals {
+        hasher.update(val);
+    }
+    hasher.finalize().into()
+}
+
+/// Commitment over a taker order for commit-reveal
+///
+/// `H(route_id || instrument_idx || side || qty || limit_px || salt)` with
+/// integers encoded little-endian.
+pub fn commitment_hash(
+    route_id: u64,
+    instrument_idx: u16,
+    side: Side,
+    qty: u64,
+    limit_px: u64,
+    salt: &[u8; SALT_LEN],
+) -> [u8; 32] {
+    sha256(&[
+        &route_id.to_le_bytes(),
+        &instrument_idx.to_le_bytes(),
+        &[side as u8],
+        &qty.to_le_bytes(),
+        &limit_px.to_le_bytes(),
+        salt,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8; 32]) -> [u8; 64] {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut out = [0u8; 64];
+        for (i, b) in bytes.iter().enumerate() {
+            out[2 * i] = DIGITS[(b >> 4) as usize];
+            out[2 * i + 1] = DIGITS[(b & 0xf) as usize];
+        }
+        out
+    }
+
+    #[test]
+    fn test_sha256_known_vectors() {
+        assert_eq!(
+            &hex(&sha256(&[b""])),
+            b"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            &hex(&sha256(&[b"abc"])),
+            b"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // Two-block message, split across inputs
+        assert_eq!(
+            &hex(&sha256(&[b"abcdbcdecdefdefgefghfghighijhijkijkl", b"jklmklmnlmnomnopnopq"])),
+            b"248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn test_commitment_hash_binds_every_field() {
+        let salt = [7u8; SALT_LEN];
+        let base = commitment_hash(1, 0, Side::Buy, 10, 100, &salt);
+
+        assert_eq!(base, commitment_hash(1, 0, Side::Buy, 10, 100, &salt));
+        assert_ne!(base, commitment_hash(2, 0, Side::Buy, 10, 100, &salt));
+        assert_ne!(base, commitment_hash(1, 1, Side::Buy, 10, 100, &salt));
+        assert_ne!(base, commitment_hash(1, 0, Side::Sell, 10, 100, &salt));
+        assert_ne!(base, commitment_hash(1, 0, Side::Buy, 11, 100, &salt));
+        assert_ne!(base, commitment_hash(1, 0, Side::Buy, 10, 101, &salt));
+        assert_ne!(base, commitment_hash(1, 0, Side::Buy, 10, 100, &[8u8; SALT_LEN]));
+    }
+}
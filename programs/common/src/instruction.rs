@@ -90,6 +90,35 @@ pub fn read_side(data: &[u8], offset: usize) -> Result<crate::Side, PercolatorEr
     }
 }
 
+/// Read a TimeInForce enum from instruction data
+#[inline]
+pub fn read_time_in_force(data: &[u8], offset: usize) -> Result<crate::TimeInForce, PercolatorError> {
+    let val = read_u8(data, offset)?;
+    match val {
+        0 => Ok(crate::TimeInForce::GTC),
+        1 => Ok(crate::TimeInForce::IOC),
+        2 => Ok(crate::TimeInForce::FOK),
+        _ => Err(PercolatorError::InvalidTimeInForce),
+    }
+}
+
+/// Read a MakerClass enum from instruction data
+#[inline]
+pub fn read_maker_class(data: &[u8], offset: usize) -> Result<crate::MakerClass, PercolatorError> {
+    let val = read_u8(data, offset)?;
+    match val {
+        0 => Ok(crate::MakerClass::REG),
+        1 => Ok(crate::MakerClass::DLP),
+        _ => Err(PercolatorError::InvalidMakerClass),
+    }
+}
+
+/// Read a SelfTradePrevention enum from instruction data
+#[inline]
+pub fn read_self_trade_prevention(data: &[u8], offset: usize) -> Result<crate::SelfTradePrevention, PercolatorError> {
+    crate::SelfTradePrevention::try_from(read_u8(data, offset)?)
+}
+
 /// Instruction data reader with tracked offset
 ///
 /// Provides a convenient way to sequentially read fields from instruction data
@@ -181,6 +210,30 @@ impl<'a> InstructionReader<'a> {
         self.offset += 1;
         Ok(val)
     }
+
+    /// Read a TimeInForce enum and advance offset
+    #[inline]
+    pub fn read_time_in_force(&mut self) -> Result<crate::TimeInForce, PercolatorError> {
+        let val = read_time_in_force(self.data, self.offset)?;
+        self.offset += 1;
+        Ok(val)
+    }
+
+    /// Read a MakerClass enum and advance offset
+    #[inline]
+    pub fn read_maker_class(&mut self) -> Result<crate::MakerClass, PercolatorError> {
+        let val = read_maker_class(self.data, self.offset)?;
+        self.offset += 1;
+        Ok(val)
+    }
+
+    /// Read a SelfTradePrevention enum and advance offset
+    #[inline]
+    pub fn read_self_trade_prevention(&mut self) -> Result<crate::SelfTradePrevention, PercolatorError> {
+        let val = read_self_trade_prevention(self.data, self.offset)?;
+        self.offset += 1;
+        Ok(val)
+    }
 }
 
 #[cfg(test)]
@@ -231,6 +284,32 @@ mod tests {
         assert!(read_side(&data, 2).is_err());
     }
 
+    #[test]
+    fn test_read_time_in_force() {
+        let data = [0u8, 1u8, 2u8, 3u8];
+        assert_eq!(read_time_in_force(&data, 0).unwrap(), crate::TimeInForce::GTC);
+        assert_eq!(read_time_in_force(&data, 1).unwrap(), crate::TimeInForce::IOC);
+        assert_eq!(read_time_in_force(&data, 2).unwrap(), crate::TimeInForce::FOK);
+        assert_eq!(read_time_in_force(&data, 3), Err(PercolatorError::InvalidTimeInForce));
+    }
+
+    #[test]
+    fn test_read_maker_class() {
+        let data = [0u8, 1u8, 2u8];
+        assert_eq!(read_maker_class(&data, 0).unwrap(), crate::MakerClass::REG);
+        assert_eq!(read_maker_class(&data, 1).unwrap(), crate::MakerClass::DLP);
+        assert_eq!(read_maker_class(&data, 2), Err(PercolatorError::InvalidMakerClass));
+    }
+
+    #[test]
+    fn test_read_self_trade_prevention() {
+        let data = [0u8, 3u8, 5u8, 6u8];
+        assert_eq!(read_self_trade_prevention(&data, 0).unwrap(), crate::SelfTradePrevention::SlabDefault);
+        assert_eq!(read_self_trade_prevention(&data, 1).unwrap(), crate::SelfTradePrevention::CancelTaking);
+        assert_eq!(read_self_trade_prevention(&data, 2).unwrap(), crate::SelfTradePrevention::Skip);
+        assert_eq!(read_self_trade_prevention(&data, 3), Err(PercolatorError::InvalidSelfTradeMode));
+    }
+
     #[test]
     fn test_instruction_reader() {
         let data = [
@@ -5,6 +5,9 @@ pub mod math;
 pub mod error;
 pub mod account;
 pub mod instruction;
+pub mod hash;
+pub mod oracle;
+pub mod clock;
 
 #[cfg(test)]
 mod tests;
@@ -14,3 +17,6 @@ pub use math::*;
 pub use error::*;
 pub use account::*;
 pub use instruction::*;
+pub use hash::*;
+pub use oracle::*;
+pub use clock::*;
@@ -94,6 +94,21 @@ pub fn round_to_lot(qty: u64, lot: u64) -> u64 {
     (qty / lot) * lot
 }
 
+/// Check whether the mark moved beyond the kill band since a reference mark
+///
+/// A zero reference or current mark (no oracle price yet) never trips the band.
+#[inline]
+pub fn exceeds_kill_band(reference_mark: i64, current_mark: i64, kill_band_bps: u64) -> bool {
+    if reference_mark == 0 || current_mark == 0 {
+        return false;
+    }
+
+    let diff = current_mark.abs_diff(reference_mark);
+    let threshold = (reference_mark.unsigned_abs() as u128 * kill_band_bps as u128) / 10_000;
+
+    diff as u128 > threshold
+}
+
 /// Calculate IM requirement: |qty| * contract_size * mark_price * imr
 #[inline]
 pub fn calculate_im(qty: i64, contract_size: u64, mark_price: u64, imr_bps: u64) -> u128 {
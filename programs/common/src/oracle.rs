@@ -0,0 +1,385 @@
+//! Oracle price adapters
+//!
+//! Reads a price from a Pyth or Switchboard price account, or from the
+//! in-repo test oracle format (`test-oracle` feature only), and normalizes it
+//! to the 1e6 price scale used across the programs. The layout is detected
+//! from the account's leading magic bytes and is only trusted from an account
+//! owned by the program that publishes it.
+
+use crate::error::PercolatorError;
+use crate::math::PRICE_DECIMALS;
+use pinocchio::pubkey::Pubkey;
+
+/// Default maximum age of an oracle price (milliseconds)
+pub const DEFAULT_ORACLE_MAX_STALENESS_MS: u64 = 60_000;
+
+/// Default maximum confidence interval relative to price (basis points)
+pub const DEFAULT_ORACLE_MAX_CONF_BPS: u64 = 100;
+
+/// Oracle price normalized to the 1e6 scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OraclePrice {
+    /// Price (1e6 scale)
+    pub price: u64,
+    /// Confidence interval (1e6 scale)
+    pub conf: u64,
+    /// Publish timestamp (ms)
+    pub publish_ms: u64,
+}
+
+/// Acceptance limits for an oracle price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OracleLimits {
+    /// Maximum age of the price (milliseconds)
+    pub max_staleness_ms: u64,
+    /// Maximum confidence interval relative to price (basis points)
+    pub max_conf_bps: u64,
+}
+
+impl Default for OracleLimits {
+    fn default() -> Self {
+        Self {
+            max_staleness_ms: DEFAULT_ORACLE_MAX_STALENESS_MS,
+            max_conf_bps: DEFAULT_ORACLE_MAX_CONF_BPS,
+        }
+    }
+}
+
+/// Read and validate the price held in an oracle account
+///
+/// # Arguments
+/// * `owner` - Program that owns the oracle account
+/// * `data` - Oracle account data
+/// * `now_ms` - Current timestamp (ms)
+/// * `limits` - Staleness and confidence limits
+pub fn load_oracle_price(
+    owner: &Pubkey,
+    data: &[u8],
+    now_ms: u64,
+    limits: &OracleLimits,
+) -> Result<OraclePrice, PercolatorError> {
+    let price = read_oracle_price(owner, data)?;
+    check_oracle_price(&price, now_ms, limits)?;
+    Ok(price)
+}
+
+/// Read the price held in an oracle account without validating it
+///
+/// Pyth and Switchboard layouts must be owned by their oracle program.
+pub fn read_oracle_price(owner: &Pubkey, data: &[u8]) -> Result<OraclePrice, PercolatorError> {
+    #[cfg(any(test, feature = "test-oracle"))]
+    if data.starts_with(&TestOracle::MAGIC) {
+        return TestOracle::parse(data);
+    }
+
+    if data.starts_with(&pyth::MAGIC.to_le_bytes()) {
+        check_owner(owner, &pyth::PROGRAM_ID)?;
+        pyth::parse(data)
+    } else if data.starts_with(&switchboard::DISCRIMINATOR) {
+        check_owner(owner, &switchboard::PROGRAM_ID)?;
+        switchboard::parse(data)
+    } else {
+        Err(PercolatorError::InvalidOracle)
+    }
+}
+
+/// Check that an oracle account is owned by the program publishing its layout
+fn check_owner(owner: &Pubkey, expected: &Pubkey) -> Result<(), PercolatorError> {
+    if owner != expected {
+        return Err(PercolatorError::InvalidOracle);
+    }
+    Ok(())
+}
+
+/// Check an oracle price against staleness and confidence limits
+pub fn check_oracle_price(
+    price: &OraclePrice,
+    now_ms: u64,
+    limits: &OracleLimits,
+) -> Result<(), PercolatorError> {
+    if now_ms.saturating_sub(price.publish_ms) > limits.max_staleness_ms {
+        return Err(PercolatorError::OracleStale);
+    }
+
+    // conf / price <= max_conf_bps / 10_000
+    if price.conf as u128 * 10_000 > price.price as u128 * limits.max_conf_bps as u128 {
+        return Err(PercolatorError::OracleConfidenceTooWide);
+    }
+
+    Ok(())
+}
+
+/// Convert a fixed-point value with exponent `expo` to the 1e6 price scale
+fn to_price_scale(value: u128, expo: i32) -> Result<u64, PercolatorError> {
+    let shift = expo.saturating_add(PRICE_DECIMALS as i32);
+    let scaled = if shift >= 0 {
+        10u128
+            .checked_pow(shift as u32)
+            .and_then(|m| value.checked_mul(m))
+            .ok_or(PercolatorError::Overflow)?
+    } else {
+        10u128
+            .checked_pow(shift.unsigned_abs())
+            .map_or(0, |d| value / d)
+    };
+    u64::try_from(scaled).map_err(|_| PercolatorError::Overflow)
+}
+
+/// Read `N` bytes at `offset`
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], PercolatorError> {
+    data.get(offset..offset + N)
+        .and_then(|b| b.try_into().ok())
+        .ok_or(PercolatorError::InvalidOracle)
+}
+
+/// Pyth price account (v2 layout)
+pub mod pyth {
+    use super::*;
+
+    /// Pyth oracle program (mainnet)
+    pub const PROGRAM_ID: Pubkey = pinocchio_pubkey::pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+    /// Magic number at offset 0
+    pub const MAGIC: u32 = 0xa1b2c3d4;
+    /// Supported layout version
+    pub const VERSION: u32 = 2;
+    /// Account type of a price account
+    pub const ACCOUNT_TYPE_PRICE: u32 = 3;
+    /// Aggregate status of a price that is currently trading
+    pub const STATUS_TRADING: u32 = 1;
+
+    pub const VERSION_OFFSET: usize = 4;
+    pub const ACCOUNT_TYPE_OFFSET: usize = 8;
+    pub const EXPO_OFFSET: usize = 20;
+    /// Publish time of the aggregate price (unix seconds)
+    pub const TIMESTAMP_OFFSET: usize = 96;
+    pub const AGG_PRICE_OFFSET: usize = 208;
+    pub const AGG_CONF_OFFSET: usize = 216;
+    pub const AGG_STATUS_OFFSET: usize = 224;
+
+    pub(super) fn parse(data: &[u8]) -> Result<OraclePrice, PercolatorError> {
+        let version = u32::from_le_bytes(read_bytes(data, VERSION_OFFSET)?);
+        let account_type = u32::from_le_bytes(read_bytes(data, ACCOUNT_TYPE_OFFSET)?);
+        if version != VERSION || account_type != ACCOUNT_TYPE_PRICE {
+            return Err(PercolatorError::InvalidOracle);
+        }
+
+        let status = u32::from_le_bytes(read_bytes(data, AGG_STATUS_OFFSET)?);
+        let price = i64::from_le_bytes(read_bytes(data, AGG_PRICE_OFFSET)?);
+        if status != STATUS_TRADING || price <= 0 {
+            return Err(PercolatorError::OraclePriceUnavailable);
+        }
+
+        let expo = i32::from_le_bytes(read_bytes(data, EXPO_OFFSET)?);
+        let conf = u64::from_le_bytes(read_bytes(data, AGG_CONF_OFFSET)?);
+        let timestamp = i64::from_le_bytes(read_bytes(data, TIMESTAMP_OFFSET)?);
+
+        Ok(OraclePrice {
+            price: to_price_scale(price as u128, expo)?,
+            conf: to_price_scale(conf as u128, expo)?,
+            publish_ms: (timestamp.max(0) as u64).saturating_mul(1_000),
+        })
+    }
+}
+
+/// Switchboard V2 aggregator account (packed layout)
+pub mod switchboard {
+    use super::*;
+
+    /// Switchboard V2 oracle program (mainnet)
+    pub const PROGRAM_ID: Pubkey = pinocchio_pubkey::pubkey!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+    /// Anchor discriminator of `AggregatorAccountData`
+    pub const DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+    // Offsets into `latest_confirmed_round`, which starts at byte 341
+    pub const NUM_SUCCESS_OFFSET: usize = 341;
+    /// Open time of the confirmed round (unix seconds)
+    pub const ROUND_OPEN_TIMESTAMP_OFFSET: usize = 358;
+    pub const RESULT_MANTISSA_OFFSET: usize = 366;
+    pub const RESULT_SCALE_OFFSET: usize = 382;
+    pub const STD_DEV_MANTISSA_OFFSET: usize = 386;
+    pub const STD_DEV_SCALE_OFFSET: usize = 402;
+
+    pub(super) fn parse(data: &[u8]) -> Result<OraclePrice, PercolatorError> {
+        let num_success = u32::from_le_bytes(read_bytes(data, NUM_SUCCESS_OFFSET)?);
+        let mantissa = i128::from_le_bytes(read_bytes(data, RESULT_MANTISSA_OFFSET)?);
+        if num_success == 0 || mantissa <= 0 {
+            return Err(PercolatorError::OraclePriceUnavailable);
+        }
+
+        let scale = u32::from_le_bytes(read_bytes(data, RESULT_SCALE_OFFSET)?);
+        let std_mantissa = i128::from_le_bytes(read_bytes(data, STD_DEV_MANTISSA_OFFSET)?);
+        let std_scale = u32::from_le_bytes(read_bytes(data, STD_DEV_SCALE_OFFSET)?);
+        let timestamp = i64::from_le_bytes(read_bytes(data, ROUND_OPEN_TIMESTAMP_OFFSET)?);
+
+        // A decimal is mantissa * 10^-scale
+        let to_expo = |scale: u32| -(scale.min(i32::MAX as u32) as i32);
+
+        Ok(OraclePrice {
+            price: to_price_scale(mantissa as u128, to_expo(scale))?,
+            conf: to_price_scale(std_mantissa.unsigned_abs(), to_expo(std_scale))?,
+            publish_ms: (timestamp.max(0) as u64).saturating_mul(1_000),
+        })
+    }
+}
+
+/// In-repo oracle price account, for local testing and program-test
+///
+/// The price is `price * 10^expo`; the publish time is in milliseconds.
+/// Accepted from any owner, so it only exists with the `test-oracle` feature.
+#[cfg(any(test, feature = "test-oracle"))]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TestOracle {
+    /// Magic bytes (`TestOracle::MAGIC`)
+    pub magic: [u8; 8],
+    /// Price mantissa
+    pub price: i64,
+    /// Confidence interval mantissa
+    pub conf: u64,
+    /// Price exponent
+    pub expo: i32,
+    /// Padding
+    pub _padding: [u8; 4],
+    /// Publish timestamp (ms)
+    pub publish_ms: u64,
+}
+
+#[cfg(any(test, feature = "test-oracle"))]
+impl TestOracle {
+    pub const MAGIC: [u8; 8] = *b"PERCORCL";
+    pub const LEN: usize = core::mem::size_of::<Self>();
+
+    /// Create a test oracle price
+    pub fn new(price: i64, conf: u64, expo: i32, publish_ms: u64) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            price,
+            conf,
+            expo,
+            _padding: [0; 4],
+            publish_ms,
+        }
+    }
+
+    /// Serialize to account data
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0u8; Self::LEN];
+        bytes[0..8].copy_from_slice(&self.magic);
+        bytes[8..16].copy_from_slice(&self.price.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.conf.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.expo.to_le_bytes());
+        bytes[32..40].copy_from_slice(&self.publish_ms.to_le_bytes());
+        bytes
+    }
+
+    fn parse(data: &[u8]) -> Result<OraclePrice, PercolatorError> {
+        let price = i64::from_le_bytes(read_bytes(data, 8)?);
+        if price <= 0 {
+            return Err(PercolatorError::OraclePriceUnavailable);
+        }
+
+        let conf = u64::from_le_bytes(read_bytes(data, 16)?);
+        let expo = i32::from_le_bytes(read_bytes(data, 24)?);
+
+        Ok(OraclePrice {
+            price: to_price_scale(price as u128, expo)?,
+            conf: to_price_scale(conf as u128, expo)?,
+            publish_ms: u64::from_le_bytes(read_bytes(data, 32)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pyth_account(price: i64, conf: u64, expo: i32, status: u32, timestamp: i64) -> [u8; 240] {
+        let mut data = [0u8; 240];
+        data[0..4].copy_from_slice(&pyth::MAGIC.to_le_bytes());
+        data[pyth::VERSION_OFFSET..][..4].copy_from_slice(&pyth::VERSION.to_le_bytes());
+        data[pyth::ACCOUNT_TYPE_OFFSET..][..4].copy_from_slice(&pyth::ACCOUNT_TYPE_PRICE.to_le_bytes());
+        data[pyth::EXPO_OFFSET..][..4].copy_from_slice(&expo.to_le_bytes());
+        data[pyth::TIMESTAMP_OFFSET..][..8].copy_from_slice(&timestamp.to_le_bytes());
+        data[pyth::AGG_PRICE_OFFSET..][..8].copy_from_slice(&price.to_le_bytes());
+        data[pyth::AGG_CONF_OFFSET..][..8].copy_from_slice(&conf.to_le_bytes());
+        data[pyth::AGG_STATUS_OFFSET..][..4].copy_from_slice(&status.to_le_bytes());
+        data
+    }
+
+    fn switchboard_account(mantissa: i128, scale: u32, std: i128, timestamp: i64) -> [u8; 406] {
+        let mut data = [0u8; 406];
+        data[0..8].copy_from_slice(&switchboard::DISCRIMINATOR);
+        data[switchboard::NUM_SUCCESS_OFFSET..][..4].copy_from_slice(&3u32.to_le_bytes());
+        data[switchboard::ROUND_OPEN_TIMESTAMP_OFFSET..][..8].copy_from_slice(&timestamp.to_le_bytes());
+        data[switchboard::RESULT_MANTISSA_OFFSET..][..16].copy_from_slice(&mantissa.to_le_bytes());
+        data[switchboard::RESULT_SCALE_OFFSET..][..4].copy_from_slice(&scale.to_le_bytes());
+        data[switchboard::STD_DEV_MANTISSA_OFFSET..][..16].copy_from_slice(&std.to_le_bytes());
+        data[switchboard::STD_DEV_SCALE_OFFSET..][..4].copy_from_slice(&scale.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_pyth_price() {
+        // $50,000.12345678 +/- $10 with expo -8
+        let data = pyth_account(5_000_012_345_678, 1_000_000_000, -8, pyth::STATUS_TRADING, 1_700_000_000);
+        let price = read_oracle_price(&pyth::PROGRAM_ID, &data).unwrap();
+        assert_eq!(price.price, 50_000_123_456);
+        assert_eq!(price.conf, 10_000_000);
+        assert_eq!(price.publish_ms, 1_700_000_000_000);
+
+        let halted = pyth_account(5_000_012_345_678, 0, -8, 0, 1_700_000_000);
+        assert_eq!(read_oracle_price(&pyth::PROGRAM_ID, &halted), Err(PercolatorError::OraclePriceUnavailable));
+    }
+
+    #[test]
+    fn test_switchboard_price() {
+        // 3000.5 with scale 1, std 0.5
+        let data = switchboard_account(30_005, 1, 5, 1_700_000_000);
+        let price = read_oracle_price(&switchboard::PROGRAM_ID, &data).unwrap();
+        assert_eq!(price.price, 3_000_500_000);
+        assert_eq!(price.conf, 500_000);
+        assert_eq!(price.publish_ms, 1_700_000_000_000);
+    }
+
+    #[test]
+    fn test_test_oracle_roundtrip() {
+        let data = TestOracle::new(50_000, 5, 0, 1_000).to_bytes();
+        let price = read_oracle_price(&[9; 32], &data).unwrap();
+        assert_eq!(price, OraclePrice { price: 50_000_000_000, conf: 5_000_000, publish_ms: 1_000 });
+    }
+
+    #[test]
+    fn test_unknown_layout_rejected() {
+        assert_eq!(read_oracle_price(&[9; 32], &[0u8; 64]), Err(PercolatorError::InvalidOracle));
+        assert_eq!(read_oracle_price(&[9; 32], &TestOracle::MAGIC), Err(PercolatorError::InvalidOracle));
+    }
+
+    #[test]
+    fn test_owner_must_publish_layout() {
+        let data = pyth_account(5_000_012_345_678, 0, -8, pyth::STATUS_TRADING, 1_700_000_000);
+        assert_eq!(read_oracle_price(&[9; 32], &data), Err(PercolatorError::InvalidOracle));
+        assert_eq!(read_oracle_price(&switchboard::PROGRAM_ID, &data), Err(PercolatorError::InvalidOracle));
+
+        let data = switchboard_account(30_005, 1, 5, 1_700_000_000);
+        assert_eq!(read_oracle_price(&pyth::PROGRAM_ID, &data), Err(PercolatorError::InvalidOracle));
+    }
+
+    #[test]
+    fn test_staleness_and_confidence_limits() {
+        let limits = OracleLimits::default();
+        let data = TestOracle::new(50_000_000_000, 50_000_000, -6, 10_000).to_bytes();
+
+        assert!(load_oracle_price(&[9; 32], &data, 10_000 + limits.max_staleness_ms, &limits).is_ok());
+        assert_eq!(
+            load_oracle_price(&[9; 32], &data, 10_001 + limits.max_staleness_ms, &limits),
+            Err(PercolatorError::OracleStale)
+        );
+
+        // $500 on $50,000 is 1%, right at the default limit
+        let wide = TestOracle::new(50_000_000_000, 500_000_001, -6, 10_000).to_bytes();
+        assert_eq!(
+            load_oracle_price(&[9; 32], &wide, 10_000, &limits),
+            Err(PercolatorError::OracleConfidenceTooWide)
+        );
+    }
+}
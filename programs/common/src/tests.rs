@@ -64,6 +64,17 @@ mod math_tests {
         assert_eq!(payment, 5000);
     }
 
+    #[test]
+    fn test_kill_band() {
+        // 1% band around $50,000
+        assert!(!exceeds_kill_band(50_000_000_000, 50_500_000_000, 100));
+        assert!(exceeds_kill_band(50_000_000_000, 50_500_000_001, 100));
+        assert!(exceeds_kill_band(50_000_000_000, 49_400_000_000, 100));
+
+        // No reference mark yet
+        assert!(!exceeds_kill_band(0, 50_000_000_000, 100));
+    }
+
     #[test]
     fn test_tick_alignment() {
         assert!(is_tick_aligned(50_000, 1000));
@@ -60,6 +60,43 @@ pub enum MakerClass {
     DLP = 1, // Designated LP - posts immediately
 }
 
+/// Self-trade prevention mode
+///
+/// Applied when a taker's walk reaches a resting order from the same account.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelfTradePrevention {
+    #[default]
+    SlabDefault = 0,   // Per-order only: use the slab header's mode
+    Allow = 1,         // Self-trades are permitted
+    CancelResting = 2, // Cancel the unreserved part of the resting order
+    CancelTaking = 3,  // Stop the walk, keep what was reserved so far
+    DecrementBoth = 4, // Shrink both orders by the overlap, no trade
+    Skip = 5,          // Leave the resting order and walk past it
+}
+
+impl TryFrom<u8> for SelfTradePrevention {
+    type Error = crate::PercolatorError;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        match val {
+            0 => Ok(Self::SlabDefault),
+            1 => Ok(Self::Allow),
+            2 => Ok(Self::CancelResting),
+            3 => Ok(Self::CancelTaking),
+            4 => Ok(Self::DecrementBoth),
+            5 => Ok(Self::Skip),
+            _ => Err(crate::PercolatorError::InvalidSelfTradeMode),
+        }
+    }
+}
+
+/// Order flag: reject the order if it would cross the book
+pub const ORDER_FLAG_POST_ONLY: u8 = 1 << 0;
+
+/// Order flag: the order may only reduce an existing position
+pub const ORDER_FLAG_REDUCE_ONLY: u8 = 1 << 1;
+
 /// Order state
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -81,19 +118,25 @@ pub struct AccountState {
     pub im: u128,
     /// Maintenance margin requirement
     pub mm: u128,
+    /// Total funding paid since the account was opened
+    pub funding_paid: u128,
+    /// Total funding received since the account was opened
+    pub funding_received: u128,
     /// Head of position linked list
     pub position_head: u32,
+    /// Head of the account's resting order list
+    pub order_head: u32,
     /// Account index
     pub index: u32,
     /// Account active flag
     pub active: bool,
     /// Padding
-    pub _padding: [u8; 7],
+    pub _padding: [u8; 3],
 }
 
 /// Instrument definition
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct Instrument {
     /// Instrument symbol (8 bytes, e.g., "BTC-PERP")
     pub symbol: [u8; 8],
@@ -105,12 +148,36 @@ pub struct Instrument {
     pub lot: u64,
     /// Current index price (from oracle)
     pub index_price: u64,
+    /// Mark price (1e6 scale)
+    pub mark_px: i64,
+    /// Previous mark price
+    pub prev_mark_px: i64,
+    /// Oracle timestamp of the current mark (ms)
+    pub mark_ts: u64,
+    /// EMA of the book basis (mid - index, 1e6 scale)
+    pub basis_ema: i64,
+    /// Timestamp of the last basis sample (ms)
+    pub basis_ts: u64,
+    /// Maximum mark move between reserve and commit (basis points)
+    pub kill_band_bps: u64,
+    /// Oracle price account feeding the mark (all zeros if unset)
+    pub oracle: Pubkey,
+    /// Program that owns the oracle price account
+    pub oracle_program: Pubkey,
     /// Current funding rate (basis points per hour)
     pub funding_rate: i64,
     /// Cumulative funding
     pub cum_funding: i128,
     /// Last funding timestamp
     pub last_funding_ts: u64,
+    /// Time-weighted premium accumulated this interval (basis points * ms)
+    pub premium_acc: i128,
+    /// Time covered by `premium_acc` (ms)
+    pub premium_acc_ms: u64,
+    /// Timestamp of the last premium sample
+    pub premium_sample_ts: u64,
+    /// Premium observed at the last sample (basis points)
+    pub premium_last_bps: i64,
     /// Bids book head
     pub bids_head: u32,
     /// Asks book head
@@ -119,14 +186,37 @@ pub struct Instrument {
     pub bids_pending_head: u32,
     /// Pending asks head
     pub asks_pending_head: u32,
+    /// Bids price-level tree root
+    pub bids_root: u32,
+    /// Asks price-level tree root
+    pub asks_root: u32,
+    /// Pending bids price-level tree root
+    pub bids_pending_root: u32,
+    /// Pending asks price-level tree root
+    pub asks_pending_root: u32,
     /// Current epoch
     pub epoch: u16,
     /// Instrument index
     pub index: u16,
+    /// Head of this instrument's aggressor ledger entries
+    pub aggressor_head: u32,
     /// Batch open timestamp
     pub batch_open_ms: u64,
     /// Freeze until timestamp
     pub freeze_until_ms: u64,
+    /// Worst frozen bid price (0 if no bid level is frozen)
+    pub frozen_bid_px: u64,
+    /// Worst frozen ask price (0 if no ask level is frozen)
+    pub frozen_ask_px: u64,
+}
+
+impl Instrument {
+    /// Update mark price (stores the previous mark)
+    pub fn update_mark_px(&mut self, new_mark_px: i64, oracle_ts: u64) {
+        self.prev_mark_px = self.mark_px;
+        self.mark_px = new_mark_px;
+        self.mark_ts = oracle_ts;
+    }
 }
 
 /// Order in the book
@@ -151,6 +241,8 @@ pub struct Order {
     pub eligible_epoch: u16,
     /// Creation timestamp
     pub created_ms: u64,
+    /// Frozen until timestamp (top-K freeze: no cancel or amend before this)
+    pub frozen_until_ms: u64,
     /// Price
     pub price: u64,
     /// Quantity
@@ -163,12 +255,18 @@ pub struct Order {
     pub next: u32,
     /// Previous order in book
     pub prev: u32,
+    /// Next order of the same account
+    pub next_in_account: u32,
+    /// Previous order of the same account
+    pub prev_in_account: u32,
     /// Next in freelist
     pub next_free: u32,
     /// Used flag
     pub used: bool,
+    /// Order flags (ORDER_FLAG_*)
+    pub flags: u8,
     /// Padding
-    pub _padding: [u8; 3],
+    pub _padding: [u8; 2],
 }
 
 /// Position
@@ -205,6 +303,8 @@ pub struct Slice {
     pub order_idx: u32,
     /// Quantity reserved from this order
     pub qty: u64,
+    /// ID of the reserved order (detects a reused order slot)
+    pub order_id: u64,
     /// Next slice in reservation
     pub next: u32,
     /// Slice index
@@ -229,19 +329,27 @@ pub struct Reservation {
     pub instrument_idx: u16,
     /// Side
     pub side: Side,
-    /// Padding
-    pub _padding: u8,
+    /// Time in force of the taker order
+    pub tif: TimeInForce,
     /// Quantity to fill
     pub qty: u64,
     /// VWAP price of reserved slices
     pub vwap_px: u64,
     /// Worst price in reservation
     pub worst_px: u64,
+    /// Quantity requested by the taker (commit-reveal preimage)
+    pub req_qty: u64,
+    /// Limit price of the taker order (commit-reveal preimage)
+    pub limit_px: u64,
+    /// Instrument mark at reserve time (kill band reference)
+    pub mark_px: i64,
+    /// Oracle timestamp of the mark snapshot (ms)
+    pub mark_ts: u64,
     /// Maximum charge (fees + notional)
     pub max_charge: u128,
     /// Commitment hash for commit-reveal
     pub commitment_hash: [u8; 32],
-    /// Salt for commitment
+    /// Salt for commitment (revealed by the router at commit)
     pub salt: [u8; 16],
     /// Book sequence number at hold time
     pub book_seqno: u64,
@@ -255,8 +363,12 @@ pub struct Reservation {
     pub used: bool,
     /// Committed flag
     pub committed: bool,
+    /// Order flags of the taker order (ORDER_FLAG_*)
+    pub flags: u8,
     /// Padding
-    pub _padding2: [u8; 6],
+    pub _padding2: u8,
+    /// Position in the slab's expiry index (u32::MAX when not indexed)
+    pub expiry_pos: u32,
 }
 
 /// Trade record in ring buffer
@@ -285,6 +397,28 @@ pub struct Trade {
     pub reveal_ms: u64,
 }
 
+/// Funding history entry, one per funding update of an instrument
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FundingRecord {
+    /// End of the last interval charged (ms)
+    pub ts: u64,
+    /// Funding rate charged per interval (basis points)
+    pub rate: i64,
+    /// TWAP premium the rate was derived from (basis points)
+    pub premium_bps: i64,
+    /// Mark price at the update (1e6 scale)
+    pub mark_px: i64,
+    /// Index price at the update (1e6 scale)
+    pub index_price: u64,
+    /// Instrument index
+    pub instrument_idx: u16,
+    /// Number of intervals charged at `rate`
+    pub intervals: u16,
+    /// Padding
+    pub _padding: [u8; 4],
+}
+
 /// Aggressor ledger entry for anti-sandwich
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
@@ -303,10 +437,12 @@ pub struct AggressorEntry {
     pub sell_qty: u64,
     /// Sell notional this batch
     pub sell_notional: u128,
+    /// Next entry of the same instrument, or next free entry
+    pub next: u32,
     /// Used flag
     pub used: bool,
     /// Padding
-    pub _padding: [u8; 7],
+    pub _padding: [u8; 3],
 }
 
 /// Maximum aggressor ledger entries (shared pool, not per account-instrument)
@@ -4,10 +4,11 @@
 //! instructions (reserve, commit, cancel, liquidation) with proper
 //! lifetime handling and actual invoke calls.
 
+use crate::state::SlabEntry;
 use percolator_common::*;
 use pinocchio::{
     account_info::AccountInfo,
-    cpi::{invoke, get_return_data},
+    cpi::{invoke, invoke_with_bounds, get_return_data},
     instruction::{AccountMeta, Instruction},
     msg,
     pubkey::Pubkey,
@@ -31,11 +32,14 @@ pub mod slab_ix {
 /// Maximum accounts for CPI calls
 pub const MAX_CPI_ACCOUNTS: usize = 16;
 
+/// Maximum oracle accounts forwarded with a liquidation
+pub const MAX_LIQUIDATION_ORACLES: usize = 4;
+
 /// Reserve instruction data size
 pub const RESERVE_IX_DATA_SIZE: usize = 73;
 
 /// Commit instruction data size
-pub const COMMIT_IX_DATA_SIZE: usize = 17;
+pub const COMMIT_IX_DATA_SIZE: usize = 33;
 
 /// Cancel instruction data size
 pub const CANCEL_IX_DATA_SIZE: usize = 9;
@@ -118,12 +122,16 @@ pub struct CommitResponse {
     pub fees: u128,
     /// Realized PnL (if closing position)
     pub realized_pnl: i128,
+    /// Reserved quantity released back to the book unfilled
+    pub released_qty: u64,
+    /// Reserved quantity lost because its maker orders left the book
+    pub shortfall_qty: u64,
 }
 
 impl CommitResponse {
     /// Parse from CPI return data
     pub fn from_return_data(data: &[u8]) -> Result<Self, PercolatorError> {
-        if data.len() < 64 {
+        if data.len() < 80 {
             msg!("Error: Commit return data too short");
             return Err(PercolatorError::CpiError);
         }
@@ -134,17 +142,21 @@ impl CommitResponse {
             notional: u128::from_le_bytes(data[16..32].try_into().map_err(|_| PercolatorError::CpiError)?),
             fees: u128::from_le_bytes(data[32..48].try_into().map_err(|_| PercolatorError::CpiError)?),
             realized_pnl: i128::from_le_bytes(data[48..64].try_into().map_err(|_| PercolatorError::CpiError)?),
+            released_qty: u64::from_le_bytes(data[64..72].try_into().map_err(|_| PercolatorError::CpiError)?),
+            shortfall_qty: u64::from_le_bytes(data[72..80].try_into().map_err(|_| PercolatorError::CpiError)?),
         })
     }
 
     /// Serialize to bytes for return data
-    pub fn to_bytes(&self) -> [u8; 64] {
-        let mut bytes = [0u8; 64];
+    pub fn to_bytes(&self) -> [u8; 80] {
+        let mut bytes = [0u8; 80];
         bytes[0..8].copy_from_slice(&self.filled_qty.to_le_bytes());
         bytes[8..16].copy_from_slice(&self.vwap_px.to_le_bytes());
         bytes[16..32].copy_from_slice(&self.notional.to_le_bytes());
         bytes[32..48].copy_from_slice(&self.fees.to_le_bytes());
         bytes[48..64].copy_from_slice(&self.realized_pnl.to_le_bytes());
+        bytes[64..72].copy_from_slice(&self.released_qty.to_le_bytes());
+        bytes[72..80].copy_from_slice(&self.shortfall_qty.to_le_bytes());
         bytes
     }
 }
@@ -209,13 +221,14 @@ pub fn serialize_reserve_data(
     data
 }
 
-/// Serialize commit instruction data
+/// Serialize commit instruction data (`qty` 0 commits the whole reservation)
 #[inline]
-pub fn serialize_commit_data(hold_id: u64, current_ts: u64) -> [u8; COMMIT_IX_DATA_SIZE] {
+pub fn serialize_commit_data(hold_id: u64, salt: &[u8; SALT_LEN], qty: u64) -> [u8; COMMIT_IX_DATA_SIZE] {
     let mut data = [0u8; COMMIT_IX_DATA_SIZE];
     data[0] = slab_ix::COMMIT;
     data[1..9].copy_from_slice(&hold_id.to_le_bytes());
-    data[9..17].copy_from_slice(&current_ts.to_le_bytes());
+    data[9..25].copy_from_slice(salt);
+    data[25..33].copy_from_slice(&qty.to_le_bytes());
     data
 }
 
@@ -247,6 +260,7 @@ pub fn serialize_liquidation_data(account_idx: u32, deficit: u128) -> [u8; LIQUI
 /// # Arguments
 /// * `slab_program` - Slab program account info
 /// * `slab_state` - Slab state account info (writable)
+/// * `authority` - Account owner or router authority (signer)
 /// * `account_idx` - Account index on slab
 /// * `instrument_idx` - Instrument index
 /// * `side` - Order side
@@ -261,6 +275,7 @@ pub fn serialize_liquidation_data(account_idx: u32, deficit: u128) -> [u8; LIQUI
 pub fn cpi_reserve<'a>(
     slab_program: &'a AccountInfo,
     slab_state: &'a AccountInfo,
+    authority: &'a AccountInfo,
     account_idx: u32,
     instrument_idx: u16,
     side: Side,
@@ -283,7 +298,10 @@ pub fn cpi_reserve<'a>(
     );
 
     // Build account metas - slab_state must be writable
-    let account_metas = [AccountMeta::writable(slab_state.key())];
+    let account_metas = [
+        AccountMeta::writable(slab_state.key()),
+        AccountMeta::readonly_signer(authority.key()),
+    ];
 
     // Build instruction
     let instruction = Instruction {
@@ -293,8 +311,8 @@ pub fn cpi_reserve<'a>(
     };
 
     // Execute CPI
-    let account_infos = [slab_state];
-    invoke::<1>(&instruction, &account_infos)
+    let account_infos = [slab_state, authority];
+    invoke::<2>(&instruction, &account_infos)
         .map_err(|_| {
             msg!("Error: Reserve CPI failed");
             PercolatorError::CpiError
@@ -321,22 +339,30 @@ pub fn cpi_reserve<'a>(
 /// # Arguments
 /// * `slab_program` - Slab program account info
 /// * `slab_state` - Slab state account info (writable)
+/// * `authority` - Account owner or router authority (signer)
 /// * `hold_id` - Hold ID from reserve
-/// * `current_ts` - Current timestamp for expiry check
+/// * `salt` - Salt that opens the reservation's commitment hash
+/// * `qty` - Quantity to fill (0 for the whole reservation); the slab
+///   releases the rest of the reservation back to the book
 ///
 /// # Returns
 /// * `CommitResponse` with execution details
 pub fn cpi_commit<'a>(
     slab_program: &'a AccountInfo,
     slab_state: &'a AccountInfo,
+    authority: &'a AccountInfo,
     hold_id: u64,
-    current_ts: u64,
+    salt: &[u8; SALT_LEN],
+    qty: u64,
 ) -> Result<CommitResponse, PercolatorError> {
-    // Build instruction data
-    let ix_data = serialize_commit_data(hold_id, current_ts);
+    // Build instruction data (the slab checks expiry against its own clock)
+    let ix_data = serialize_commit_data(hold_id, salt, qty);
 
     // Build account metas
-    let account_metas = [AccountMeta::writable(slab_state.key())];
+    let account_metas = [
+        AccountMeta::writable(slab_state.key()),
+        AccountMeta::readonly_signer(authority.key()),
+    ];
 
     // Build instruction
     let instruction = Instruction {
@@ -346,8 +372,8 @@ pub fn cpi_commit<'a>(
     };
 
     // Execute CPI
-    let account_infos = [slab_state];
-    invoke::<1>(&instruction, &account_infos)
+    let account_infos = [slab_state, authority];
+    invoke::<2>(&instruction, &account_infos)
         .map_err(|_| {
             msg!("Error: Commit CPI failed");
             PercolatorError::CpiError
@@ -373,17 +399,22 @@ pub fn cpi_commit<'a>(
 /// # Arguments
 /// * `slab_program` - Slab program account info
 /// * `slab_state` - Slab state account info (writable)
+/// * `authority` - Account owner or router authority (signer)
 /// * `hold_id` - Hold ID to cancel
 pub fn cpi_cancel<'a>(
     slab_program: &'a AccountInfo,
     slab_state: &'a AccountInfo,
+    authority: &'a AccountInfo,
     hold_id: u64,
 ) -> Result<(), PercolatorError> {
     // Build instruction data
     let ix_data = serialize_cancel_data(hold_id);
 
     // Build account metas
-    let account_metas = [AccountMeta::writable(slab_state.key())];
+    let account_metas = [
+        AccountMeta::writable(slab_state.key()),
+        AccountMeta::readonly_signer(authority.key()),
+    ];
 
     // Build instruction
     let instruction = Instruction {
@@ -393,8 +424,8 @@ pub fn cpi_cancel<'a>(
     };
 
     // Execute CPI
-    let account_infos = [slab_state];
-    invoke::<1>(&instruction, &account_infos)
+    let account_infos = [slab_state, authority];
+    invoke::<2>(&instruction, &account_infos)
         .map_err(|_| {
             msg!("Error: Cancel CPI failed");
             PercolatorError::CpiError
@@ -405,36 +436,69 @@ pub fn cpi_cancel<'a>(
 
 /// Execute liquidation CPI to slab program
 ///
+/// The oracle accounts are checked against the slab's registered oracle
+/// program and price limits, then forwarded so the slab refreshes the marks
+/// of the instruments bound to them before liquidating.
+///
 /// # Arguments
 /// * `slab_program` - Slab program account info
 /// * `slab_state` - Slab state account info (writable)
+/// * `authority` - Router authority (signer), so the slab honours `deficit`
+/// * `slab_entry` - Registry entry of the slab
+/// * `oracles` - Oracle price accounts (at most `MAX_LIQUIDATION_ORACLES`)
 /// * `account_idx` - Account index on slab
 /// * `deficit` - Target deficit to liquidate
+/// * `now_ms` - Current timestamp (ms)
 ///
 /// # Returns
 /// * `LiquidationResponse` with liquidation details
+#[allow(clippy::too_many_arguments)]
 pub fn cpi_liquidation<'a>(
     slab_program: &'a AccountInfo,
     slab_state: &'a AccountInfo,
+    authority: &'a AccountInfo,
+    slab_entry: &SlabEntry,
+    oracles: &[&'a AccountInfo],
     account_idx: u32,
     deficit: u128,
+    now_ms: u64,
 ) -> Result<LiquidationResponse, PercolatorError> {
+    if oracles.len() > MAX_LIQUIDATION_ORACLES {
+        msg!("Error: Too many oracle accounts");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    // Only forward prices from the slab's registered oracle program
+    for oracle in oracles {
+        let data = oracle.try_borrow_data().map_err(|_| PercolatorError::InvalidOracle)?;
+        slab_entry.read_oracle_price(oracle.owner(), &data, now_ms).inspect_err(|_| {
+            msg!("Error: Oracle account rejected");
+        })?;
+    }
+
     // Build instruction data
     let ix_data = serialize_liquidation_data(account_idx, deficit);
 
-    // Build account metas
-    let account_metas = [AccountMeta::writable(slab_state.key())];
+    // Build account metas: slab, authority, then the oracles
+    let count = 2 + oracles.len();
+    let account_metas: [AccountMeta; 2 + MAX_LIQUIDATION_ORACLES] = core::array::from_fn(|i| match i {
+        0 => AccountMeta::writable(slab_state.key()),
+        1 => AccountMeta::readonly_signer(authority.key()),
+        _ => AccountMeta::readonly(oracles.get(i - 2).map_or(slab_state.key(), |o| o.key())),
+    });
 
     // Build instruction
     let instruction = Instruction {
         program_id: slab_program.key(),
-        accounts: &account_metas,
+        accounts: &account_metas[..count],
         data: &ix_data,
     };
 
     // Execute CPI
-    let account_infos = [slab_state];
-    invoke::<1>(&instruction, &account_infos)
+    let mut account_infos = [slab_state; 2 + MAX_LIQUIDATION_ORACLES];
+    account_infos[1] = authority;
+    account_infos[2..count].copy_from_slice(oracles);
+    invoke_with_bounds::<{ 2 + MAX_LIQUIDATION_ORACLES }>(&instruction, &account_infos[..count])
         .map_err(|_| {
             msg!("Error: Liquidation CPI failed");
             PercolatorError::CpiError
@@ -515,6 +579,7 @@ impl Default for MultiCommitResult {
 /// # Arguments
 /// * `slab_programs` - Slice of slab program account infos
 /// * `slab_states` - Slice of slab state account infos
+/// * `authority` - Account owner or router authority (signer)
 /// * `params` - Reserve parameters for each slab
 ///
 /// # Returns
@@ -522,6 +587,7 @@ impl Default for MultiCommitResult {
 pub fn atomic_multi_reserve<'a>(
     slab_programs: &[&'a AccountInfo],
     slab_states: &[&'a AccountInfo],
+    authority: &'a AccountInfo,
     params: &[ReserveParams],
 ) -> Result<MultiReserveResult, PercolatorError> {
     // Validate input lengths
@@ -547,6 +613,7 @@ pub fn atomic_multi_reserve<'a>(
         let reserve_result = cpi_reserve(
             slab_programs[i],
             slab_states[i],
+            authority,
             params[i].account_idx,
             params[i].instrument_idx,
             params[i].side,
@@ -566,7 +633,7 @@ pub fn atomic_multi_reserve<'a>(
                 // Rollback: Cancel all previous reservations
                 msg!("Error: Reserve failed, initiating rollback");
                 for j in 0..result.count as usize {
-                    let _ = cpi_cancel(slab_programs[j], slab_states[j], result.responses[j].hold_id);
+                    let _ = cpi_cancel(slab_programs[j], slab_states[j], authority, result.responses[j].hold_id);
                 }
                 return Err(e);
             }
@@ -585,21 +652,29 @@ pub fn atomic_multi_reserve<'a>(
 /// # Arguments
 /// * `slab_programs` - Slice of slab program account infos
 /// * `slab_states` - Slice of slab state account infos
+/// * `authority` - Account owner or router authority (signer)
 /// * `reserve_result` - Reserve result from prior reservations
-/// * `current_ts` - Current timestamp for expiry check
+/// * `salts` - Commit-reveal salt for each reservation, in the same order
+/// * `qtys` - Quantity to commit from each reservation (0 for all of it)
 ///
 /// # Returns
 /// * `MultiCommitResult` on success
 pub fn atomic_multi_commit<'a>(
     slab_programs: &[&'a AccountInfo],
     slab_states: &[&'a AccountInfo],
+    authority: &'a AccountInfo,
     reserve_result: &MultiReserveResult,
-    current_ts: u64,
+    salts: &[[u8; SALT_LEN]],
+    qtys: &[u64],
 ) -> Result<MultiCommitResult, PercolatorError> {
     let reservation_count = reserve_result.count as usize;
     
     // Validate input lengths
-    if slab_programs.len() < reservation_count || slab_states.len() < reservation_count {
+    if slab_programs.len() < reservation_count
+        || slab_states.len() < reservation_count
+        || salts.len() < reservation_count
+        || qtys.len() < reservation_count
+    {
         msg!("Error: Not enough accounts for reservations");
         return Err(PercolatorError::InvalidInstruction);
     }
@@ -611,12 +686,17 @@ pub fn atomic_multi_commit<'a>(
         let commit_result = cpi_commit(
             slab_programs[i],
             slab_states[i],
+            authority,
             reserve_result.responses[i].hold_id,
-            current_ts,
+            &salts[i],
+            qtys[i],
         );
         
         match commit_result {
             Ok(r) => {
+                if r.shortfall_qty > 0 {
+                    msg!("Warning: Slab commit fell short of its reservation");
+                }
                 result.responses[i] = r;
                 result.count += 1;
             }
@@ -624,7 +704,7 @@ pub fn atomic_multi_commit<'a>(
                 // Cancel remaining reservations (commits can't be undone)
                 msg!("Error: Commit failed, cancelling remaining");
                 for j in (i + 1)..reservation_count {
-                    let _ = cpi_cancel(slab_programs[j], slab_states[j], reserve_result.responses[j].hold_id);
+                    let _ = cpi_cancel(slab_programs[j], slab_states[j], authority, reserve_result.responses[j].hold_id);
                 }
                 return Err(e);
             }
@@ -639,10 +719,12 @@ pub fn atomic_multi_commit<'a>(
 /// # Arguments
 /// * `slab_programs` - Slice of slab program account infos
 /// * `slab_states` - Slice of slab state account infos
+/// * `authority` - Account owner or router authority (signer)
 /// * `hold_ids` - Hold IDs to cancel
 pub fn multi_cancel<'a>(
     slab_programs: &[&'a AccountInfo],
     slab_states: &[&'a AccountInfo],
+    authority: &'a AccountInfo,
     hold_ids: &[u64],
 ) -> Result<(), PercolatorError> {
     if slab_programs.len() != slab_states.len() || slab_programs.len() != hold_ids.len() {
@@ -651,7 +733,7 @@ pub fn multi_cancel<'a>(
     }
 
     for i in 0..hold_ids.len() {
-        cpi_cancel(slab_programs[i], slab_states[i], hold_ids[i])?;
+        cpi_cancel(slab_programs[i], slab_states[i], authority, hold_ids[i])?;
     }
     
     Ok(())
@@ -719,13 +801,15 @@ mod tests {
 
     #[test]
     fn test_serialize_commit_data() {
-        let data = serialize_commit_data(123, 1704067200000);
+        let data = serialize_commit_data(123, &[9; SALT_LEN], 500_000);
         
         assert_eq!(data[0], slab_ix::COMMIT);
         assert_eq!(data.len(), COMMIT_IX_DATA_SIZE);
         
         let parsed_hold_id = u64::from_le_bytes(data[1..9].try_into().unwrap());
         assert_eq!(parsed_hold_id, 123);
+        assert_eq!(&data[9..25], &[9; SALT_LEN]);
+        assert_eq!(u64::from_le_bytes(data[25..33].try_into().unwrap()), 500_000);
     }
 
     #[test]
@@ -788,23 +872,28 @@ mod tests {
 
     #[test]
     fn test_commit_response_parsing() {
-        let mut data = [0u8; 64];
+        let mut data = [0u8; 80];
         data[0..8].copy_from_slice(&1_000_000u64.to_le_bytes());
         data[8..16].copy_from_slice(&50_000_000_000u64.to_le_bytes());
         data[16..32].copy_from_slice(&50_000_000_000_000u128.to_le_bytes());
         data[32..48].copy_from_slice(&50_000_000_000u128.to_le_bytes());
         data[48..64].copy_from_slice(&1_000_000_000i128.to_le_bytes());
+        data[64..72].copy_from_slice(&250_000u64.to_le_bytes());
+        data[72..80].copy_from_slice(&50_000u64.to_le_bytes());
         
         let response = CommitResponse::from_return_data(&data).unwrap();
         
         assert_eq!(response.filled_qty, 1_000_000);
         assert_eq!(response.notional, 50_000_000_000_000);
+        assert_eq!(response.released_qty, 250_000);
+        assert_eq!(response.shortfall_qty, 50_000);
+        assert!(CommitResponse::from_return_data(&data[..72]).is_err());
     }
 
     #[test]
     fn test_response_struct_sizes() {
         assert_eq!(core::mem::size_of::<ReserveResponse>(), 64);
-        assert_eq!(core::mem::size_of::<CommitResponse>(), 64);
+        assert_eq!(core::mem::size_of::<CommitResponse>(), 80);
         assert_eq!(core::mem::size_of::<LiquidationResponse>(), 48);
     }
 
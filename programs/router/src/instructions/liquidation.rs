@@ -253,7 +253,10 @@ fn calculate_position_pnl(qty: i64, mark_price: u64) -> i128 {
 /// * `registry` - Slab registry
 /// * `slab_accounts` - Slab account infos
 /// * `health_check` - Result of liquidation health check
-/// * `current_ts` - Current timestamp
+///
+/// Time comes from the Clock sysvar at whole-second resolution (the router
+/// keeps no clock anchor); each slab applies its own clock when it
+/// liquidates.
 ///
 /// # Returns
 /// * `LiquidationResult` with execution details
@@ -264,7 +267,6 @@ pub fn process_global_liquidation(
     registry: &SlabRegistry,
     _slab_accounts: &[AccountInfo],
     health_check: &LiquidationHealthCheck,
-    current_ts: u64,
 ) -> Result<LiquidationResult, PercolatorError> {
     // Validate
     if &portfolio.user != user {
@@ -277,6 +279,7 @@ pub fn process_global_liquidation(
         return Err(PercolatorError::PortfolioNotLiquidatable);
     }
     
+    let current_ts = current_ms(&mut ClockAnchor::default())?;
     let mut result = LiquidationResult::default();
     let mut remaining_deficit = health_check.deficit;
     
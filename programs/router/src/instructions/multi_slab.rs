@@ -229,7 +229,12 @@ pub fn process_multi_slab_reserve(
 /// * `vault` - Collateral vault (mutable)
 /// * `slab_accounts` - Array of slab account infos
 /// * `reservations` - Reservation info from reserve phase
-/// * `current_ts` - Current timestamp for expiry check
+///
+/// Expiry is checked against the Clock sysvar. The router keeps no clock
+/// anchor, so its time is the start of the current second and trails a
+/// slab's slot-interpolated time by less than a second: a reservation that
+/// expired under a second ago can pass this check. The slab checks expiry
+/// again against its own clock at commit and rejects it there.
 ///
 /// # Returns
 /// * Updated `MultiSlabResult` with commit results
@@ -240,7 +245,6 @@ pub fn process_multi_slab_commit(
     slab_accounts: &[AccountInfo],
     reservations: &[ReservationInfo],
     splits: &[SlabSplit],
-    current_ts: u64,
 ) -> Result<MultiSlabResult, PercolatorError> {
     // Validate inputs
     if reservations.is_empty() || reservations.len() > MAX_SLABS_PER_ORDER {
@@ -260,6 +264,7 @@ pub fn process_multi_slab_commit(
     }
 
     // Check all reservations haven't expired
+    let current_ts = current_ms(&mut ClockAnchor::default())?;
     for resv in reservations {
         if current_ts > resv.expiry_ms && resv.expiry_ms > 0 {
             msg!("Error: Reservation expired");
@@ -461,11 +466,10 @@ pub fn build_reserve_cpi_data(
 }
 
 /// Build CPI instruction data for slab commit
-pub fn build_commit_cpi_data(hold_id: u64, current_ts: u64) -> [u8; 17] {
-    let mut data = [0u8; 17];
+pub fn build_commit_cpi_data(hold_id: u64) -> [u8; 9] {
+    let mut data = [0u8; 9];
     data[0] = 1; // Commit discriminator
     data[1..9].copy_from_slice(&hold_id.to_le_bytes());
-    data[9..17].copy_from_slice(&current_ts.to_le_bytes());
     data
 }
 
@@ -538,9 +542,9 @@ mod tests {
 
     #[test]
     fn test_build_commit_cpi_data() {
-        let data = build_commit_cpi_data(123, 1704067200000);
+        let data = build_commit_cpi_data(123);
         assert_eq!(data[0], 1); // Commit discriminator
-        assert_eq!(data.len(), 17);
+        assert_eq!(data.len(), 9);
     }
 
     #[test]
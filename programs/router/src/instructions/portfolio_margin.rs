@@ -352,17 +352,20 @@ fn calculate_correlation_benefit(
 // ============================================================================
 
 /// Update portfolio marks and recalculate margin
+///
+/// `current_ts` is the caller's Clock sysvar time (ms).
 pub fn mark_to_market(
     portfolio: &mut Portfolio,
     mark_prices: &[(u16, u16, u64)], // (slab_idx, instrument_idx, mark_price)
     risk_params: &[InstrumentRiskParams],
+    current_ts: u64,
 ) -> PortfolioMarginResult {
     // Calculate new margin based on current marks
     let result = calculate_portfolio_margin(portfolio, risk_params, None);
     
     // Update portfolio
     portfolio.update_margin(result.net_im, result.net_mm);
-    portfolio.last_mark_ts = 0; // Would be current timestamp in production
+    portfolio.last_mark_ts = current_ts;
     
     let _ = mark_prices; // Used for PnL calculation in production
     
@@ -1,7 +1,7 @@
 //! Slab registry for governance and validation
 
 use pinocchio::pubkey::Pubkey;
-use percolator_common::MAX_SLABS;
+use percolator_common::{load_oracle_price, OracleLimits, PercolatorError, MAX_SLABS};
 
 /// Slab registration entry
 #[repr(C)]
@@ -33,6 +33,26 @@ pub struct SlabEntry {
     pub _padding: [u8; 7],
 }
 
+impl SlabEntry {
+    /// Read a mark price from an oracle price account
+    ///
+    /// The account must be owned by the oracle program registered for the
+    /// slab, and the price must pass the default staleness and confidence
+    /// limits.
+    pub fn read_oracle_price(
+        &self,
+        oracle_owner: &Pubkey,
+        oracle_data: &[u8],
+        now_ms: u64,
+    ) -> Result<u64, PercolatorError> {
+        if self.oracle_id == Pubkey::default() || *oracle_owner != self.oracle_id {
+            return Err(PercolatorError::InvalidOracle);
+        }
+
+        load_oracle_price(oracle_owner, oracle_data, now_ms, &OracleLimits::default()).map(|p| p.price)
+    }
+}
+
 /// Slab registry account
 /// PDA: ["registry", router_id]
 #[repr(C)]
@@ -261,4 +281,25 @@ mod tests {
         registry.deactivate_slab(&slab_id).unwrap();
         assert!(registry.find_slab(&slab_id).is_none());
     }
+
+    #[test]
+    fn test_read_oracle_price() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        let oracle_program = Pubkey::from([9; 32]);
+        let idx = registry
+            .register_slab(Pubkey::from([1; 32]), [0; 32], oracle_program, 500, 250, 10, 20, 1000, 1_000_000, 0)
+            .unwrap();
+        let entry = registry.get_slab_by_index(idx).unwrap();
+        let data = percolator_common::TestOracle::new(50_000_000_000, 0, -6, 1_000).to_bytes();
+
+        assert_eq!(entry.read_oracle_price(&oracle_program, &data, 2_000), Ok(50_000_000_000));
+        assert_eq!(
+            entry.read_oracle_price(&Pubkey::from([8; 32]), &data, 2_000),
+            Err(PercolatorError::InvalidOracle)
+        );
+        assert_eq!(
+            entry.read_oracle_price(&oracle_program, &data, 1_000_000),
+            Err(PercolatorError::OracleStale)
+        );
+    }
 }
@@ -2,6 +2,7 @@
 
 use pinocchio::{
     account_info::AccountInfo,
+    cpi::set_return_data,
     entrypoint,
     msg,
     pubkey::Pubkey,
@@ -13,14 +14,30 @@ use crate::instructions::{
     process_reserve, 
     process_commit, 
     process_cancel, 
+    process_cancel_order,
     process_batch_open, 
     process_initialize_slab,
     process_add_instrument,
     process_update_funding,
+    process_settle_funding,
     process_liquidation,
+    process_place_order,
+    process_update_param,
+    process_amend_order,
+    process_mass_quote,
+    process_add_dlp,
+    process_remove_dlp,
+    process_set_oracle,
+    process_update_mark,
+    process_sweep_expired,
+    OrderParams,
+    SlabParam,
+    TakerOptions,
+    QuoteLevel,
+    MAX_QUOTE_LEVELS,
 };
 use crate::state::SlabState;
-use percolator_common::{PercolatorError, validate_owner, validate_writable, borrow_account_data_mut, InstructionReader};
+use percolator_common::{PercolatorError, SelfTradePrevention, Side, SALT_LEN, TimeInForce, validate_owner, validate_signer, validate_writable, borrow_account_data_mut, current_ms, read_oracle_price, InstructionReader};
 
 entrypoint!(process_instruction);
 
@@ -46,6 +63,17 @@ pub fn process_instruction(
         5 => SlabInstruction::AddInstrument,
         6 => SlabInstruction::UpdateFunding,
         7 => SlabInstruction::Liquidation,
+        14 => SlabInstruction::PlaceOrder,
+        15 => SlabInstruction::UpdateParam,
+        16 => SlabInstruction::AmendOrder,
+        17 => SlabInstruction::MassQuote,
+        18 => SlabInstruction::AddDlp,
+        19 => SlabInstruction::RemoveDlp,
+        20 => SlabInstruction::SetOracle,
+        21 => SlabInstruction::UpdateMark,
+        22 => SlabInstruction::SettleFunding,
+        23 => SlabInstruction::SweepExpired,
+        24 => SlabInstruction::CancelOrder,
         _ => {
             msg!("Error: Unknown instruction");
             return Err(PercolatorError::InvalidInstruction.into());
@@ -86,19 +114,94 @@ pub fn process_instruction(
             msg!("Instruction: Liquidation");
             process_liquidation_inner(program_id, accounts, &instruction_data[1..])
         }
+        SlabInstruction::PlaceOrder => {
+            msg!("Instruction: PlaceOrder");
+            process_place_order_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::UpdateParam => {
+            msg!("Instruction: UpdateParam");
+            process_update_param_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::AmendOrder => {
+            msg!("Instruction: AmendOrder");
+            process_amend_order_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::MassQuote => {
+            msg!("Instruction: MassQuote");
+            process_mass_quote_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::AddDlp => {
+            msg!("Instruction: AddDlp");
+            process_dlp_registry_inner(program_id, accounts, &instruction_data[1..], true)
+        }
+        SlabInstruction::RemoveDlp => {
+            msg!("Instruction: RemoveDlp");
+            process_dlp_registry_inner(program_id, accounts, &instruction_data[1..], false)
+        }
+        SlabInstruction::SetOracle => {
+            msg!("Instruction: SetOracle");
+            process_set_oracle_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::UpdateMark => {
+            msg!("Instruction: UpdateMark");
+            process_update_mark_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::SettleFunding => {
+            msg!("Instruction: SettleFunding");
+            process_settle_funding_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::SweepExpired => {
+            msg!("Instruction: SweepExpired");
+            process_sweep_expired_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::CancelOrder => {
+            msg!("Instruction: CancelOrder");
+            process_cancel_order_inner(program_id, accounts, &instruction_data[1..])
+        }
     }
 }
 
 // Instruction processors with account validation
+//
+// Time always comes from the Clock sysvar (see `current_ms`), never from
+// instruction data.
+
+/// Require `authority` to sign for `account_idx`: either the router
+/// authority or the owner of the account itself
+fn validate_account_authority(slab: &SlabState, authority: &AccountInfo, account_idx: u32) -> ProgramResult {
+    validate_signer(authority)?;
+    if *authority.key() == slab.header.router_id {
+        return Ok(());
+    }
+    match slab.get_account(account_idx) {
+        Some(account) if account.key == *authority.key() => Ok(()),
+        _ => {
+            msg!("Error: Signer is not the account owner or router authority");
+            Err(PercolatorError::Unauthorized.into())
+        }
+    }
+}
+
+/// Require `authority` to sign for the account that holds reservation `hold_id`
+fn validate_hold_authority(slab: &SlabState, authority: &AccountInfo, hold_id: u64) -> ProgramResult {
+    let account_idx = slab
+        .find_reservation_by_hold_id(hold_id)
+        .and_then(|idx| slab.get_reservation(idx))
+        .map(|resv| resv.account_idx)
+        .ok_or_else(|| {
+            msg!("Error: Reservation not found");
+            PercolatorError::ReservationNotFound
+        })?;
+    validate_account_authority(slab, authority, account_idx)
+}
 
 /// Process reserve instruction
 ///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` User account
-/// 2. `[]` Router program (for CPI validation)
+/// 1. `[signer]` Account owner or router authority
 ///
-/// Expected data layout (78 bytes):
+/// Expected data layout (71 bytes, 74 with optional fields):
 /// - account_idx: u32 (4 bytes)
 /// - instrument_idx: u16 (2 bytes)
 /// - side: u8 (1 byte)
@@ -107,10 +210,13 @@ pub fn process_instruction(
 /// - ttl_ms: u64 (8 bytes)
 /// - commitment_hash: [u8; 32] (32 bytes)
 /// - route_id: u64 (8 bytes)
+/// - tif: u8 (1 byte, optional, defaults to IOC)
+/// - flags: u8 (1 byte, optional, defaults to 0)
+/// - stp: u8 (1 byte, optional, defaults to the slab's mode)
 fn process_reserve_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     // Validate account count
-    if accounts.len() < 1 {
-        msg!("Error: Reserve instruction requires at least 1 account");
+    if accounts.len() < 2 {
+        msg!("Error: Reserve instruction requires at least 2 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
@@ -133,6 +239,20 @@ fn process_reserve_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[
     let ttl_ms = reader.read_u64()?;
     let commitment_hash = reader.read_bytes::<32>()?;
     let route_id = reader.read_u64()?;
+    let tif = if reader.remaining() > 0 {
+        reader.read_time_in_force()?
+    } else {
+        TimeInForce::IOC
+    };
+    let flags = if reader.remaining() > 0 { reader.read_u8()? } else { 0 };
+    let stp = if reader.remaining() > 0 {
+        reader.read_self_trade_prevention()?
+    } else {
+        SelfTradePrevention::SlabDefault
+    };
+    let opts = TakerOptions { tif, flags, stp };
+    validate_account_authority(slab, &accounts[1], account_idx)?;
+    let current_ts = current_ms(&mut slab.header.clock)?;
 
     // Call the instruction handler
     let _result = process_reserve(
@@ -145,6 +265,8 @@ fn process_reserve_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[
         ttl_ms,
         commitment_hash,
         route_id,
+        &opts,
+        current_ts,
     )?;
 
     msg!("Reserve processed successfully");
@@ -155,14 +277,17 @@ fn process_reserve_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[
 ///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` User account
+/// 1. `[signer]` Reservation's account owner or router authority
 ///
-/// Expected data layout (16 bytes):
+/// Returns the `CommitResult` (80 bytes) as return data.
+///
+/// Expected data layout (8 bytes, 24 with the optional salt, 32 with the optional qty):
 /// - hold_id: u64 (8 bytes)
-/// - current_ts: u64 (8 bytes)
+/// - salt: [u8; 16] (16 bytes, optional, reveals the reservation's commitment)
+/// - qty: u64 (8 bytes, optional, fills part of the reservation; 0 or absent fills all)
 fn process_commit_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: Commit instruction requires at least 1 account");
+    if accounts.len() < 2 {
+        msg!("Error: Commit instruction requires at least 2 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
@@ -175,10 +300,18 @@ fn process_commit_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u
     // Parse instruction data
     let mut reader = InstructionReader::new(data);
     let hold_id = reader.read_u64()?;
-    let current_ts = reader.read_u64()?;
+    let salt = if reader.remaining() > 0 {
+        reader.read_bytes::<SALT_LEN>()?
+    } else {
+        [0; SALT_LEN]
+    };
+    let target_qty = if reader.remaining() > 0 { reader.read_u64()? } else { 0 };
+    validate_hold_authority(slab, &accounts[1], hold_id)?;
+    let current_ts = current_ms(&mut slab.header.clock)?;
 
     // Call the instruction handler
-    let _result = process_commit(slab, hold_id, current_ts)?;
+    let result = process_commit(slab, hold_id, &salt, target_qty, current_ts)?;
+    set_return_data(&result.to_bytes());
 
     msg!("Commit processed successfully");
     Ok(())
@@ -188,13 +321,13 @@ fn process_commit_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u
 ///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` User account
+/// 1. `[signer]` Reservation's account owner or router authority
 ///
 /// Expected data layout (8 bytes):
 /// - hold_id: u64 (8 bytes)
 fn process_cancel_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: Cancel instruction requires at least 1 account");
+    if accounts.len() < 2 {
+        msg!("Error: Cancel instruction requires at least 2 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
@@ -207,6 +340,7 @@ fn process_cancel_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u
     // Parse instruction data
     let mut reader = InstructionReader::new(data);
     let hold_id = reader.read_u64()?;
+    validate_hold_authority(slab, &accounts[1], hold_id)?;
 
     // Call the instruction handler
     process_cancel(slab, hold_id)?;
@@ -215,15 +349,55 @@ fn process_cancel_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u
     Ok(())
 }
 
+/// Process cancel order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Order owner
+///
+/// Expected data layout (4 bytes):
+/// - order_idx: u32 (4 bytes)
+fn process_cancel_order_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: CancelOrder instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let order_idx = reader.read_u32()?;
+    let current_ts = current_ms(&mut slab.header.clock)?;
+
+    // An owner without an account slot has no orders to cancel
+    let account_idx = slab.find_account(owner.key()).ok_or_else(|| {
+        msg!("Error: Not authorized to cancel this order");
+        PercolatorError::Unauthorized
+    })?;
+
+    // Call the instruction handler
+    process_cancel_order(slab, order_idx, account_idx, current_ts)?;
+
+    msg!("CancelOrder processed successfully");
+    Ok(())
+}
+
 /// Process batch open instruction
 ///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
 /// 1. `[signer]` Authority account (for permissioned batch opening)
 ///
-/// Expected data layout (10 bytes):
+/// Expected data layout (2 bytes):
 /// - instrument_idx: u16 (2 bytes)
-/// - current_ts: u64 (8 bytes)
 fn process_batch_open_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     if accounts.len() < 1 {
         msg!("Error: BatchOpen instruction requires at least 1 account");
@@ -239,7 +413,7 @@ fn process_batch_open_inner(program_id: &Pubkey, accounts: &[AccountInfo], data:
     // Parse instruction data
     let mut reader = InstructionReader::new(data);
     let instrument_idx = reader.read_u16()?;
-    let current_ts = reader.read_u64()?;
+    let current_ts = current_ms(&mut slab.header.clock)?;
 
     // Call the instruction handler
     process_batch_open(slab, instrument_idx, current_ts)?;
@@ -346,16 +520,16 @@ fn process_add_instrument_inner(program_id: &Pubkey, accounts: &[AccountInfo], d
 
 /// Process update funding instruction
 ///
+/// The rate comes from the premium sampled at batch opens, so no price is
+/// passed in.
+///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` Oracle/Authority
 ///
-/// Expected data layout (18 bytes):
+/// Expected data layout (2 bytes):
 /// - instrument_idx: u16 (2 bytes)
-/// - index_price: u64 (8 bytes)
-/// - current_ts: u64 (8 bytes)
 fn process_update_funding_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
+    if accounts.is_empty() {
         msg!("Error: UpdateFunding instruction requires at least 1 account");
         return Err(PercolatorError::InvalidInstruction.into());
     }
@@ -369,26 +543,59 @@ fn process_update_funding_inner(program_id: &Pubkey, accounts: &[AccountInfo], d
     // Parse instruction data
     let mut reader = InstructionReader::new(data);
     let instrument_idx = reader.read_u16()?;
-    let index_price = reader.read_u64()?;
-    let current_ts = reader.read_u64()?;
+    let current_ts = current_ms(&mut slab.header.clock)?;
 
     // Call the instruction handler
-    process_update_funding(slab, instrument_idx, index_price, current_ts)?;
+    process_update_funding(slab, instrument_idx, current_ts)?;
 
     msg!("UpdateFunding processed successfully");
     Ok(())
 }
 
+/// Process settle funding instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+///
+/// Expected data layout (4 bytes):
+/// - account_idx: u32 (4 bytes)
+fn process_settle_funding_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.is_empty() {
+        msg!("Error: SettleFunding instruction requires at least 1 account");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let account_idx = reader.read_u32()?;
+
+    // Call the instruction handler
+    process_settle_funding(slab, account_idx)?;
+
+    msg!("SettleFunding processed successfully");
+    Ok(())
+}
+
 /// Process liquidation instruction
 ///
+/// Marks of instruments bound to the supplied oracle accounts are refreshed
+/// before the account is liquidated. Anyone may liquidate; `deficit_target`
+/// is only honoured when the router authority signs.
+///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` Router authority
+/// 1. `[signer]` Router authority or liquidator
+/// 2.. `[]` Oracle price accounts (optional)
 ///
 /// Expected data layout (20 bytes):
 /// - account_idx: u32 (4 bytes)
 /// - deficit_target: i128 (16 bytes) - stored as bytes
-/// - current_ts: u64 (8 bytes)
 fn process_liquidation_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     if accounts.len() < 1 {
         msg!("Error: Liquidation instruction requires at least 1 account");
@@ -407,9 +614,26 @@ fn process_liquidation_inner(program_id: &Pubkey, accounts: &[AccountInfo], data
     
     // Read deficit_target as i128 (16 bytes)
     let deficit_bytes = reader.read_bytes::<16>()?;
-    let deficit_target = i128::from_le_bytes(deficit_bytes);
+    let router_signed = accounts
+        .get(1)
+        .is_some_and(|authority| authority.is_signer() && *authority.key() == slab.header.router_id);
+    let deficit_target = if router_signed {
+        i128::from_le_bytes(deficit_bytes)
+    } else {
+        0
+    };
     
-    let current_ts = reader.read_u64()?;
+    let current_ts = current_ms(&mut slab.header.clock)?;
+
+    // Refresh marks from the supplied oracles
+    for oracle_account in accounts.iter().skip(2) {
+        let oracle_data = oracle_account.try_borrow_data()?;
+        for instrument_idx in 0..slab.header.instrument_count {
+            if slab.instruments[instrument_idx as usize].oracle == *oracle_account.key() {
+                process_update_mark(slab, instrument_idx, oracle_account.key(), oracle_account.owner(), &oracle_data, current_ts)?;
+            }
+        }
+    }
 
     // Call the instruction handler
     let result = process_liquidation(slab, account_idx, deficit_target, current_ts)?;
@@ -417,3 +641,396 @@ fn process_liquidation_inner(program_id: &Pubkey, accounts: &[AccountInfo], data
     msg!("Liquidation processed successfully");
     Ok(())
 }
+
+/// Process place order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Order owner
+///
+/// Expected data layout (22 bytes):
+/// - instrument_idx: u16 (2 bytes)
+/// - side: u8 (1 byte)
+/// - price: u64 (8 bytes)
+/// - qty: u64 (8 bytes)
+/// - tif: u8 (1 byte)
+/// - maker_class: u8 (1 byte)
+/// - flags: u8 (1 byte)
+fn process_place_order_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: PlaceOrder instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let params = OrderParams {
+        instrument_idx: reader.read_u16()?,
+        side: reader.read_side()?,
+        price: reader.read_u64()?,
+        qty: reader.read_u64()?,
+        tif: reader.read_time_in_force()?,
+        maker_class: reader.read_maker_class()?,
+        flags: reader.read_u8()?,
+    };
+    let current_ts = current_ms(&mut slab.header.clock)?;
+
+    // Resolve the owner's account slot
+    let account_idx = slab.get_or_create_account(owner.key()).ok_or_else(|| {
+        msg!("Error: Account pool full");
+        PercolatorError::PoolFull
+    })?;
+
+    // Call the instruction handler
+    let _result = process_place_order(slab, account_idx, &params, current_ts)?;
+
+    msg!("PlaceOrder processed successfully");
+    Ok(())
+}
+
+/// Process update param instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+///
+/// Expected data layout (9 bytes):
+/// - param: u8 (1 byte)
+/// - value: u64 (8 bytes)
+fn process_update_param_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: UpdateParam instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let authority = &accounts[1];
+    validate_signer(authority)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let param = SlabParam::try_from(reader.read_u8()?)?;
+    let value = reader.read_u64()?;
+
+    // Call the instruction handler
+    process_update_param(slab, authority.key(), param, value)?;
+
+    msg!("UpdateParam processed successfully");
+    Ok(())
+}
+
+/// Process amend order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Order owner
+///
+/// Expected data layout (20 bytes):
+/// - order_idx: u32 (4 bytes)
+/// - new_price: u64 (8 bytes)
+/// - new_qty: u64 (8 bytes)
+fn process_amend_order_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: AmendOrder instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let order_idx = reader.read_u32()?;
+    let new_price = reader.read_u64()?;
+    let new_qty = reader.read_u64()?;
+    let current_ts = current_ms(&mut slab.header.clock)?;
+
+    // Resolve the owner's account slot
+    let account_idx = slab.get_or_create_account(owner.key()).ok_or_else(|| {
+        msg!("Error: Account pool full");
+        PercolatorError::PoolFull
+    })?;
+
+    // Call the instruction handler
+    let _result = process_amend_order(slab, account_idx, order_idx, new_price, new_qty, current_ts)?;
+
+    msg!("AmendOrder processed successfully");
+    Ok(())
+}
+
+/// Process mass quote instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Maker
+///
+/// Expected data layout (4 + 18 * level_count bytes):
+/// - instrument_idx: u16 (2 bytes)
+/// - maker_class: u8 (1 byte)
+/// - level_count: u8 (1 byte, at most MAX_QUOTE_LEVELS)
+/// - per level: side u8, price u64, qty u64, flags u8 (18 bytes)
+///
+/// Return data: cancelled u32, trimmed u32, posted u32, then per level a
+/// posted flag u8 and error code u32 (0 when posted).
+fn process_mass_quote_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: MassQuote instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let instrument_idx = reader.read_u16()?;
+    let maker_class = reader.read_maker_class()?;
+    let level_count = reader.read_u8()? as usize;
+    let current_ts = current_ms(&mut slab.header.clock)?;
+
+    if level_count > MAX_QUOTE_LEVELS {
+        msg!("Error: Too many quote levels");
+        return Err(PercolatorError::QuoteLevelLimitExceeded.into());
+    }
+
+    let mut levels = [QuoteLevel { side: Side::Buy, price: 0, qty: 0, flags: 0 }; MAX_QUOTE_LEVELS];
+    for level in levels.iter_mut().take(level_count) {
+        level.side = reader.read_side()?;
+        level.price = reader.read_u64()?;
+        level.qty = reader.read_u64()?;
+        level.flags = reader.read_u8()?;
+    }
+
+    // Resolve the owner's account slot
+    let account_idx = slab.get_or_create_account(owner.key()).ok_or_else(|| {
+        msg!("Error: Account pool full");
+        PercolatorError::PoolFull
+    })?;
+
+    // Call the instruction handler
+    let result = process_mass_quote(
+        slab,
+        account_idx,
+        instrument_idx,
+        maker_class,
+        &levels[..level_count],
+        current_ts,
+    )?;
+
+    // Report per-level outcomes to the caller
+    let mut out = [0u8; 12 + 5 * MAX_QUOTE_LEVELS];
+    out[0..4].copy_from_slice(&result.cancelled.to_le_bytes());
+    out[4..8].copy_from_slice(&result.trimmed.to_le_bytes());
+    out[8..12].copy_from_slice(&result.posted.to_le_bytes());
+    for (i, outcome) in result.level_errors.iter().take(level_count).enumerate() {
+        let at = 12 + 5 * i;
+        match outcome {
+            None => out[at] = 1,
+            Some(e) => out[at + 1..at + 5].copy_from_slice(&(*e as u32).to_le_bytes()),
+        }
+    }
+    set_return_data(&out[..12 + 5 * level_count]);
+
+    msg!("MassQuote processed successfully");
+    Ok(())
+}
+
+/// Process add / remove DLP instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+///
+/// Expected data layout (32 bytes):
+/// - dlp: Pubkey (32 bytes)
+fn process_dlp_registry_inner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+    add: bool,
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: DLP registry instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let authority = &accounts[1];
+    validate_signer(authority)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let dlp = reader.read_bytes::<32>()?;
+
+    // Call the instruction handler
+    if add {
+        process_add_dlp(slab, authority.key(), &dlp)?;
+        msg!("AddDlp processed successfully");
+    } else {
+        process_remove_dlp(slab, authority.key(), &dlp)?;
+        msg!("RemoveDlp processed successfully");
+    }
+    Ok(())
+}
+
+/// Process set oracle instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+/// 2. `[]` Oracle price account
+///
+/// Expected data layout (2 bytes):
+/// - instrument_idx: u16 (2 bytes)
+fn process_set_oracle_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: SetOracle instruction requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let authority = &accounts[1];
+    validate_signer(authority)?;
+
+    let oracle_account = &accounts[2];
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let instrument_idx = reader.read_u16()?;
+
+    // The account must already hold a readable price from its oracle program
+    read_oracle_price(oracle_account.owner(), &oracle_account.try_borrow_data()?)?;
+
+    // Call the instruction handler
+    process_set_oracle(slab, authority.key(), instrument_idx, oracle_account.key(), oracle_account.owner())?;
+
+    msg!("SetOracle processed successfully");
+    Ok(())
+}
+
+/// Process update mark instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[]` Instrument's oracle price account
+///
+/// Expected data layout (2 bytes):
+/// - instrument_idx: u16 (2 bytes)
+fn process_update_mark_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: UpdateMark instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let oracle_account = &accounts[1];
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let instrument_idx = reader.read_u16()?;
+    let current_ts = current_ms(&mut slab.header.clock)?;
+
+    // Call the instruction handler
+    process_update_mark(
+        slab,
+        instrument_idx,
+        oracle_account.key(),
+        oracle_account.owner(),
+        &oracle_account.try_borrow_data()?,
+        current_ts,
+    )?;
+
+    msg!("UpdateMark processed successfully");
+    Ok(())
+}
+
+/// Process sweep expired instruction
+///
+/// Anyone may crank it; the keeper's account is credited with the sweep
+/// reward. Returns the number of reservations released (u32) and the reward
+/// paid (u128) as return data.
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Keeper
+///
+/// Expected data layout (1 byte):
+/// - max_sweep: u8 (1 byte, at most MAX_SWEEP)
+fn process_sweep_expired_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: SweepExpired instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    let keeper = &accounts[1];
+    validate_signer(keeper)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    // Parse instruction data
+    let mut reader = InstructionReader::new(data);
+    let max_sweep = reader.read_u8()? as u32;
+    let current_ts = current_ms(&mut slab.header.clock)?;
+
+    // Resolve the keeper's account slot
+    let keeper_idx = slab.get_or_create_account(keeper.key()).ok_or_else(|| {
+        msg!("Error: Account pool full");
+        PercolatorError::PoolFull
+    })?;
+
+    // Call the instruction handler
+    let result = process_sweep_expired(slab, keeper_idx, max_sweep, current_ts)?;
+
+    let mut out = [0u8; 20];
+    out[..4].copy_from_slice(&result.swept.to_le_bytes());
+    out[4..].copy_from_slice(&result.reward.to_le_bytes());
+    set_return_data(&out);
+
+    msg!("SweepExpired processed successfully");
+    Ok(())
+}
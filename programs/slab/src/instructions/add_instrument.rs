@@ -4,7 +4,7 @@
 
 use crate::state::SlabState;
 use percolator_common::*;
-use pinocchio::msg;
+use pinocchio::{msg, pubkey::Pubkey};
 
 /// Process add instrument instruction
 ///
@@ -67,27 +67,41 @@ pub fn process_add_instrument(
         tick,
         lot,
         index_price: initial_mark, // Use initial mark as index initially
+        mark_px: initial_mark as i64,
+        prev_mark_px: 0,
+        mark_ts: 0,
+        basis_ema: 0,
+        basis_ts: 0,
+        oracle: Pubkey::default(),
+        oracle_program: Pubkey::default(),
+        kill_band_bps: slab.header.kill_band_bps,
         funding_rate: 0,
         cum_funding: 0,
         last_funding_ts: 0,
+        premium_acc: 0,
+        premium_acc_ms: 0,
+        premium_sample_ts: 0,
+        premium_last_bps: 0,
         bids_head: SlabState::INVALID_INDEX,
         asks_head: SlabState::INVALID_INDEX,
         bids_pending_head: SlabState::INVALID_INDEX,
         asks_pending_head: SlabState::INVALID_INDEX,
+        bids_root: SlabState::INVALID_INDEX,
+        asks_root: SlabState::INVALID_INDEX,
+        bids_pending_root: SlabState::INVALID_INDEX,
+        asks_pending_root: SlabState::INVALID_INDEX,
         epoch: 0,
         index: idx,
+        aggressor_head: SlabState::INVALID_INDEX,
         batch_open_ms: 0,
         freeze_until_ms: 0,
+        frozen_bid_px: 0,
+        frozen_ask_px: 0,
     };
 
     // Update count
     slab.header.instrument_count = idx + 1;
 
-    // Update mark price if this is the first instrument
-    if slab.header.mark_px == 0 {
-        slab.header.mark_px = initial_mark as i64;
-    }
-
     // Increment seqno
     slab.header.increment_seqno();
 
@@ -102,12 +116,19 @@ pub fn process_add_instrument(
 /// * `instrument_idx` - Instrument to update
 /// * `tick` - New tick size (or 0 to keep current)
 /// * `lot` - New lot size (or 0 to keep current)
+/// * `kill_band_bps` - New kill band in basis points (`None` to keep current; 0 is a valid band)
 pub fn process_update_instrument(
     slab: &mut SlabState,
     instrument_idx: u16,
     tick: u64,
     lot: u64,
+    kill_band_bps: Option<u64>,
 ) -> Result<(), PercolatorError> {
+    if kill_band_bps.is_some_and(|bps| bps > 10_000) {
+        msg!("Error: Kill band cannot exceed 10,000 bps");
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+
     let instr = slab.get_instrument_mut(instrument_idx)
         .ok_or_else(|| {
             msg!("Error: Invalid instrument index");
@@ -126,6 +147,11 @@ pub fn process_update_instrument(
         instr.lot = lot;
     }
 
+    // Update kill band if specified
+    if let Some(bps) = kill_band_bps {
+        instr.kill_band_bps = bps;
+    }
+
     slab.header.increment_seqno();
 
     Ok(())
@@ -137,18 +163,21 @@ pub fn process_update_instrument(
 /// * `slab` - The slab state
 /// * `instrument_idx` - Instrument to update
 /// * `new_mark_px` - New mark price (1e6 scale)
+/// * `oracle_ts` - Oracle timestamp of the price (ms)
 pub fn process_update_mark_price(
     slab: &mut SlabState,
     instrument_idx: u16,
     new_mark_px: u64,
+    oracle_ts: u64,
 ) -> Result<(), PercolatorError> {
-    if slab.get_instrument(instrument_idx).is_none() {
-        msg!("Error: Invalid instrument index");
-        return Err(PercolatorError::InvalidInstrument);
-    }
+    let instr = slab.get_instrument_mut(instrument_idx)
+        .ok_or_else(|| {
+            msg!("Error: Invalid instrument index");
+            PercolatorError::InvalidInstrument
+        })?;
 
-    // Update header mark price (used for margin calculations)
-    slab.header.update_mark_px(new_mark_px as i64);
+    // Each instrument keeps its own mark (used for margin, funding and liquidation)
+    instr.update_mark_px(new_mark_px as i64, oracle_ts);
 
     slab.header.increment_seqno();
 
@@ -180,6 +209,8 @@ pub struct InstrumentSummary {
     pub lot: u64,
     /// Current index price
     pub index_price: u64,
+    /// Current mark price
+    pub mark_px: i64,
     /// Current funding rate
     pub funding_rate: i64,
     /// Has bids
@@ -199,6 +230,7 @@ pub fn get_instrument_summary(slab: &SlabState, instrument_idx: u16) -> Option<I
         tick: instr.tick,
         lot: instr.lot,
         index_price: instr.index_price,
+        mark_px: instr.mark_px,
         funding_rate: instr.funding_rate,
         has_bids: instr.bids_head != SlabState::INVALID_INDEX,
         has_asks: instr.asks_head != SlabState::INVALID_INDEX,
@@ -208,6 +240,7 @@ pub fn get_instrument_summary(slab: &SlabState, instrument_idx: u16) -> Option<I
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::new_test_slab;
 
     #[test]
     fn test_instrument_summary_size() {
@@ -220,4 +253,41 @@ mod tests {
         let symbol = *b"BTC-PERP";
         assert_eq!(symbol.len(), 8);
     }
+
+    #[test]
+    fn test_mark_price_is_per_instrument() {
+        let mut slab = new_test_slab();
+        let eth = process_add_instrument(&mut slab, *b"ETH-PERP", 1_000_000, 1_000, 1_000, 3_000_000_000).unwrap();
+        assert_eq!(slab.instruments[eth as usize].mark_px, 3_000_000_000);
+        assert_eq!(slab.instruments[eth as usize].kill_band_bps, slab.header.kill_band_bps);
+
+        process_update_mark_price(&mut slab, eth, 3_100_000_000, 1_000).unwrap();
+        assert_eq!(slab.instruments[eth as usize].mark_px, 3_100_000_000);
+        assert_eq!(slab.instruments[eth as usize].prev_mark_px, 3_000_000_000);
+        assert_eq!(slab.instruments[eth as usize].mark_ts, 1_000);
+
+        // BTC keeps its own mark
+        assert_eq!(slab.instruments[0].mark_px, 50_000_000_000);
+        assert_eq!(slab.instruments[0].prev_mark_px, 0);
+    }
+
+    #[test]
+    fn test_update_instrument_kill_band() {
+        let mut slab = new_test_slab();
+
+        process_update_instrument(&mut slab, 0, 0, 0, Some(250)).unwrap();
+        assert_eq!(slab.instruments[0].kill_band_bps, 250);
+        assert_eq!(slab.instruments[0].tick, 1_000_000);
+
+        // None keeps the band, a zero band is applied
+        process_update_instrument(&mut slab, 0, 0, 0, None).unwrap();
+        assert_eq!(slab.instruments[0].kill_band_bps, 250);
+        process_update_instrument(&mut slab, 0, 0, 0, Some(0)).unwrap();
+        assert_eq!(slab.instruments[0].kill_band_bps, 0);
+
+        assert_eq!(
+            process_update_instrument(&mut slab, 0, 0, 0, Some(10_001)).unwrap_err(),
+            PercolatorError::InvalidRiskParams
+        );
+    }
 }
@@ -0,0 +1,350 @@
+//! Amend order instruction - modify a resting maker order
+//!
+//! A pure size reduction is applied in place and keeps the order's time
+//! priority. A price change or size increase re-inserts the order with a new
+//! order ID, exactly as a cancel followed by a fresh place would. An order
+//! with reserved quantity keeps its ID so the reserved slices still find it,
+//! and cannot grow if the added size would have to wait in the pending queue.
+
+use super::batch_open::{is_order_frozen, jumps_frozen_levels};
+use super::commit::reducible_qty;
+use super::place_order::check_not_crossing;
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::msg;
+
+/// Result of an amend order operation
+#[derive(Debug, Clone, Copy)]
+pub struct AmendOrderResult {
+    /// Order ID after the amend (unchanged when priority was kept or the order is reserved)
+    pub order_id: u64,
+    /// Whether the order kept its place in the queue
+    pub priority_kept: bool,
+    /// State of the order after the amend
+    pub state: OrderState,
+}
+
+/// Process amend order instruction
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `account_idx` - Account index of the maker (must own the order)
+/// * `order_idx` - Index of the order in the order pool
+/// * `new_price` - New limit price (1e6 scale)
+/// * `new_qty` - New total quantity (1e6 scale), including any reserved quantity
+/// * `current_ts` - Current timestamp (ms)
+///
+/// # Returns
+/// * `AmendOrderResult` with the order's ID and whether priority was kept
+pub fn process_amend_order(
+    slab: &mut SlabState,
+    account_idx: u32,
+    order_idx: u32,
+    new_price: u64,
+    new_qty: u64,
+    current_ts: u64,
+) -> Result<AmendOrderResult, PercolatorError> {
+    let order = *slab.get_order(order_idx).ok_or_else(|| {
+        msg!("Error: Order not found");
+        PercolatorError::OrderNotFound
+    })?;
+
+    if order.account_idx != account_idx {
+        msg!("Error: Not authorized to amend this order");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    if is_order_frozen(&order, current_ts) {
+        msg!("Error: Order is frozen");
+        return Err(PercolatorError::OrderFrozen);
+    }
+
+    let instr = slab.get_instrument(order.instrument_idx).ok_or_else(|| {
+        msg!("Error: Invalid instrument index");
+        PercolatorError::InvalidInstrument
+    })?;
+
+    // Validate price
+    if new_price == 0 {
+        msg!("Error: Price must be positive");
+        return Err(PercolatorError::InvalidPrice);
+    }
+
+    if !is_tick_aligned(new_price, instr.tick) {
+        msg!("Error: Price not aligned to tick size");
+        return Err(PercolatorError::PriceNotAligned);
+    }
+
+    // Validate quantity
+    if new_qty == 0 {
+        msg!("Error: Quantity must be positive");
+        return Err(PercolatorError::InvalidQuantity);
+    }
+
+    if !is_lot_aligned(new_qty, instr.lot) {
+        msg!("Error: Quantity not aligned to lot size");
+        return Err(PercolatorError::QuantityNotAligned);
+    }
+
+    // Reserved quantity is promised to a pending commit
+    if new_qty < order.reserved_qty {
+        msg!("Error: Cannot amend below reserved quantity");
+        return Err(PercolatorError::ReservedQtyExceeded);
+    }
+
+    // Size reduction: keep the order where it is
+    if new_price == order.price && new_qty <= order.qty {
+        slab.set_order_qty(order_idx, new_qty, order.reserved_qty);
+        slab.header.increment_seqno();
+        slab.refresh_quote_cache(order.instrument_idx);
+
+        return Ok(AmendOrderResult {
+            order_id: order.order_id,
+            priority_kept: true,
+            state: order.state,
+        });
+    }
+
+    // Commit fills at the order's price, so a reserved order cannot move
+    if new_price != order.price && order.reserved_qty > 0 {
+        msg!("Error: Cannot reprice an order with reserved quantity");
+        return Err(PercolatorError::ReservedQtyExceeded);
+    }
+
+    check_not_crossing(slab, order.instrument_idx, order.side, new_price, order.flags)?;
+
+    if order.flags & ORDER_FLAG_REDUCE_ONLY != 0
+        && new_qty > reducible_qty(slab, account_idx, order.instrument_idx, order.side)
+    {
+        msg!("Error: Reduce-only order would increase position");
+        return Err(PercolatorError::ReduceOnlyViolation);
+    }
+
+    // A maker dropped from the DLP registry re-enters as a regular order
+    let is_dlp = slab
+        .get_account(account_idx)
+        .is_some_and(|account| slab.is_dlp(&account.key));
+    let maker_class = if is_dlp { order.maker_class } else { MakerClass::REG };
+
+    // Added size would have to wait for the next batch, but reserved slices
+    // must stay live to be committed, so such an order cannot grow
+    let goes_pending = maker_class == MakerClass::REG
+        || jumps_frozen_levels(slab, order.instrument_idx, order.side, new_price, current_ts);
+    if goes_pending && order.reserved_qty > 0 {
+        msg!("Error: Cannot grow a pending-bound order with reserved quantity");
+        return Err(PercolatorError::ReservedQtyExceeded);
+    }
+
+    // Re-insert as a new order
+    slab.remove_order_from_book(order_idx);
+
+    // Reserved slices are bound to the order ID, so a reserved order keeps it
+    let order_id = if order.reserved_qty > 0 {
+        order.order_id
+    } else {
+        slab.header.next_order_id()
+    };
+    let (state, eligible_epoch) = if goes_pending {
+        (
+            OrderState::PENDING,
+            slab.header.current_epoch.wrapping_add(1) as u16,
+        )
+    } else {
+        (order.state, order.eligible_epoch)
+    };
+
+    if let Some(order) = slab.get_order_mut(order_idx) {
+        order.order_id = order_id;
+        order.maker_class = maker_class;
+        order.state = state;
+        order.eligible_epoch = eligible_epoch;
+        order.created_ms = current_ts;
+        order.price = new_price;
+        order.qty = new_qty;
+        order.qty_orig = new_qty;
+    }
+
+    slab.insert_order_into_book(order_idx, order.instrument_idx);
+    slab.header.increment_seqno();
+    slab.refresh_quote_cache(order.instrument_idx);
+
+    Ok(AmendOrderResult {
+        order_id,
+        priority_kept: false,
+        state,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{process_cancel, process_commit, process_place_order, process_reserve, OrderParams, TakerOptions};
+    use crate::tests::{new_test_slab, test_dlp};
+
+    const PX: u64 = 50_000_000_000;
+    const TICK: u64 = 1_000_000;
+    const QTY: u64 = 1_000_000;
+
+    fn post(slab: &mut SlabState, acct: u32, price: u64, qty: u64, maker_class: MakerClass) -> u32 {
+        let params = OrderParams {
+            instrument_idx: 0,
+            side: Side::Sell,
+            price,
+            qty,
+            tif: TimeInForce::GTC,
+            maker_class,
+            flags: 0,
+        };
+        process_place_order(slab, acct, &params, 0).unwrap().order_idx
+    }
+
+    #[test]
+    fn test_size_reduction_keeps_priority() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let first = post(&mut slab, maker, PX, 5 * QTY, MakerClass::DLP);
+        let second = post(&mut slab, maker, PX, 5 * QTY, MakerClass::DLP);
+        let order_id = slab.get_order(first).unwrap().order_id;
+
+        let result = process_amend_order(&mut slab, maker, first, PX, 2 * QTY, 10).unwrap();
+        assert!(result.priority_kept);
+        assert_eq!(result.order_id, order_id);
+
+        let order = slab.get_order(first).unwrap();
+        assert_eq!(order.qty, 2 * QTY);
+        assert_eq!(order.next, second);
+        assert_eq!(slab.instruments[0].asks_head, first);
+    }
+
+    #[test]
+    fn test_size_increase_loses_priority() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let first = post(&mut slab, maker, PX, 5 * QTY, MakerClass::DLP);
+        let second = post(&mut slab, maker, PX, 5 * QTY, MakerClass::DLP);
+        let old_id = slab.get_order(first).unwrap().order_id;
+
+        let result = process_amend_order(&mut slab, maker, first, PX, 6 * QTY, 10).unwrap();
+        assert!(!result.priority_kept);
+        assert!(result.order_id > old_id);
+        assert_eq!(slab.instruments[0].asks_head, second);
+        assert_eq!(slab.get_order(second).unwrap().next, first);
+    }
+
+    #[test]
+    fn test_reprice_moves_order() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let first = post(&mut slab, maker, PX, 5 * QTY, MakerClass::DLP);
+        let second = post(&mut slab, maker, PX + TICK, 5 * QTY, MakerClass::DLP);
+
+        process_amend_order(&mut slab, maker, first, PX + 2 * TICK, 5 * QTY, 10).unwrap();
+        assert_eq!(slab.instruments[0].asks_head, second);
+        assert_eq!(slab.get_order(first).unwrap().price, PX + 2 * TICK);
+    }
+
+    #[test]
+    fn test_reg_reinsert_goes_pending() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let order_idx = post(&mut slab, maker, PX, 5 * QTY, MakerClass::REG);
+        slab.promote_pending_orders(0, 1);
+        slab.header.current_epoch = 1;
+        assert_eq!(slab.get_order(order_idx).unwrap().state, OrderState::LIVE);
+
+        let result = process_amend_order(&mut slab, maker, order_idx, PX - TICK, 5 * QTY, 10).unwrap();
+        assert_eq!(result.state, OrderState::PENDING);
+        assert_eq!(slab.instruments[0].asks_pending_head, order_idx);
+        assert_eq!(slab.get_order(order_idx).unwrap().eligible_epoch, 2);
+    }
+
+    #[test]
+    fn test_removed_dlp_reinsert_goes_pending() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let order_idx = post(&mut slab, maker, PX, 5 * QTY, MakerClass::DLP);
+        slab.remove_dlp(&[10; 32]);
+
+        // Size reductions are still fine, a reprice waits for the next batch
+        process_amend_order(&mut slab, maker, order_idx, PX, 4 * QTY, 10).unwrap();
+        let result = process_amend_order(&mut slab, maker, order_idx, PX + TICK, 4 * QTY, 10).unwrap();
+        assert_eq!(result.state, OrderState::PENDING);
+        assert_eq!(slab.get_order(order_idx).unwrap().maker_class, MakerClass::REG);
+    }
+
+    #[test]
+    fn test_amend_respects_reserved_qty() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        let order_idx = post(&mut slab, maker, PX, 5 * QTY, MakerClass::DLP);
+        process_reserve(&mut slab, taker, 0, Side::Buy, 3 * QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+
+        assert_eq!(
+            process_amend_order(&mut slab, maker, order_idx, PX, 2 * QTY, 10).unwrap_err(),
+            PercolatorError::ReservedQtyExceeded
+        );
+        assert_eq!(
+            process_amend_order(&mut slab, maker, order_idx, PX + TICK, 5 * QTY, 10).unwrap_err(),
+            PercolatorError::ReservedQtyExceeded
+        );
+
+        // Down to the reserved amount is fine
+        process_amend_order(&mut slab, maker, order_idx, PX, 3 * QTY, 10).unwrap();
+        assert_eq!(slab.get_order(order_idx).unwrap().qty, 3 * QTY);
+    }
+
+    #[test]
+    fn test_size_increase_keeps_reserved_slices() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        let order_idx = post(&mut slab, maker, PX, 3 * QTY, MakerClass::DLP);
+        let order_id = slab.get_order(order_idx).unwrap().order_id;
+        let first = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+        let second = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 2, &TakerOptions::default(), 0).unwrap();
+
+        let result = process_amend_order(&mut slab, maker, order_idx, PX, 5 * QTY, 10).unwrap();
+        assert!(!result.priority_kept);
+        assert_eq!(result.order_id, order_id);
+
+        // Both holds still resolve to the order
+        let commit = process_commit(&mut slab, first.hold_id, &[0; SALT_LEN], 0, 20).unwrap();
+        assert_eq!((commit.filled_qty, commit.shortfall_qty), (QTY, 0));
+        process_cancel(&mut slab, second.hold_id).unwrap();
+        let order = slab.get_order(order_idx).unwrap();
+        assert_eq!((order.qty, order.reserved_qty), (4 * QTY, 0));
+    }
+
+    #[test]
+    fn test_reserved_reg_order_cannot_grow() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        let order_idx = post(&mut slab, maker, PX, 3 * QTY, MakerClass::REG);
+        slab.promote_pending_orders(0, 1);
+        slab.header.current_epoch = 1;
+        process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+
+        // The added size could only go live at the next batch
+        assert_eq!(
+            process_amend_order(&mut slab, maker, order_idx, PX, 5 * QTY, 10).unwrap_err(),
+            PercolatorError::ReservedQtyExceeded
+        );
+        let order = slab.get_order(order_idx).unwrap();
+        assert_eq!((order.state, order.qty), (OrderState::LIVE, 3 * QTY));
+    }
+
+    #[test]
+    fn test_amend_requires_owner() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let other = test_dlp(&mut slab, 20);
+        let order_idx = post(&mut slab, maker, PX, 5 * QTY, MakerClass::DLP);
+
+        assert_eq!(
+            process_amend_order(&mut slab, other, order_idx, PX, QTY, 10).unwrap_err(),
+            PercolatorError::Unauthorized
+        );
+    }
+}
@@ -3,6 +3,7 @@
 //! Increments the epoch, promotes pending orders to live,
 //! and sets freeze windows as configured.
 
+use super::funding::sample_premium;
 use crate::state::SlabState;
 use percolator_common::*;
 use pinocchio::msg;
@@ -12,8 +13,10 @@ use pinocchio::msg;
 /// Opens a new batch window by:
 /// 1. Incrementing the epoch
 /// 2. Promoting pending orders that are eligible for this epoch
-/// 3. Setting freeze windows on top-K orders (anti-sandwich)
-/// 4. Recording batch open timestamp
+/// 3. Recycling the aggressor ledger (ARG)
+/// 4. Setting freeze windows on top-K orders (anti-sandwich)
+/// 5. Sampling the funding premium
+/// 6. Recording batch open timestamp
 ///
 /// # Arguments
 /// * `slab` - The slab state
@@ -55,14 +58,21 @@ pub fn process_batch_open(
     // Promote pending orders
     slab.promote_pending_orders(instrument_idx, new_epoch);
 
+    // Recycle the aggressor ledger for the new batch
+    slab.clear_aggressors(instrument_idx);
+
     // Apply freeze levels (anti-sandwich protection)
     apply_freeze_levels(slab, instrument_idx, current_ts)?;
 
+    // Feed the funding TWAP
+    sample_premium(slab, instrument_idx, current_ts);
+
     // Update header timestamps
     slab.header.last_batch_open_ts = current_ts;
 
     // Increment seqno
     slab.header.increment_seqno();
+    slab.refresh_quote_cache(instrument_idx);
 
     msg!("Batch opened successfully");
     Ok(())
@@ -71,7 +81,9 @@ pub fn process_batch_open(
 /// Apply freeze levels to top-K orders on each side
 ///
 /// This prevents front-running by freezing the top price levels
-/// until the freeze window expires.
+/// until the freeze window expires. Every live order on the top
+/// `freeze_levels` prices of each side is marked, and the worst frozen
+/// price is recorded so new orders cannot be inserted ahead of it.
 fn apply_freeze_levels(
     slab: &mut SlabState,
     instrument_idx: u16,
@@ -80,25 +92,61 @@ fn apply_freeze_levels(
     let freeze_levels = slab.header.freeze_levels;
     let batch_ms = slab.header.batch_ms;
 
+    let (bids_head, asks_head) = match slab.get_instrument(instrument_idx) {
+        Some(instr) => (instr.bids_head, instr.asks_head),
+        None => return Err(PercolatorError::InvalidInstrument),
+    };
+
     if freeze_levels == 0 {
-        return Ok(()); // Freeze disabled
+        // Freeze disabled; drop any boundary left from an earlier batch
+        if let Some(instr) = slab.get_instrument_mut(instrument_idx) {
+            instr.frozen_bid_px = 0;
+            instr.frozen_ask_px = 0;
+        }
+        return Ok(());
     }
 
     let freeze_until = current_ts + (batch_ms / 2); // Freeze for half the batch window
 
-    // Get instrument
+    let frozen_bid_px = freeze_side(slab, bids_head, freeze_levels, freeze_until);
+    let frozen_ask_px = freeze_side(slab, asks_head, freeze_levels, freeze_until);
+
     if let Some(instr) = slab.get_instrument_mut(instrument_idx) {
         instr.freeze_until_ms = freeze_until;
+        instr.frozen_bid_px = frozen_bid_px;
+        instr.frozen_ask_px = frozen_ask_px;
     }
 
-    // Mark top-K bid orders as frozen (by storing freeze time in order)
-    // Note: In a full implementation, we'd traverse and mark each order
-    // For now, the instrument-level freeze is sufficient
+    Ok(())
+}
 
-    // Mark top-K ask orders as frozen
-    // Same as above
+/// Freeze the orders on the first `levels` prices of a live book side
+///
+/// Returns the worst frozen price, or 0 if the side is empty.
+fn freeze_side(slab: &mut SlabState, head: u32, levels: u16, freeze_until: u64) -> u64 {
+    let mut order_idx = head;
+    let mut level_px = 0u64;
+    let mut level_count = 0u16;
 
-    Ok(())
+    while order_idx != SlabState::INVALID_INDEX {
+        let order = match slab.get_order_mut(order_idx) {
+            Some(o) => o,
+            None => break,
+        };
+
+        if level_count == 0 || order.price != level_px {
+            if level_count == levels {
+                break;
+            }
+            level_count += 1;
+            level_px = order.price;
+        }
+
+        order.frozen_until_ms = freeze_until;
+        order_idx = order.next;
+    }
+
+    level_px
 }
 
 /// Process batch open for all instruments
@@ -138,10 +186,17 @@ pub fn process_batch_open_all(
 
         // Promote pending orders for this instrument
         slab.promote_pending_orders(i, new_epoch);
+        slab.clear_aggressors(i);
+
+        // Apply freeze levels (anti-sandwich protection)
+        apply_freeze_levels(slab, i, current_ts)?;
+
+        sample_premium(slab, i, current_ts);
     }
 
     // Increment seqno
     slab.header.increment_seqno();
+    slab.refresh_all_quote_caches();
 
     Ok(instrument_count)
 }
@@ -162,6 +217,31 @@ pub fn is_frozen(slab: &SlabState, instrument_idx: u16, current_ts: u64) -> bool
     }
 }
 
+/// Check if a resting order is inside the top-K freeze
+pub fn is_order_frozen(order: &Order, current_ts: u64) -> bool {
+    current_ts < order.frozen_until_ms
+}
+
+/// Check if a new live order at `price` would jump ahead of frozen levels
+///
+/// Such orders are queued behind the freeze: they go to the pending queue
+/// and become live at the next batch.
+pub fn jumps_frozen_levels(slab: &SlabState, instrument_idx: u16, side: Side, price: u64, current_ts: u64) -> bool {
+    let instr = match slab.get_instrument(instrument_idx) {
+        Some(i) => i,
+        None => return false,
+    };
+
+    if current_ts >= instr.freeze_until_ms {
+        return false;
+    }
+
+    match side {
+        Side::Buy => instr.frozen_bid_px != 0 && price > instr.frozen_bid_px,
+        Side::Sell => instr.frozen_ask_px != 0 && price < instr.frozen_ask_px,
+    }
+}
+
 /// Get current batch status
 #[derive(Debug, Clone, Copy)]
 pub struct BatchStatus {
@@ -200,6 +280,124 @@ pub fn get_batch_status(slab: &SlabState, instrument_idx: u16, current_ts: u64)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instructions::{process_amend_order, process_cancel_order, process_place_order, OrderParams};
+    use crate::tests::{new_test_slab, test_account, test_dlp};
+
+    const PX: u64 = 50_000_000_000;
+    const TICK: u64 = 1_000_000;
+    const QTY: u64 = 1_000_000;
+
+    fn post(slab: &mut SlabState, acct: u32, side: Side, price: u64, ts: u64) -> u32 {
+        let params = OrderParams {
+            instrument_idx: 0,
+            side,
+            price,
+            qty: QTY,
+            tif: TimeInForce::GTC,
+            maker_class: MakerClass::DLP,
+            flags: 0,
+        };
+        process_place_order(slab, acct, &params, ts).unwrap().order_idx
+    }
+
+    /// Four ask levels with two orders on the best one, then a batch at t=1000
+    fn frozen_book(slab: &mut SlabState) -> (u32, [u32; 5]) {
+        slab.header.freeze_levels = 3;
+        let maker = test_dlp(slab, 10);
+        let orders = [
+            post(slab, maker, Side::Sell, PX, 0),
+            post(slab, maker, Side::Sell, PX, 0),
+            post(slab, maker, Side::Sell, PX + TICK, 0),
+            post(slab, maker, Side::Sell, PX + 2 * TICK, 0),
+            post(slab, maker, Side::Sell, PX + 3 * TICK, 0),
+        ];
+        process_batch_open(slab, 0, 1_000).unwrap();
+        (maker, orders)
+    }
+
+    #[test]
+    fn test_batch_open_freezes_top_levels() {
+        let mut slab = new_test_slab();
+        let (_, orders) = frozen_book(&mut slab);
+
+        // batch_ms 100 freezes for 50ms
+        for &idx in &orders[..4] {
+            assert!(is_order_frozen(slab.get_order(idx).unwrap(), 1_049));
+        }
+        assert!(!is_order_frozen(slab.get_order(orders[4]).unwrap(), 1_049));
+        assert!(!is_order_frozen(slab.get_order(orders[0]).unwrap(), 1_050));
+        assert_eq!(slab.instruments[0].frozen_ask_px, PX + 2 * TICK);
+        assert_eq!(slab.instruments[0].frozen_bid_px, 0);
+    }
+
+    #[test]
+    fn test_frozen_order_cannot_be_cancelled_or_amended() {
+        let mut slab = new_test_slab();
+        let (maker, orders) = frozen_book(&mut slab);
+
+        assert_eq!(
+            process_cancel_order(&mut slab, orders[0], maker, 1_010).unwrap_err(),
+            PercolatorError::OrderFrozen
+        );
+        assert_eq!(
+            process_amend_order(&mut slab, maker, orders[0], PX, QTY, 1_010).unwrap_err(),
+            PercolatorError::OrderFrozen
+        );
+
+        // Outside the top-K, and after the window, both work again
+        process_cancel_order(&mut slab, orders[4], maker, 1_010).unwrap();
+        process_cancel_order(&mut slab, orders[0], maker, 1_050).unwrap();
+    }
+
+    #[test]
+    fn test_new_orders_queue_behind_freeze() {
+        let mut slab = new_test_slab();
+        let (maker, _) = frozen_book(&mut slab);
+
+        // Inside the frozen range: parked until the next batch
+        let inside = post(&mut slab, maker, Side::Sell, PX + TICK, 1_010);
+        assert_eq!(slab.get_order(inside).unwrap().state, OrderState::PENDING);
+
+        // At or behind the worst frozen level: live, behind the frozen orders
+        let behind = post(&mut slab, maker, Side::Sell, PX + 2 * TICK, 1_010);
+        assert_eq!(slab.get_order(behind).unwrap().state, OrderState::LIVE);
+
+        // After the window the book is open again
+        let after = post(&mut slab, maker, Side::Sell, PX - TICK, 1_050);
+        assert_eq!(slab.get_order(after).unwrap().state, OrderState::LIVE);
+        assert_eq!(slab.instruments[0].asks_head, after);
+    }
+
+    #[test]
+    fn test_promotion_never_crosses_the_book() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let reg = test_account(&mut slab, 20);
+        let params = OrderParams {
+            instrument_idx: 0,
+            side: Side::Buy,
+            price: PX,
+            qty: QTY,
+            tif: TimeInForce::GTC,
+            maker_class: MakerClass::REG,
+            flags: 0,
+        };
+        let bid = process_place_order(&mut slab, reg, &params, 0).unwrap().order_idx;
+
+        // Pending orders are not on the live book, so the ask posts
+        let ask = post(&mut slab, maker, Side::Sell, PX, 0);
+
+        // The bid would cross the live ask, so it waits
+        process_batch_open(&mut slab, 0, 1_000).unwrap();
+        assert_eq!(slab.get_order(bid).unwrap().state, OrderState::PENDING);
+        assert_eq!(slab.instruments[0].bids_head, SlabState::INVALID_INDEX);
+
+        // Once the ask is gone it goes live at the next batch
+        process_cancel_order(&mut slab, ask, maker, 1_050).unwrap();
+        process_batch_open(&mut slab, 0, 2_000).unwrap();
+        assert_eq!(slab.get_order(bid).unwrap().state, OrderState::LIVE);
+        assert_eq!(slab.instruments[0].bids_head, bid);
+    }
 
     #[test]
     fn test_batch_status_struct() {
@@ -3,6 +3,7 @@
 //! Releases all slices locked during reserve, restoring the available
 //! quantity on each order.
 
+use super::batch_open::is_order_frozen;
 use crate::state::SlabState;
 use percolator_common::*;
 use pinocchio::msg;
@@ -40,6 +41,7 @@ pub fn process_cancel(
     }
 
     let slice_head = resv.slice_head;
+    let instrument_idx = resv.instrument_idx;
 
     // Release all slices
     release_slices(slab, slice_head);
@@ -49,6 +51,7 @@ pub fn process_cancel(
 
     // Increment seqno
     slab.header.increment_seqno();
+    slab.refresh_quote_cache(instrument_idx);
 
     msg!("Reservation cancelled successfully");
     Ok(())
@@ -56,12 +59,14 @@ pub fn process_cancel(
 
 /// Process cancel order instruction
 ///
-/// Cancels a resting order from the book. Only the order owner can cancel.
+/// Cancels a resting order from the book. Only the order owner can cancel,
+/// and orders inside the top-K freeze cannot be cancelled until it expires.
 ///
 /// # Arguments
 /// * `slab` - The slab state
 /// * `order_idx` - The order index to cancel
 /// * `account_idx` - The account that owns the order (for authorization)
+/// * `current_ts` - Current timestamp (ms)
 ///
 /// # Returns
 /// * `Ok(())` on success
@@ -69,6 +74,7 @@ pub fn process_cancel_order(
     slab: &mut SlabState,
     order_idx: u32,
     account_idx: u32,
+    current_ts: u64,
 ) -> Result<(), PercolatorError> {
     // Get the order
     let order = slab.get_order(order_idx)
@@ -83,6 +89,13 @@ pub fn process_cancel_order(
         return Err(PercolatorError::Unauthorized);
     }
 
+    if is_order_frozen(order, current_ts) {
+        msg!("Error: Order is frozen");
+        return Err(PercolatorError::OrderFrozen);
+    }
+
+    let instrument_idx = order.instrument_idx;
+
     // Check if order has reserved quantity (partially locked)
     if order.reserved_qty > 0 {
         // Can only cancel unreserved portion
@@ -92,11 +105,9 @@ pub fn process_cancel_order(
             return Err(PercolatorError::ReservedQtyExceeded);
         }
 
-        // Reduce order quantity to just the reserved amount
-        if let Some(order) = slab.get_order_mut(order_idx) {
-            order.qty = order.reserved_qty;
-            order.qty_orig = order.reserved_qty; // Update original to match
-        }
+        // Reduce order quantity to just the reserved amount; qty_orig keeps
+        // the size the order was placed with
+        slab.set_order_qty(order_idx, order.reserved_qty, order.reserved_qty);
 
         msg!("Order partially cancelled (unreserved portion)");
     } else {
@@ -109,95 +120,262 @@ pub fn process_cancel_order(
 
     // Increment seqno
     slab.header.increment_seqno();
+    slab.refresh_quote_cache(instrument_idx);
 
     Ok(())
 }
 
 /// Release all slices in a reservation chain
-fn release_slices(slab: &mut SlabState, slice_head: u32) {
+pub(crate) fn release_slices(slab: &mut SlabState, slice_head: u32) {
     let mut slice_idx = slice_head;
 
     while slice_idx != SlabState::INVALID_INDEX {
         let slice = match slab.get_slice(slice_idx) {
-            Some(s) => s,
+            Some(s) => *s,
             None => break,
         };
 
-        let order_idx = slice.order_idx;
-        let qty = slice.qty;
-        let next = slice.next;
-
-        // Restore order's reserved_qty
-        if let Some(order) = slab.get_order_mut(order_idx) {
-            order.reserved_qty = order.reserved_qty.saturating_sub(qty);
+        // Restore order's reserved_qty (unless the order is gone)
+        if let Some(order) = slab.get_slice_order(&slice).copied() {
+            slab.set_order_qty(slice.order_idx, order.qty, order.reserved_qty.saturating_sub(slice.qty));
         }
 
         // Free the slice
         slab.free_slice(slice_idx);
-        slice_idx = next;
+        slice_idx = slice.next;
     }
 }
 
-/// Cleanup expired reservations
+/// Most reservations released by one sweep instruction (compute budget)
+pub const MAX_SWEEP: u32 = 64;
+
+/// Result of a sweep
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SweepResult {
+    /// Number of expired reservations released
+    pub swept: u32,
+    /// Total reward credited to the keeper (cash units)
+    pub reward: u128,
+}
+
+/// Process sweep expired instruction
 ///
-/// Called periodically to clean up reservations that have expired without
-/// being committed or explicitly cancelled.
+/// Permissionless crank that releases reservations which expired without
+/// being committed or cancelled, oldest expiry first, restoring the reserved
+/// quantity on the maker orders. For every reservation released the keeper
+/// is paid `header.sweep_reward` by the reservation's taker, capped at the
+/// taker's positive cash.
 ///
 /// # Arguments
 /// * `slab` - The slab state
-/// * `current_ts` - Current timestamp
-/// * `max_cleanup` - Maximum number of reservations to clean up (gas limit)
+/// * `keeper_idx` - Account credited with the reward
+/// * `max_sweep` - Maximum number of reservations to release (at most MAX_SWEEP)
+/// * `current_ts` - Current timestamp (ms)
 ///
 /// # Returns
-/// * Number of reservations cleaned up
-pub fn cleanup_expired_reservations(
+/// * `SweepResult` with the number released and the reward paid
+pub fn process_sweep_expired(
     slab: &mut SlabState,
+    keeper_idx: u32,
+    max_sweep: u32,
     current_ts: u64,
-    max_cleanup: u32,
-) -> u32 {
-    let mut cleaned = 0u32;
-
-    // Iterate through reservations looking for expired ones
-    for i in 0..crate::state::slab::POOL_RESERVATIONS {
-        if cleaned >= max_cleanup {
-            break;
-        }
+) -> Result<SweepResult, PercolatorError> {
+    if max_sweep == 0 || max_sweep > MAX_SWEEP {
+        msg!("Error: Sweep count must be between 1 and MAX_SWEEP");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+    if slab.get_account(keeper_idx).is_none() {
+        msg!("Error: Keeper account not found");
+        return Err(PercolatorError::InvalidAccount);
+    }
 
-        let resv = match slab.get_reservation(i as u32) {
-            Some(r) => r,
-            None => continue,
+    let sweep_reward = slab.header.sweep_reward as i128;
+    let mut result = SweepResult::default();
+    while result.swept < max_sweep {
+        let resv_idx = match slab.next_expired(current_ts) {
+            Some(idx) => idx,
+            None => break,
+        };
+        let (slice_head, taker_idx) = match slab.get_reservation(resv_idx) {
+            Some(resv) => (resv.slice_head, resv.account_idx),
+            None => break,
         };
 
-        // Skip committed reservations (shouldn't exist but just in case)
-        if resv.committed {
-            continue;
-        }
-
-        // Check if expired
-        if resv.expiry_ms > 0 && current_ts > resv.expiry_ms {
-            let slice_head = resv.slice_head;
-
-            // Release slices
-            release_slices(slab, slice_head);
-
-            // Free reservation
-            slab.free_reservation(i as u32);
-            cleaned += 1;
+        release_slices(slab, slice_head);
+        slab.free_reservation(resv_idx);
+        result.swept += 1;
+
+        // The taker that let the hold lapse pays the keeper
+        let reward = match slab.get_account_mut(taker_idx) {
+            Some(taker) => {
+                let reward = sweep_reward.min(taker.cash.max(0));
+                taker.cash -= reward;
+                reward
+            }
+            None => 0,
+        };
+        if let Some(keeper) = slab.get_account_mut(keeper_idx) {
+            keeper.cash += reward;
         }
+        result.reward += reward as u128;
     }
 
-    if cleaned > 0 {
+    if result.swept > 0 {
         slab.header.increment_seqno();
+        slab.refresh_all_quote_caches();
     }
 
-    cleaned
+    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::instructions::{process_commit, process_place_order, process_reserve, OrderParams, TakerOptions};
+    use crate::tests::{new_test_slab, test_account, test_dlp};
+
+    const PX: u64 = 50_000_000_000;
+    const QTY: u64 = 1_000_000;
+
+    fn post(slab: &mut SlabState, qty: u64) -> u32 {
+        let maker = test_dlp(slab, 10);
+        let params = OrderParams {
+            instrument_idx: 0,
+            side: Side::Sell,
+            price: PX,
+            qty,
+            tif: TimeInForce::GTC,
+            maker_class: MakerClass::DLP,
+            flags: 0,
+        };
+        process_place_order(slab, maker, &params, 0).unwrap().order_idx
+    }
+
+    fn reserve(slab: &mut SlabState, taker: u32, ttl_ms: u64, route_id: u64) -> u64 {
+        process_reserve(slab, taker, 0, Side::Buy, QTY, PX, ttl_ms, [0; 32], route_id, &TakerOptions::default(), 1_000)
+            .unwrap()
+            .hold_id
+    }
+
+    #[test]
+    fn test_cancel_keeps_reserved_part() {
+        let mut slab = new_test_slab();
+        let order_idx = post(&mut slab, 3 * QTY);
+        let maker = slab.orders[order_idx as usize].account_idx;
+        let taker = test_account(&mut slab, 11);
+        let hold_id = reserve(&mut slab, taker, 1_000, 1);
+
+        // Only the unreserved part goes; the placed size is still on record
+        process_cancel_order(&mut slab, order_idx, maker, 1_000).unwrap();
+        let order = slab.orders[order_idx as usize];
+        assert_eq!((order.qty, order.reserved_qty, order.qty_orig), (QTY, QTY, 3 * QTY));
+        assert_eq!(
+            process_cancel_order(&mut slab, order_idx, maker, 1_000),
+            Err(PercolatorError::ReservedQtyExceeded)
+        );
+
+        let result = process_commit(&mut slab, hold_id, &[0; SALT_LEN], 0, 1_000).unwrap();
+        assert_eq!((result.filled_qty, result.shortfall_qty), (QTY, 0));
+        assert!(slab.get_order(order_idx).is_none());
+    }
+
+    #[test]
+    fn test_sweep_restores_maker_quantity() {
+        let mut slab = new_test_slab();
+        let order_idx = post(&mut slab, 3 * QTY);
+        let taker = test_account(&mut slab, 11);
+        let keeper = test_account(&mut slab, 12);
+        slab.accounts[taker as usize].cash = 1_000_000_000_000;
+
+        reserve(&mut slab, taker, 500, 1);
+        reserve(&mut slab, taker, 100, 2);
+        reserve(&mut slab, taker, u64::MAX, 3); // clamped to max_ttl_ms
+        assert_eq!(slab.orders[order_idx as usize].reserved_qty, 3 * QTY);
+        assert_eq!(slab.header.expiry_count, 3);
+
+        // Nothing has expired yet
+        let result = process_sweep_expired(&mut slab, keeper, MAX_SWEEP, 1_100).unwrap();
+        assert_eq!(result, SweepResult::default());
+
+        // Earliest expiry first, bounded by the sweep count
+        let seqno = slab.header.seqno;
+        let result = process_sweep_expired(&mut slab, keeper, 1, 2_000).unwrap();
+        assert_eq!(result.swept, 1);
+        assert!(slab.find_reservation_by_hold_id(2).is_none());
+        assert_eq!(slab.orders[order_idx as usize].reserved_qty, 2 * QTY);
+        assert_eq!(slab.header.seqno, seqno + 1);
+        assert_eq!(slab.get_quote_cache(0).unwrap().best_asks[0].avail_qty, QTY as i64);
+
+        let result = process_sweep_expired(&mut slab, keeper, MAX_SWEEP, 2_000).unwrap();
+        assert_eq!(result.swept, 1);
+        assert_eq!(slab.orders[order_idx as usize].reserved_qty, QTY);
+        assert!(slab.find_reservation_by_hold_id(3).is_some());
+
+        // The clamped hold lapses at the slab's maximum TTL
+        let expiry_ms = 1_000 + slab.header.max_ttl_ms;
+        assert_eq!(process_sweep_expired(&mut slab, keeper, MAX_SWEEP, expiry_ms).unwrap().swept, 0);
+        assert_eq!(process_sweep_expired(&mut slab, keeper, MAX_SWEEP, expiry_ms + 1).unwrap().swept, 1);
+        assert_eq!(slab.orders[order_idx as usize].reserved_qty, 0);
+        assert_eq!(slab.header.expiry_count, 0);
+
+        // Holds without an expiry are refused
+        assert_eq!(
+            process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 0, [0; 32], 4, &TakerOptions::default(), 1_000)
+                .unwrap_err(),
+            PercolatorError::InvalidInstruction
+        );
+    }
+
+    #[test]
+    fn test_sweep_pays_keeper_from_taker() {
+        let mut slab = new_test_slab();
+        post(&mut slab, 2 * QTY);
+        let taker = test_account(&mut slab, 11);
+        let keeper = test_account(&mut slab, 12);
+        let reward = slab.header.sweep_reward as i128;
+        slab.accounts[taker as usize].cash = reward + reward / 2;
+
+        reserve(&mut slab, taker, 100, 1);
+        reserve(&mut slab, taker, 100, 2);
+
+        // The second reward is capped at what the taker has left
+        let result = process_sweep_expired(&mut slab, keeper, MAX_SWEEP, 2_000).unwrap();
+        assert_eq!(result.swept, 2);
+        assert_eq!(result.reward, (reward + reward / 2) as u128);
+        assert_eq!(slab.accounts[taker as usize].cash, 0);
+        assert_eq!(slab.accounts[keeper as usize].cash, reward + reward / 2);
+    }
+
+    #[test]
+    fn test_sweep_skips_settled_reservations() {
+        let mut slab = new_test_slab();
+        let order_idx = post(&mut slab, 3 * QTY);
+        let taker = test_account(&mut slab, 11);
+        let keeper = test_account(&mut slab, 12);
+
+        let committed = reserve(&mut slab, taker, 100, 1);
+        let cancelled = reserve(&mut slab, taker, 100, 2);
+        reserve(&mut slab, taker, 100, 3);
+        process_commit(&mut slab, committed, &[0; SALT_LEN], 0, 1_050).unwrap();
+        process_cancel(&mut slab, cancelled).unwrap();
+        assert_eq!(slab.header.expiry_count, 1);
+
+        let result = process_sweep_expired(&mut slab, keeper, MAX_SWEEP, 2_000).unwrap();
+        assert_eq!(result.swept, 1);
+        assert_eq!(slab.orders[order_idx as usize].reserved_qty, 0);
+        assert_eq!(slab.header.reservation_count, 0);
+    }
+
     #[test]
-    fn test_cancel_compiles() {
-        // Basic compilation test
-        assert!(true);
+    fn test_sweep_rejects_bad_arguments() {
+        let mut slab = new_test_slab();
+        let keeper = test_account(&mut slab, 12);
+
+        assert_eq!(process_sweep_expired(&mut slab, keeper, 0, 0), Err(PercolatorError::InvalidInstruction));
+        assert_eq!(
+            process_sweep_expired(&mut slab, keeper, MAX_SWEEP + 1, 0),
+            Err(PercolatorError::InvalidInstruction)
+        );
+        assert_eq!(process_sweep_expired(&mut slab, 999, 1, 0), Err(PercolatorError::InvalidAccount));
     }
 }
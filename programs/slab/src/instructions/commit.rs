@@ -1,9 +1,12 @@
 //! Commit instruction - phase 2 of two-phase execution
 //!
 //! Executes trades at the maker prices captured during reserve.
-//! Applies fees, updates positions, and records trades.
+//! Applies fees, updates positions, books both legs' cash, and records trades.
 
-use crate::state::SlabState;
+use super::cancel::release_slices;
+use super::funding::settle_position_funding;
+use super::liquidation::unlink_position_from_account;
+use crate::state::{ArgMode, ShortfallPolicy, SlabState};
 use percolator_common::*;
 use pinocchio::msg;
 
@@ -20,6 +23,28 @@ pub struct CommitResult {
     pub fees: u128,
     /// Realized PnL (if closing position)
     pub realized_pnl: i128,
+    /// Reserved quantity released back to the maker orders unfilled
+    pub released_qty: u64,
+    /// Reserved quantity no longer backed by its maker orders at commit
+    pub shortfall_qty: u64,
+}
+
+impl CommitResult {
+    /// Size of the serialized result
+    pub const LEN: usize = 80;
+
+    /// Serialize for return data (read by the router's commit CPI)
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0u8; Self::LEN];
+        bytes[0..8].copy_from_slice(&self.filled_qty.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.vwap_px.to_le_bytes());
+        bytes[16..32].copy_from_slice(&self.notional.to_le_bytes());
+        bytes[32..48].copy_from_slice(&self.fees.to_le_bytes());
+        bytes[48..64].copy_from_slice(&self.realized_pnl.to_le_bytes());
+        bytes[64..72].copy_from_slice(&self.released_qty.to_le_bytes());
+        bytes[72..80].copy_from_slice(&self.shortfall_qty.to_le_bytes());
+        bytes
+    }
 }
 
 /// Process commit instruction
@@ -28,9 +53,24 @@ pub struct CommitResult {
 /// applies anti-toxicity checks, executes trades at captured maker prices,
 /// updates positions, and records trades.
 ///
+/// If the reservation carries a commitment hash, the router must reveal the
+/// salt and the slab recomputes
+/// `H(route_id || instrument_idx || side || qty || limit_px || salt)`.
+///
+/// A commit may take only part of the reservation: slices are filled in
+/// order up to `target_qty` and everything reserved beyond it is released
+/// back to the maker orders. The reservation is closed either way.
+///
+/// Each slice is checked against the order it was reserved from (same slot,
+/// same order ID). Quantity whose order has left the slab or shrunk below
+/// the slice is shortfall: under `ShortfallPolicy::StrictFail` the commit is
+/// rejected, otherwise the backed part fills and the shortfall is reported.
+///
 /// # Arguments
 /// * `slab` - The slab state
 /// * `hold_id` - The hold ID from reserve
+/// * `salt` - Commit-reveal salt (ignored if the reservation has no commitment)
+/// * `target_qty` - Quantity to fill, at most the reserved quantity (0 fills all of it)
 /// * `current_ts` - Current timestamp (for expiry check)
 ///
 /// # Returns
@@ -38,6 +78,8 @@ pub struct CommitResult {
 pub fn process_commit(
     slab: &mut SlabState,
     hold_id: u64,
+    salt: &[u8; SALT_LEN],
+    target_qty: u64,
     current_ts: u64,
 ) -> Result<CommitResult, PercolatorError> {
     // Find the reservation
@@ -57,8 +99,15 @@ pub fn process_commit(
         return Err(PercolatorError::InvalidReservation);
     }
 
+    // A partial commit cannot ask for more than was reserved
+    if target_qty > resv.qty {
+        msg!("Error: Commit quantity exceeds reservation");
+        return Err(PercolatorError::InvalidQuantity);
+    }
+    let commit_qty = if target_qty == 0 { resv.qty } else { target_qty };
+
     // Check expiry
-    if current_ts > resv.expiry_ms && resv.expiry_ms > 0 {
+    if current_ts > resv.expiry_ms {
         // Release the reservation and return error
         release_reservation_slices(slab, resv_idx);
         slab.free_reservation(resv_idx);
@@ -66,32 +115,100 @@ pub fn process_commit(
         return Err(PercolatorError::ReservationExpired);
     }
 
-    // Anti-toxicity: Kill band check
-    // The mark price should not have moved beyond the kill band since reservation
-    let mark_at_reserve = slab.header.prev_mark_px;
-    let mark_now = slab.header.mark_px;
-    
-    if mark_at_reserve != 0 && mark_now != 0 {
-        let kill_band_bps = slab.header.kill_band_bps;
-        let diff = (mark_now - mark_at_reserve).abs();
-        let threshold = (mark_at_reserve.abs() as u64 * kill_band_bps) / 10_000;
-        
-        if diff > threshold as i64 {
-            release_reservation_slices(slab, resv_idx);
-            slab.free_reservation(resv_idx);
-            msg!("Error: Kill band exceeded");
-            return Err(PercolatorError::KillBandExceeded);
+    // Commit-reveal: the salt must open the commitment made at reserve
+    if resv.commitment_hash != [0; 32] {
+        let expected = commitment_hash(
+            resv.route_id,
+            resv.instrument_idx,
+            resv.side,
+            resv.req_qty,
+            resv.limit_px,
+            salt,
+        );
+        if expected != resv.commitment_hash {
+            msg!("Error: Commitment hash mismatch");
+            return Err(PercolatorError::InvalidCommitment);
         }
+    } else if slab.header.commit_reveal_required {
+        msg!("Error: Reservation has no commitment");
+        return Err(PercolatorError::InvalidCommitment);
+    }
+
+    // Anti-toxicity: Kill band check
+    // The instrument's mark must not have moved beyond its kill band since the
+    // snapshot taken at reserve time
+    let instr = slab.get_instrument(resv.instrument_idx).ok_or_else(|| {
+        msg!("Error: Invalid instrument index");
+        PercolatorError::InvalidInstrument
+    })?;
+
+    if exceeds_kill_band(resv.mark_px, instr.mark_px, instr.kill_band_bps) {
+        release_reservation_slices(slab, resv_idx);
+        slab.free_reservation(resv_idx);
+        msg!("Error: Kill band exceeded");
+        return Err(PercolatorError::KillBandExceeded);
+    }
+
+    // Reduce-only takers may not grow their position, even if it changed
+    // between reserve and commit
+    if resv.flags & ORDER_FLAG_REDUCE_ONLY != 0
+        && commit_qty > reducible_qty(slab, resv.account_idx, resv.instrument_idx, resv.side)
+    {
+        release_reservation_slices(slab, resv_idx);
+        slab.free_reservation(resv_idx);
+        msg!("Error: Reduce-only reservation would increase position");
+        return Err(PercolatorError::ReduceOnlyViolation);
     }
 
     // Extract reservation data before mutable operations
     let account_idx = resv.account_idx;
     let instrument_idx = resv.instrument_idx;
     let side = resv.side;
+    let route_id = resv.route_id;
     let slice_head = resv.slice_head;
+    let resv_qty = resv.qty;
+
+    // Slice integrity: how much of the reservation its maker orders still back
+    let shortfall_qty = resv_qty.saturating_sub(backed_qty(slab, slice_head));
+    if shortfall_qty > 0 && slab.header.shortfall_policy == ShortfallPolicy::StrictFail {
+        release_reservation_slices(slab, resv_idx);
+        slab.free_reservation(resv_idx);
+        msg!("Error: Reserved maker order no longer backs its slice");
+        return Err(PercolatorError::ReservedSliceMissing);
+    }
+
+    // Aggressor Roundtrip Guard: a taker trading back against its own legs
+    // from the same batch is taxed, clipped or rejected
+    let arg_mode = slab.header.arg_mode;
+    let (aggressor_idx, overlap) = if slab.header.arg_enabled {
+        let epoch = slab.header.current_epoch as u16;
+        let entry_idx = slab.get_or_create_aggressor(account_idx, instrument_idx, epoch).ok_or_else(|| {
+            msg!("Error: Aggressor ledger full");
+            PercolatorError::PoolFull
+        })?;
+        let overlap = roundtrip_overlap(&slab.aggressors[entry_idx as usize], side).min(commit_qty);
+        (Some(entry_idx), overlap)
+    } else {
+        (None, 0)
+    };
+
+    let max_fill = match arg_mode {
+        ArgMode::Reject | ArgMode::Clip if overlap > 0 => {
+            let max_fill = commit_qty - overlap;
+            if arg_mode == ArgMode::Reject || max_fill == 0 {
+                release_reservation_slices(slab, resv_idx);
+                slab.free_reservation(resv_idx);
+                msg!("Error: Roundtrip detected");
+                return Err(PercolatorError::RoundtripDetected);
+            }
+            max_fill
+        }
+        _ => commit_qty,
+    };
 
     // Execute fills
-    let (filled_qty, total_notional, fees) = execute_fills(slab, slice_head, current_ts)?;
+    let (filled_qty, total_notional, mut fees, realized_pnl) =
+        execute_fills(slab, slice_head, account_idx, side, route_id, max_fill, current_ts)?;
 
     // Calculate VWAP
     let vwap_px = if filled_qty > 0 {
@@ -100,17 +217,40 @@ pub fn process_commit(
         0
     };
 
-    // Update position
-    let realized_pnl = update_position(slab, account_idx, instrument_idx, side, filled_qty as i64, vwap_px)?;
+    // ARG tax on the overlapping notional, and the ledger update
+    if arg_mode == ArgMode::Tax && overlap > 0 {
+        let taxed_notional = mul_u64(overlap.min(filled_qty), vwap_px);
+        fees += (taxed_notional * slab.header.arg_tax_bps as u128) / 10_000;
+    }
+    if let Some(idx) = aggressor_idx {
+        let entry = &mut slab.aggressors[idx as usize];
+        match side {
+            Side::Buy => {
+                entry.buy_qty += filled_qty;
+                entry.buy_notional += total_notional;
+            }
+            Side::Sell => {
+                entry.sell_qty += filled_qty;
+                entry.sell_notional += total_notional;
+            }
+        }
+    }
+
+    // Settle the taker leg: realized PnL less fees and any ARG tax
+    if let Some(taker) = slab.get_account_mut(account_idx) {
+        taker.cash += realized_pnl - fees as i128;
+    }
 
     // Mark reservation as committed and free it
     if let Some(resv) = slab.get_reservation_mut(resv_idx) {
         resv.committed = true;
+        resv.salt = *salt;
     }
     slab.free_reservation(resv_idx);
 
     // Increment seqno
     slab.header.increment_seqno();
+    slab.refresh_quote_cache(instrument_idx);
 
     Ok(CommitResult {
         filled_qty,
@@ -118,41 +258,82 @@ pub fn process_commit(
         notional: total_notional,
         fees,
         realized_pnl,
+        released_qty: resv_qty.saturating_sub(filled_qty + shortfall_qty),
+        shortfall_qty,
     })
 }
 
+/// Reserved quantity still backed by the orders the slices were taken from
+fn backed_qty(slab: &SlabState, slice_head: u32) -> u64 {
+    let mut backed = 0u64;
+    let mut slice_idx = slice_head;
+    while let Some(slice) = slab.get_slice(slice_idx) {
+        if let Some(order) = slab.get_slice_order(slice) {
+            backed += slice.qty.min(order.qty);
+        }
+        slice_idx = slice.next;
+    }
+    backed
+}
+
+/// Quantity of a new taker leg that would trade back against the account's
+/// opposite legs from the same batch
+fn roundtrip_overlap(entry: &AggressorEntry, side: Side) -> u64 {
+    match side {
+        Side::Buy => entry.sell_qty.saturating_sub(entry.buy_qty),
+        Side::Sell => entry.buy_qty.saturating_sub(entry.sell_qty),
+    }
+}
+
 /// Execute fills for all slices in a reservation
 ///
-/// Returns (filled_qty, total_notional, fees)
-fn execute_fills(
+/// Settles both legs of every fill at the fill price: each position is
+/// opened, extended or flipped, and the maker's realized PnL plus its fee
+/// (or rebate) is booked to the maker's cash. The taker's realized PnL and
+/// fees are returned for the caller to book.
+///
+/// At most `max_qty` is filled; reserved quantity beyond it is released
+/// back to the maker orders.
+///
+/// Returns (filled_qty, total_notional, taker_fees, taker_pnl)
+pub(crate) fn execute_fills(
     slab: &mut SlabState,
     slice_head: u32,
+    taker_account_idx: u32,
+    taker_side: Side,
+    route_id: u64,
+    max_qty: u64,
     current_ts: u64,
-) -> Result<(u64, u128, u128), PercolatorError> {
+) -> Result<(u64, u128, u128, i128), PercolatorError> {
     let mut total_qty = 0u64;
     let mut total_notional = 0u128;
     let mut total_fees = 0u128;
+    let mut taker_pnl = 0i128;
 
     let taker_fee_bps = slab.header.taker_fee_bps;
     let maker_fee_bps = slab.header.maker_fee_bps;
-    let maker_rebate_min_ms = slab.header.maker_rebate_min_ms;
+
+    let maker_side = match taker_side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+    };
 
     let mut slice_idx = slice_head;
     while slice_idx != SlabState::INVALID_INDEX {
         let slice = match slab.get_slice(slice_idx) {
-            Some(s) => s,
+            Some(s) => *s,
             None => break,
         };
 
         let order_idx = slice.order_idx;
-        let fill_qty = slice.qty;
+        let slice_qty = slice.qty;
         let next_slice = slice.next;
 
         // Get order details
-        let order = match slab.get_order(order_idx) {
+        let order = match slab.get_slice_order(&slice) {
             Some(o) => o,
             None => {
-                // Order was canceled, skip this slice
+                // Order left the slab: the whole slice is shortfall
                 slab.free_slice(slice_idx);
                 slice_idx = next_slice;
                 continue;
@@ -161,7 +342,21 @@ fn execute_fills(
 
         let fill_price = order.price;
         let order_created_ms = order.created_ms;
+        let order_id = order.order_id;
+        let maker_account_idx = order.account_idx;
         let instrument_idx = order.instrument_idx;
+        let reduce_only = order.flags & ORDER_FLAG_REDUCE_ONLY != 0;
+
+        // Only what is left on the order can fill (the rest is shortfall).
+        // Reduce-only makers fill at most what closes their position;
+        // the rest of the slice is released back to the order
+        let backed_qty = slice_qty.min(order.qty);
+        let fill_qty = if reduce_only {
+            backed_qty.min(reducible_qty(slab, maker_account_idx, instrument_idx, maker_side))
+        } else {
+            backed_qty
+        };
+        let fill_qty = fill_qty.min(max_qty - total_qty);
 
         // Calculate notional for this fill
         let fill_notional = mul_u64(fill_qty, fill_price);
@@ -169,38 +364,68 @@ fn execute_fills(
         // Calculate taker fee
         let taker_fee = (fill_notional * taker_fee_bps as u128) / 10_000;
 
-        // Calculate maker fee/rebate (with JIT penalty check)
-        let _maker_fee = if current_ts.saturating_sub(order_created_ms) < maker_rebate_min_ms {
+        // Calculate maker fee/rebate (negative = rebate)
+        let mut maker_fee = (fill_notional as i128 * maker_fee_bps as i128) / 10_000;
+        if maker_fee < 0 && slab.header.is_jit_order(order_created_ms, current_ts) {
             // JIT penalty: no rebate for orders posted too recently
-            0i128
-        } else {
-            // Normal maker fee (can be negative for rebate)
-            (fill_notional as i128 * maker_fee_bps as i128) / 10_000
-        };
-        // Note: maker_fee is tracked but settlement happens via router
+            maker_fee = 0;
+        }
 
         // Update order quantity
-        if let Some(order) = slab.get_order_mut(order_idx) {
-            order.qty = order.qty.saturating_sub(fill_qty);
-            order.reserved_qty = order.reserved_qty.saturating_sub(fill_qty);
+        if let Some(order) = slab.get_order(order_idx).copied() {
+            slab.set_order_qty(
+                order_idx,
+                order.qty.saturating_sub(fill_qty),
+                order.reserved_qty.saturating_sub(slice_qty),
+            );
 
             // If order is fully filled, remove from book
-            if order.qty == 0 {
-                let order_idx_copy = order_idx;
-                // End mutable borrow before calling other methods
-                let _ = order;
-                slab.remove_order_from_book(order_idx_copy);
-                slab.free_order(order_idx_copy);
+            if order.qty <= fill_qty {
+                slab.remove_order_from_book(order_idx);
+                slab.free_order(order_idx);
             }
         }
 
+        if fill_qty == 0 {
+            trim_reduce_only_order(slab, order_idx);
+            slab.free_slice(slice_idx);
+            slice_idx = next_slice;
+            continue;
+        }
+
+        // Settle the maker leg
+        let maker_pnl = update_position(
+            slab,
+            maker_account_idx,
+            instrument_idx,
+            maker_side,
+            fill_qty as i64,
+            fill_price,
+        )?;
+        if let Some(maker) = slab.get_account_mut(maker_account_idx) {
+            maker.cash += maker_pnl - maker_fee;
+        }
+        if reduce_only {
+            trim_reduce_only_order(slab, order_idx);
+        }
+
+        // Settle the taker leg
+        taker_pnl += update_position(
+            slab,
+            taker_account_idx,
+            instrument_idx,
+            taker_side,
+            fill_qty as i64,
+            fill_price,
+        )?;
+
         // Record trade
         let trade = Trade {
             ts: current_ts,
-            order_id_maker: slab.get_order(order_idx).map(|o| o.order_id).unwrap_or(0),
-            order_id_taker: 0, // Filled by caller if needed
+            order_id_maker: order_id,
+            order_id_taker: route_id,
             instrument_idx,
-            side: Side::Buy, // From taker perspective
+            side: taker_side,
             _padding: [0; 5],
             price: fill_price,
             qty: fill_qty,
@@ -219,42 +444,20 @@ fn execute_fills(
         slice_idx = next_slice;
     }
 
-    Ok((total_qty, total_notional, total_fees))
+    Ok((total_qty, total_notional, total_fees, taker_pnl))
 }
 
 /// Release all slices in a reservation (restore order available qty)
 fn release_reservation_slices(slab: &mut SlabState, resv_idx: u32) {
-    let slice_head = match slab.get_reservation(resv_idx) {
-        Some(r) => r.slice_head,
-        None => return,
-    };
-
-    let mut slice_idx = slice_head;
-    while slice_idx != SlabState::INVALID_INDEX {
-        let slice = match slab.get_slice(slice_idx) {
-            Some(s) => s,
-            None => break,
-        };
-
-        let order_idx = slice.order_idx;
-        let qty = slice.qty;
-        let next = slice.next;
-
-        // Restore order's reserved_qty
-        if let Some(order) = slab.get_order_mut(order_idx) {
-            order.reserved_qty = order.reserved_qty.saturating_sub(qty);
-        }
-
-        // Free the slice
-        slab.free_slice(slice_idx);
-        slice_idx = next;
+    if let Some(resv) = slab.get_reservation(resv_idx) {
+        release_slices(slab, resv.slice_head);
     }
 }
 
 /// Update position after a fill
 ///
 /// Returns realized PnL if closing/reducing position
-fn update_position(
+pub(crate) fn update_position(
     slab: &mut SlabState,
     account_idx: u32,
     instrument_idx: u16,
@@ -273,6 +476,9 @@ fn update_position(
 
     match pos_idx {
         Some(idx) => {
+            // Funding accrued at the old size is settled before it changes
+            settle_position_funding(slab, idx);
+
             // Update existing position
             let pos = slab.get_position_mut(idx)
                 .ok_or(PercolatorError::PositionNotFound)?;
@@ -296,6 +502,7 @@ fn update_position(
 
             if new_qty == 0 {
                 // Position fully closed
+                unlink_position_from_account(slab, account_idx, idx);
                 slab.free_position(idx);
             } else {
                 // Update position
@@ -311,6 +518,9 @@ fn update_position(
                     if new_abs_qty > 0 {
                         pos.entry_px = (new_notional / new_abs_qty) as u64;
                     }
+                } else if (old_qty > 0) != (new_qty > 0) {
+                    // Flipped through zero - the residual was opened at the fill price
+                    pos.entry_px = fill_price;
                 }
                 // For reductions, entry price stays the same (remaining position at original entry)
             }
@@ -318,6 +528,10 @@ fn update_position(
             Ok(realized_pnl)
         }
         None => {
+            let cum_funding = slab.get_instrument(instrument_idx)
+                .map(|i| i.cum_funding)
+                .unwrap_or(0);
+
             // Create new position
             let new_idx = slab.alloc_position()
                 .ok_or_else(|| {
@@ -330,7 +544,7 @@ fn update_position(
                 pos.instrument_idx = instrument_idx;
                 pos.qty = qty_change;
                 pos.entry_px = fill_price;
-                pos.last_funding = 0;
+                pos.last_funding = cum_funding;
                 pos.next_in_account = SlabState::INVALID_INDEX;
             }
 
@@ -351,7 +565,7 @@ fn update_position(
 }
 
 /// Find position for account/instrument
-fn find_position(slab: &SlabState, account_idx: u32, instrument_idx: u16) -> Option<u32> {
+pub(crate) fn find_position(slab: &SlabState, account_idx: u32, instrument_idx: u16) -> Option<u32> {
     let acc = slab.get_account(account_idx)?;
     let mut pos_idx = acc.position_head;
 
@@ -366,12 +580,529 @@ fn find_position(slab: &SlabState, account_idx: u32, instrument_idx: u16) -> Opt
     None
 }
 
+/// Quantity an order on `side` can trade without increasing |position|
+///
+/// This is the size of the opposite-signed position, or zero when the account
+/// is flat or already positioned on `side`.
+pub(crate) fn reducible_qty(slab: &SlabState, account_idx: u32, instrument_idx: u16, side: Side) -> u64 {
+    let qty = find_position(slab, account_idx, instrument_idx)
+        .and_then(|idx| slab.get_position(idx))
+        .map(|p| p.qty)
+        .unwrap_or(0);
+
+    match side {
+        Side::Buy if qty < 0 => qty.unsigned_abs(),
+        Side::Sell if qty > 0 => qty as u64,
+        _ => 0,
+    }
+}
+
+/// Shrink a reduce-only order so it never exceeds the maker's position
+///
+/// Reserved quantity is kept so outstanding reservations can still commit.
+fn trim_reduce_only_order(slab: &mut SlabState, order_idx: u32) {
+    let order = match slab.get_order(order_idx) {
+        Some(o) if o.flags & ORDER_FLAG_REDUCE_ONLY != 0 => o,
+        _ => return,
+    };
+
+    let reducible = reducible_qty(slab, order.account_idx, order.instrument_idx, order.side);
+    let max_qty = reducible.max(order.reserved_qty);
+    if order.qty <= max_qty {
+        return;
+    }
+
+    if max_qty == 0 {
+        slab.remove_order_from_book(order_idx);
+        slab.free_order(order_idx);
+    } else {
+        slab.set_order_qty(order_idx, max_qty, order.reserved_qty);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instructions::{
+        process_add_instrument, process_batch_open, process_place_order, process_reserve,
+        process_update_mark_price, OrderParams, TakerOptions,
+    };
+    use crate::tests::{new_test_slab, test_dlp};
+
+    const PX: u64 = 50_000_000_000;
+    const TICK: u64 = 1_000_000;
+    const QTY: u64 = 1_000_000;
+
+    fn post(slab: &mut SlabState, acct: u32, side: Side, price: u64, qty: u64, ts: u64) -> u32 {
+        let params = OrderParams {
+            instrument_idx: 0,
+            side,
+            price,
+            qty,
+            tif: TimeInForce::GTC,
+            maker_class: MakerClass::DLP,
+            flags: 0,
+        };
+        process_place_order(slab, acct, &params, ts).unwrap().order_idx
+    }
+
+    fn take(slab: &mut SlabState, acct: u32, side: Side, qty: u64, limit_px: u64, route_id: u64, ts: u64) -> CommitResult {
+        let resv = process_reserve(slab, acct, 0, side, qty, limit_px, 10_000, [0; 32], route_id, &TakerOptions::default(), 0).unwrap();
+        process_commit(slab, resv.hold_id, &[0; SALT_LEN], 0, ts).unwrap()
+    }
+
+    fn position_qty(slab: &SlabState, acct: u32) -> Option<i64> {
+        find_position(slab, acct, 0).and_then(|idx| slab.get_position(idx)).map(|p| p.qty)
+    }
 
     #[test]
     fn test_commit_result_size() {
-        assert!(core::mem::size_of::<CommitResult>() <= 64);
+        assert!(core::mem::size_of::<CommitResult>() <= 80);
+        // Return data: the router's CommitResponse plus released and shortfall quantities
+        assert_eq!(CommitResult::LEN, 64 + 16);
+    }
+
+    #[test]
+    fn test_commit_settles_both_legs() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+
+        let order_idx = post(&mut slab, maker, Side::Sell, PX, 5 * QTY, 0);
+        let result = take(&mut slab, taker, Side::Buy, 2 * QTY, PX, 77, 1_000);
+        assert_eq!(result.filled_qty, 2 * QTY);
+
+        // Both legs carry the opposite positions
+        assert_eq!(position_qty(&slab, taker), Some(2 * QTY as i64));
+        assert_eq!(position_qty(&slab, maker), Some(-(2 * QTY as i64)));
+        let maker_pos = slab.get_position(find_position(&slab, maker, 0).unwrap()).unwrap();
+        assert_eq!(maker_pos.entry_px, PX);
+
+        // Aged maker order earns the rebate, the taker pays the fee
+        let notional = mul_u64(2 * QTY, PX) as i128;
+        assert_eq!(slab.accounts[maker as usize].cash, notional * 5 / 10_000);
+        assert_eq!(slab.accounts[taker as usize].cash, -(notional * 20 / 10_000));
+
+        // Resting order shrinks
+        assert_eq!(slab.get_order(order_idx).unwrap().qty, 3 * QTY);
+        assert_eq!(slab.get_order(order_idx).unwrap().reserved_qty, 0);
+
+        // Trade records the taker side and route
+        let trade = slab.trades[0];
+        assert_eq!(trade.side, Side::Buy);
+        assert_eq!(trade.order_id_taker, 77);
+        assert_eq!(trade.order_id_maker, slab.get_order(order_idx).unwrap().order_id);
+    }
+
+    #[test]
+    fn test_jit_maker_gets_no_rebate() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+
+        // Posted 10ms before the fill (min age is 50ms)
+        post(&mut slab, maker, Side::Sell, PX, QTY, 1_000);
+        take(&mut slab, taker, Side::Buy, QTY, PX, 1, 1_010);
+
+        assert_eq!(slab.accounts[maker as usize].cash, 0);
+        assert_eq!(position_qty(&slab, maker), Some(-(QTY as i64)));
+
+        // With the penalty disabled the rebate is paid
+        slab.header.jit_penalty_on = false;
+        post(&mut slab, maker, Side::Sell, PX, QTY, 2_000);
+        take(&mut slab, taker, Side::Buy, QTY, PX, 2, 2_001);
+        assert_eq!(slab.accounts[maker as usize].cash, mul_u64(QTY, PX) as i128 * 5 / 10_000);
+    }
+
+    #[test]
+    fn test_maker_position_flips_and_closes() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        slab.header.maker_fee_bps = 0;
+
+        // Maker goes short 2 @ 50,000
+        post(&mut slab, maker, Side::Sell, PX, 2 * QTY, 0);
+        take(&mut slab, taker, Side::Buy, 2 * QTY, PX, 1, 1_000);
+
+        // Maker bid of 5 @ 49,000 is hit: buys back 2 and flips long 3
+        let bid_px = 49_000_000_000;
+        post(&mut slab, maker, Side::Buy, bid_px, 5 * QTY, 0);
+        take(&mut slab, taker, Side::Sell, 5 * QTY, bid_px, 2, 1_000);
+
+        let maker_pos = slab.get_position(find_position(&slab, maker, 0).unwrap()).unwrap();
+        assert_eq!(maker_pos.qty, 3 * QTY as i64);
+        assert_eq!(maker_pos.entry_px, bid_px);
+        assert_eq!(
+            slab.accounts[maker as usize].cash,
+            calculate_pnl(-(2 * QTY as i64), PX, bid_px)
+        );
+
+        // Maker offer of 3 is lifted: position closes and leaves the account list
+        post(&mut slab, maker, Side::Sell, PX, 3 * QTY, 0);
+        take(&mut slab, taker, Side::Buy, 3 * QTY, PX, 3, 1_000);
+
+        assert_eq!(position_qty(&slab, maker), None);
+        assert_eq!(slab.accounts[maker as usize].position_head, SlabState::INVALID_INDEX);
+        assert_eq!(position_qty(&slab, taker), None);
+    }
+
+    #[test]
+    fn test_reduce_only_maker_never_grows_position() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+
+        // Maker gets long 1
+        post(&mut slab, taker, Side::Sell, PX, QTY, 0);
+        take(&mut slab, maker, Side::Buy, QTY, PX, 1, 1_000);
+
+        // Two reduce-only offers, each individually within the position
+        for _ in 0..2 {
+            let params = OrderParams {
+                instrument_idx: 0,
+                side: Side::Sell,
+                price: PX,
+                qty: QTY,
+                tif: TimeInForce::GTC,
+                maker_class: MakerClass::DLP,
+                flags: ORDER_FLAG_REDUCE_ONLY,
+            };
+            process_place_order(&mut slab, maker, &params, 0).unwrap();
+        }
+
+        // Taker lifts both, but only one can close the maker
+        let result = take(&mut slab, taker, Side::Buy, 2 * QTY, PX, 2, 1_000);
+        assert_eq!(result.filled_qty, QTY);
+        assert_eq!(position_qty(&slab, maker), None);
+
+        // The leftover reduce-only offer is gone
+        assert_eq!(slab.instruments[0].asks_head, SlabState::INVALID_INDEX);
+        assert_eq!(slab.header.order_count, 0);
+    }
+
+    #[test]
+    fn test_reduce_only_taker_rejected_if_position_changed() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+
+        // Taker long 1
+        post(&mut slab, maker, Side::Sell, PX, QTY, 0);
+        take(&mut slab, taker, Side::Buy, QTY, PX, 1, 1_000);
+
+        // Reduce-only sell reserved, then the position is closed elsewhere
+        post(&mut slab, maker, Side::Buy, PX, 2 * QTY, 0);
+        let opts = TakerOptions { flags: ORDER_FLAG_REDUCE_ONLY, ..Default::default() };
+        let resv = process_reserve(&mut slab, taker, 0, Side::Sell, QTY, PX, 1_000, [0; 32], 2, &opts, 0).unwrap();
+        take(&mut slab, taker, Side::Sell, QTY, PX, 3, 1_000);
+
+        assert_eq!(
+            process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap_err(),
+            PercolatorError::ReduceOnlyViolation
+        );
+        assert_eq!(position_qty(&slab, taker), None);
+        assert_eq!(slab.header.reservation_count, 0);
+    }
+
+    #[test]
+    fn test_commit_reveal() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        post(&mut slab, maker, Side::Sell, PX, 2 * QTY, 0);
+
+        let salt = [42u8; SALT_LEN];
+        let hash = commitment_hash(7, 0, Side::Buy, QTY, PX, &salt);
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, hash, 7, &TakerOptions::default(), 0).unwrap();
+
+        // A wrong salt does not open the commitment and leaves the hold intact
+        assert_eq!(
+            process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap_err(),
+            PercolatorError::InvalidCommitment
+        );
+
+        let result = process_commit(&mut slab, resv.hold_id, &salt, 0, 1_000).unwrap();
+        assert_eq!(result.filled_qty, QTY);
+    }
+
+    #[test]
+    fn test_commit_reveal_required() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        post(&mut slab, maker, Side::Sell, PX, 2 * QTY, 0);
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+
+        slab.header.commit_reveal_required = true;
+        assert_eq!(
+            process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap_err(),
+            PercolatorError::InvalidCommitment
+        );
+        assert_eq!(
+            process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 2, &TakerOptions::default(), 0).unwrap_err(),
+            PercolatorError::InvalidCommitment
+        );
+    }
+
+    #[test]
+    fn test_partial_commit_releases_remainder() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        let first = post(&mut slab, maker, Side::Sell, PX, QTY, 0);
+        let second = post(&mut slab, maker, Side::Sell, PX + 1_000_000, 2 * QTY, 0);
+
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, 3 * QTY, PX + 1_000_000, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+        assert_eq!(resv.filled_qty, 3 * QTY);
+
+        // Slices fill in order: the whole first level, then half of the second
+        let result = process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 2 * QTY, 1_000).unwrap();
+        assert_eq!(result.filled_qty, 2 * QTY);
+        assert_eq!(result.released_qty, QTY);
+        assert_eq!(result.vwap_px, PX + 500_000);
+        assert_eq!(position_qty(&slab, taker), Some(2 * QTY as i64));
+
+        // The rest of the second order is back on the book, keeping its place
+        assert!(slab.get_order(first).is_none());
+        let order = slab.get_order(second).unwrap();
+        assert_eq!((order.qty, order.reserved_qty), (QTY, 0));
+        assert_eq!(slab.instruments[0].asks_head, second);
+        assert_eq!(slab.header.reservation_count, 0);
+
+        let bytes = result.to_bytes();
+        assert_eq!(u64::from_le_bytes(bytes[0..8].try_into().unwrap()), 2 * QTY);
+        assert_eq!(u64::from_le_bytes(bytes[64..72].try_into().unwrap()), QTY);
+    }
+
+    #[test]
+    fn test_commit_qty_above_reservation_rejected() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        post(&mut slab, maker, Side::Sell, PX, QTY, 0);
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+
+        // The hold survives a bad request and can still be committed in full
+        assert_eq!(
+            process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], QTY + 1, 1_000).unwrap_err(),
+            PercolatorError::InvalidQuantity
+        );
+        let result = process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap();
+        assert_eq!((result.filled_qty, result.released_qty), (QTY, 0));
+    }
+
+    #[test]
+    fn test_slice_of_departed_order_is_shortfall() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        let first = post(&mut slab, maker, Side::Sell, PX, QTY, 0);
+        let second = post(&mut slab, maker, Side::Sell, PX, 2 * QTY, 0);
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, 3 * QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+
+        // The first order leaves and a new order takes over its slot
+        slab.remove_order_from_book(first);
+        slab.free_order(first);
+        let reused = post(&mut slab, maker, Side::Sell, PX, 5 * QTY, 0);
+        assert_eq!(reused, first);
+
+        let result = process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap();
+        assert_eq!(result.filled_qty, 2 * QTY);
+        assert_eq!((result.shortfall_qty, result.released_qty), (QTY, 0));
+        assert_eq!(position_qty(&slab, taker), Some(2 * QTY as i64));
+
+        // The new order in the reused slot is untouched
+        assert!(slab.get_order(second).is_none());
+        let order = slab.get_order(reused).unwrap();
+        assert_eq!((order.qty, order.reserved_qty), (5 * QTY, 0));
+
+        let bytes = result.to_bytes();
+        assert_eq!(u64::from_le_bytes(bytes[72..80].try_into().unwrap()), QTY);
+    }
+
+    /// Reserve 2 from one order, then shrink the order to 1 behind the hold
+    fn shrunk_order(slab: &mut SlabState, policy: ShortfallPolicy) -> (u32, Result<CommitResult, PercolatorError>) {
+        slab.header.shortfall_policy = policy;
+        let maker = test_dlp(slab, 10);
+        let taker = test_dlp(slab, 20);
+        let order_idx = post(slab, maker, Side::Sell, PX, 2 * QTY, 0);
+        let resv = process_reserve(slab, taker, 0, Side::Buy, 2 * QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+        slab.set_order_qty(order_idx, QTY, 2 * QTY);
+        (order_idx, process_commit(slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000))
+    }
+
+    #[test]
+    fn test_shortfall_auto_reduce() {
+        let mut slab = new_test_slab();
+        let (order_idx, result) = shrunk_order(&mut slab, ShortfallPolicy::AutoReduce);
+        let result = result.unwrap();
+        assert_eq!((result.filled_qty, result.shortfall_qty, result.released_qty), (QTY, QTY, 0));
+        assert!(slab.get_order(order_idx).is_none());
+    }
+
+    #[test]
+    fn test_shortfall_strict_fail() {
+        let mut slab = new_test_slab();
+        let (order_idx, result) = shrunk_order(&mut slab, ShortfallPolicy::StrictFail);
+        assert_eq!(result.unwrap_err(), PercolatorError::ReservedSliceMissing);
+
+        // Nothing filled; the hold is gone and the order's reservation released
+        assert_eq!(slab.header.reservation_count, 0);
+        let order = slab.get_order(order_idx).unwrap();
+        assert_eq!((order.qty, order.reserved_qty), (QTY, 0));
+        assert_eq!(slab.header.position_count, 0);
+    }
+
+    /// Taker 20 buys 2 in this batch, then sells 3 back
+    fn roundtrip(slab: &mut SlabState, mode: ArgMode) -> Result<CommitResult, PercolatorError> {
+        slab.header.arg_mode = mode;
+        let maker = test_dlp(slab, 10);
+        let taker = test_dlp(slab, 20);
+
+        post(slab, maker, Side::Sell, PX, 2 * QTY, 0);
+        take(slab, taker, Side::Buy, 2 * QTY, PX, 1, 1_000);
+
+        post(slab, maker, Side::Buy, PX, 3 * QTY, 0);
+        let resv = process_reserve(slab, taker, 0, Side::Sell, 3 * QTY, PX, 1_000, [0; 32], 2, &TakerOptions::default(), 0).unwrap();
+        process_commit(slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000)
+    }
+
+    #[test]
+    fn test_arg_tax_on_overlap() {
+        let mut slab = new_test_slab();
+        let result = roundtrip(&mut slab, ArgMode::Tax).unwrap();
+
+        // 20 bps taker fee on 3, plus 50 bps ARG tax on the 2 that round-trip
+        let taker_fee = mul_u64(3 * QTY, PX) * 20 / 10_000;
+        let arg_tax = mul_u64(2 * QTY, PX) * 50 / 10_000;
+        assert_eq!(result.filled_qty, 3 * QTY);
+        assert_eq!(result.fees, taker_fee + arg_tax);
+
+        // The taker's cash carries both legs' fees and the tax (no PnL at one price)
+        let first_fee = mul_u64(2 * QTY, PX) * 20 / 10_000;
+        let taker = test_dlp(&mut slab, 20);
+        assert_eq!(slab.accounts[taker as usize].cash, -((first_fee + result.fees) as i128));
+    }
+
+    #[test]
+    fn test_commit_books_taker_pnl() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        slab.header.arg_enabled = false;
+
+        post(&mut slab, maker, Side::Sell, PX, 2 * QTY, 0);
+        let open = take(&mut slab, taker, Side::Buy, 2 * QTY, PX, 1, 1_000);
+        post(&mut slab, maker, Side::Buy, PX + TICK, 2 * QTY, 0);
+        let close = take(&mut slab, taker, Side::Sell, 2 * QTY, PX + TICK, 2, 2_000);
+
+        // $1 on 2 units, less both fees
+        assert_eq!(close.realized_pnl, mul_u64(2 * QTY, TICK) as i128);
+        let fees = (open.fees + close.fees) as i128;
+        assert_eq!(slab.accounts[taker as usize].cash, close.realized_pnl - fees);
+        assert_eq!(position_qty(&slab, taker), None);
+    }
+
+    #[test]
+    fn test_arg_clip_fills_only_new_risk() {
+        let mut slab = new_test_slab();
+        let result = roundtrip(&mut slab, ArgMode::Clip).unwrap();
+
+        assert_eq!(result.filled_qty, QTY);
+        // The clipped quantity went back to the maker's bid
+        let bid = slab.get_order(slab.instruments[0].bids_head).unwrap();
+        assert_eq!(bid.qty, 2 * QTY);
+        assert_eq!(bid.reserved_qty, 0);
+    }
+
+    #[test]
+    fn test_arg_reject() {
+        let mut slab = new_test_slab();
+        assert_eq!(roundtrip(&mut slab, ArgMode::Reject).unwrap_err(), PercolatorError::RoundtripDetected);
+        assert_eq!(slab.header.reservation_count, 0);
+    }
+
+    #[test]
+    fn test_arg_ledger_recycled_at_batch_open() {
+        let mut slab = new_test_slab();
+        slab.header.arg_mode = ArgMode::Reject;
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+
+        post(&mut slab, maker, Side::Sell, PX, QTY, 0);
+        take(&mut slab, taker, Side::Buy, QTY, PX, 1, 1_000);
+        process_batch_open(&mut slab, 0, 1_000).unwrap();
+        assert!(slab.aggressors.iter().all(|e| !e.used));
+
+        // Selling back in the next batch is not a roundtrip
+        post(&mut slab, maker, Side::Buy, PX, QTY, 1_000);
+        let result = take(&mut slab, taker, Side::Sell, QTY, PX, 2, 1_100);
+        assert_eq!(result.filled_qty, QTY);
+    }
+
+    #[test]
+    fn test_arg_ledger_full_rejects_commit() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        post(&mut slab, maker, Side::Sell, PX, QTY, 0);
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 10_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+
+        // Roundtrips cannot go untracked
+        slab.header.aggressor_freelist_head = SlabState::INVALID_INDEX;
+        assert_eq!(
+            process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap_err(),
+            PercolatorError::PoolFull
+        );
+
+        slab.header.arg_enabled = false;
+        assert_eq!(process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap().filled_qty, QTY);
+    }
+
+    #[test]
+    fn test_kill_band_uses_own_instrument_mark() {
+        let mut slab = new_test_slab();
+        let eth = process_add_instrument(&mut slab, *b"ETH-PERP", 1_000_000, 1_000, 1_000, 3_000_000_000).unwrap();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+
+        // A 10% ETH move does not kill a BTC commit
+        post(&mut slab, maker, Side::Sell, PX, 2 * QTY, 0);
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+        process_update_mark_price(&mut slab, eth, 3_300_000_000, 500).unwrap();
+        assert_eq!(process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap().filled_qty, QTY);
+
+        // A 2% BTC move does
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 2, &TakerOptions::default(), 0).unwrap();
+        process_update_mark_price(&mut slab, 0, 51_000_000_000, 500).unwrap();
+        assert_eq!(
+            process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap_err(),
+            PercolatorError::KillBandExceeded
+        );
+    }
+
+    #[test]
+    fn test_kill_band_measured_from_reserve_snapshot() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        post(&mut slab, maker, Side::Sell, PX, 2 * QTY, 0);
+
+        // Two 0.8% steps: each is inside the band, together they are not
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+        process_update_mark_price(&mut slab, 0, 50_400_000_000, 500).unwrap();
+        process_update_mark_price(&mut slab, 0, 50_800_000_000, 600).unwrap();
+        assert_eq!(
+            process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap_err(),
+            PercolatorError::KillBandExceeded
+        );
+        assert_eq!(slab.header.reservation_count, 0);
+
+        // A fresh reservation snapshots the new mark and commits
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 2, &TakerOptions::default(), 0).unwrap();
+        let snapshot = slab.reservations.iter().find(|r| r.used && r.hold_id == resv.hold_id).unwrap();
+        assert_eq!((snapshot.mark_px, snapshot.mark_ts), (50_800_000_000, 600));
+        assert_eq!(process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 1_000).unwrap().filled_qty, QTY);
     }
 }
@@ -0,0 +1,109 @@
+//! DLP registry instructions - add or remove designated LPs
+//!
+//! Only registered DLPs may post with `MakerClass::DLP` and skip the
+//! pending queue. The registry is managed by the LP owner.
+
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::{msg, pubkey::Pubkey};
+
+/// Process add DLP instruction
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `authority` - Signer of the instruction, must be the LP owner
+/// * `dlp` - Key of the maker to register
+pub fn process_add_dlp(
+    slab: &mut SlabState,
+    authority: &Pubkey,
+    dlp: &Pubkey,
+) -> Result<(), PercolatorError> {
+    if *authority != slab.header.lp_owner {
+        msg!("Error: Only the LP owner can add DLPs");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    if slab.is_dlp(dlp) {
+        msg!("Error: DLP already registered");
+        return Err(PercolatorError::DlpAlreadyRegistered);
+    }
+
+    if !slab.add_dlp(dlp) {
+        msg!("Error: DLP registry full");
+        return Err(PercolatorError::PoolFull);
+    }
+
+    slab.header.increment_seqno();
+    Ok(())
+}
+
+/// Process remove DLP instruction
+///
+/// Orders already resting live stay on the book; new orders from the
+/// removed maker go through the pending queue.
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `authority` - Signer of the instruction, must be the LP owner
+/// * `dlp` - Key of the maker to unregister
+pub fn process_remove_dlp(
+    slab: &mut SlabState,
+    authority: &Pubkey,
+    dlp: &Pubkey,
+) -> Result<(), PercolatorError> {
+    if *authority != slab.header.lp_owner {
+        msg!("Error: Only the LP owner can remove DLPs");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    if !slab.remove_dlp(dlp) {
+        msg!("Error: DLP not registered");
+        return Err(PercolatorError::DlpNotRegistered);
+    }
+
+    slab.header.increment_seqno();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::new_test_slab;
+
+    const LP_OWNER: Pubkey = [1; 32];
+    const MAKER: Pubkey = [10; 32];
+
+    #[test]
+    fn test_add_and_remove_dlp() {
+        let mut slab = new_test_slab();
+
+        process_add_dlp(&mut slab, &LP_OWNER, &MAKER).unwrap();
+        assert!(slab.is_dlp(&MAKER));
+        assert_eq!(
+            process_add_dlp(&mut slab, &LP_OWNER, &MAKER).unwrap_err(),
+            PercolatorError::DlpAlreadyRegistered
+        );
+
+        process_remove_dlp(&mut slab, &LP_OWNER, &MAKER).unwrap();
+        assert!(!slab.is_dlp(&MAKER));
+        assert_eq!(
+            process_remove_dlp(&mut slab, &LP_OWNER, &MAKER).unwrap_err(),
+            PercolatorError::DlpNotRegistered
+        );
+    }
+
+    #[test]
+    fn test_dlp_registry_requires_lp_owner() {
+        let mut slab = new_test_slab();
+
+        assert_eq!(
+            process_add_dlp(&mut slab, &MAKER, &MAKER).unwrap_err(),
+            PercolatorError::Unauthorized
+        );
+        process_add_dlp(&mut slab, &LP_OWNER, &MAKER).unwrap();
+        assert_eq!(
+            process_remove_dlp(&mut slab, &MAKER, &MAKER).unwrap_err(),
+            PercolatorError::Unauthorized
+        );
+    }
+}
@@ -1,25 +1,75 @@
-//! Funding rate instruction - updates funding rates and accrues funding payments
+//! Funding rate instruction - updates funding rates and settles funding payments
 //!
-//! Implements time-weighted funding rate calculations and applies funding
-//! payments to all open positions.
+//! The mark/index premium is sampled at every batch open into a
+//! time-weighted accumulator per instrument. Each funding interval charges
+//! the TWAP premium plus the slab's interest component, clamped to the
+//! per-interval cap, so the moment of the crank does not move the rate.
+//!
+//! A crank only moves the instrument's cumulative funding. Each position
+//! settles the difference to its own snapshot into account cash whenever
+//! it is touched (fill, liquidation, explicit settle), keeping the crank
+//! O(1) however many positions are open.
+//!
+//! Every update is appended to the slab's funding history, and each
+//! account keeps running totals of the funding it paid and received.
 
 use crate::state::SlabState;
 use percolator_common::*;
-use pinocchio::msg;
+use pinocchio::{msg, pubkey::Pubkey};
 
 /// Funding rate update interval (1 hour in milliseconds)
 pub const FUNDING_INTERVAL_MS: u64 = 3_600_000;
 
+/// Fold the current mark/index premium into the instrument's funding TWAP
+///
+/// Called at every batch open. The premium observed at the previous sample
+/// is held over the time since then, so a spike right before a sample gets
+/// no weight for the time already past. A stale mark stops the clock until
+/// the next fresh sample.
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `instrument_idx` - The instrument to sample
+/// * `current_ts` - Current timestamp
+pub fn sample_premium(slab: &mut SlabState, instrument_idx: u16, current_ts: u64) {
+    let max_staleness_ms = slab.header.oracle_max_staleness_ms;
+    let instr = match slab.get_instrument_mut(instrument_idx) {
+        Some(i) => i,
+        None => return,
+    };
+
+    let last_sample_ts = instr.premium_sample_ts;
+    if last_sample_ts != 0 && current_ts <= last_sample_ts {
+        return;
+    }
+
+    if last_sample_ts != 0 {
+        let elapsed = current_ts - last_sample_ts;
+        instr.premium_acc += instr.premium_last_bps as i128 * elapsed as i128;
+        instr.premium_acc_ms += elapsed;
+    }
+
+    let stale = instr.oracle != Pubkey::default()
+        && current_ts.saturating_sub(instr.mark_ts) > max_staleness_ms;
+    if stale || instr.index_price == 0 || instr.mark_px <= 0 {
+        instr.premium_sample_ts = 0;
+        return;
+    }
+
+    instr.premium_last_bps = calculate_premium_bps(instr.mark_px as u64, instr.index_price) as i64;
+    instr.premium_sample_ts = current_ts;
+}
+
 /// Process update funding instruction
 ///
-/// Updates the funding rate for an instrument and applies funding payments
-/// to all positions. Funding rate is calculated based on the difference
-/// between mark price and index price.
+/// Charges every funding interval elapsed since the last update, at the
+/// TWAP premium sampled over those intervals. A crank that is several
+/// intervals late charges each of them exactly once. The first crank on an
+/// instrument only starts its funding clock.
 ///
 /// # Arguments
 /// * `slab` - The slab state
 /// * `instrument_idx` - The instrument to update
-/// * `index_price` - Current index/spot price (1e6 scale)
 /// * `current_ts` - Current timestamp
 ///
 /// # Returns
@@ -27,7 +77,6 @@ pub const FUNDING_INTERVAL_MS: u64 = 3_600_000;
 pub fn process_update_funding(
     slab: &mut SlabState,
     instrument_idx: u16,
-    index_price: u64,
     current_ts: u64,
 ) -> Result<(), PercolatorError> {
     // Validate instrument exists
@@ -37,41 +86,65 @@ pub fn process_update_funding(
             PercolatorError::InvalidInstrument
         })?;
 
+    if instr.last_funding_ts == 0 {
+        if let Some(instr) = slab.get_instrument_mut(instrument_idx) {
+            instr.last_funding_ts = current_ts;
+            instr.premium_acc = 0;
+            instr.premium_acc_ms = 0;
+        }
+        slab.header.increment_seqno();
+        msg!("Funding clock started");
+        return Ok(());
+    }
+
     // Check if enough time has passed since last funding update
-    let last_funding_ts = instr.last_funding_ts;
-    let time_elapsed = current_ts.saturating_sub(last_funding_ts);
+    let time_elapsed = current_ts.saturating_sub(instr.last_funding_ts);
+    let intervals = time_elapsed / FUNDING_INTERVAL_MS;
 
-    if time_elapsed < FUNDING_INTERVAL_MS {
+    if intervals == 0 {
         msg!("Error: Funding interval not elapsed");
         return Err(PercolatorError::InvalidInstruction);
     }
 
-    // Get mark price
-    let mark_price = slab.header.mark_px as u64;
-    if mark_price == 0 {
-        msg!("Error: Mark price not set");
-        return Err(PercolatorError::InvalidPrice);
-    }
+    // Premium averaged over the samples since the last update
+    let twap_premium_bps = if instr.premium_acc_ms == 0 {
+        0
+    } else {
+        instr.premium_acc / instr.premium_acc_ms as i128
+    };
 
-    // Calculate funding rate
-    // Funding rate = (mark - index) / index * rate_factor
     // Positive rate means longs pay shorts
-    let funding_rate = calculate_funding_rate(mark_price, index_price, time_elapsed);
-
-    // Update cumulative funding
-    let old_cum_funding = instr.cum_funding;
-    let new_cum_funding = old_cum_funding + funding_rate;
+    let funding_rate = calculate_funding_rate(
+        twap_premium_bps,
+        slab.header.funding_interest_bps,
+        slab.header.funding_cap_bps,
+    );
+
+    // Update cumulative funding, once per elapsed interval. Positions
+    // settle against it lazily when they are next touched.
+    let new_cum_funding = instr.cum_funding + funding_rate * intervals as i128;
+    let next_funding_ts = instr.last_funding_ts + intervals * FUNDING_INTERVAL_MS;
+
+    let record = FundingRecord {
+        ts: next_funding_ts,
+        rate: funding_rate as i64,
+        premium_bps: twap_premium_bps as i64,
+        mark_px: instr.mark_px,
+        index_price: instr.index_price,
+        instrument_idx,
+        intervals: intervals.min(u16::MAX as u64) as u16,
+        _padding: [0; 4],
+    };
 
     // Apply funding rate to instrument
     if let Some(instr) = slab.get_instrument_mut(instrument_idx) {
         instr.funding_rate = funding_rate as i64;
         instr.cum_funding = new_cum_funding;
-        instr.index_price = index_price;
-        instr.last_funding_ts = current_ts;
+        instr.last_funding_ts = next_funding_ts;
+        instr.premium_acc = 0;
+        instr.premium_acc_ms = 0;
     }
-
-    // Apply funding to all positions for this instrument
-    apply_funding_to_positions(slab, instrument_idx, old_cum_funding, new_cum_funding)?;
+    slab.record_funding(record);
 
     // Update header timestamp
     slab.header.last_funding_ts = current_ts;
@@ -83,95 +156,110 @@ pub fn process_update_funding(
     Ok(())
 }
 
-/// Calculate funding rate based on mark/index price difference
-///
-/// # Arguments
-/// * `mark_price` - Current mark price (1e6 scale)
-/// * `index_price` - Current index/spot price (1e6 scale)
-/// * `time_elapsed_ms` - Time since last funding update
-///
-/// # Returns
-/// * Funding rate adjustment (signed, 1e6 scale)
-fn calculate_funding_rate(mark_price: u64, index_price: u64, time_elapsed_ms: u64) -> i128 {
+/// Premium of mark over index in basis points
+fn calculate_premium_bps(mark_price: u64, index_price: u64) -> i128 {
     if index_price == 0 {
         return 0;
     }
 
-    // Calculate premium/discount: (mark - index) / index
     let mark_i128 = mark_price as i128;
     let index_i128 = index_price as i128;
+    ((mark_i128 - index_i128) * 10_000) / index_i128
+}
 
-    // Premium in basis points * 1e4
-    let premium_bps = ((mark_i128 - index_i128) * 10_000) / index_i128;
-
-    // Time-weight: funding accrues over 8 hours typically
-    // Hourly rate = premium / 8
-    // Scale by time elapsed relative to 1 hour
-    let hourly_rate = premium_bps / 8;
+/// Calculate the funding rate for one interval
+///
+/// # Arguments
+/// * `twap_premium_bps` - Time-weighted premium over the interval (bps)
+/// * `interest_bps` - Interest-rate component per interval (bps)
+/// * `cap_bps` - Maximum absolute rate per interval (bps)
+///
+/// # Returns
+/// * Funding rate per interval (signed, bps)
+fn calculate_funding_rate(twap_premium_bps: i128, interest_bps: u64, cap_bps: u64) -> i128 {
+    // The premium converges over 8 hours, so each hourly interval charges 1/8
+    let premium_rate = twap_premium_bps / 8;
+    let cap = cap_bps as i128;
 
-    // Scale by actual time elapsed
-    let time_factor = (time_elapsed_ms as i128 * 1_000_000) / (FUNDING_INTERVAL_MS as i128);
-    
-    (hourly_rate * time_factor) / 1_000_000
+    (premium_rate + interest_bps as i128).clamp(-cap, cap)
 }
 
-/// Apply funding payments to all positions for an instrument
-fn apply_funding_to_positions(
-    slab: &mut SlabState,
-    instrument_idx: u16,
-    _old_cum_funding: i128,
-    new_cum_funding: i128,
-) -> Result<(), PercolatorError> {
-    let funding_delta = new_cum_funding - _old_cum_funding;
-
-    // Iterate through all accounts and their positions
-    for acc_idx in 0..slab.header.account_count {
-        // First pass: collect positions and calculate funding
-        let mut positions_to_update: [(u32, i128); 32] = [(SlabState::INVALID_INDEX, 0); 32];
-        let mut update_count = 0usize;
-        let mut funding_payment: i128 = 0;
-        
-        // Get position head
-        let pos_head = match slab.get_account(acc_idx as u32) {
-            Some(a) => a.position_head,
-            None => continue,
-        };
+/// Settle a position's accrued funding into its account's cash
+///
+/// Moves the position's funding snapshot up to the instrument's cumulative
+/// funding. Must run before the position's quantity changes.
+///
+/// # Returns
+/// * Funding paid by the account (negative = received)
+pub fn settle_position_funding(slab: &mut SlabState, pos_idx: u32) -> i128 {
+    let (account_idx, payment, cum_funding) = match slab.get_position(pos_idx) {
+        Some(pos) => match slab.get_instrument(pos.instrument_idx) {
+            Some(instr) => (
+                pos.account_idx,
+                calculate_funding_payment(pos.qty, instr.cum_funding, pos.last_funding),
+                instr.cum_funding,
+            ),
+            None => return 0,
+        },
+        None => return 0,
+    };
 
-        let mut pos_idx = pos_head;
-        while pos_idx != SlabState::INVALID_INDEX && update_count < 32 {
-            let (qty, instr_idx, next) = match slab.get_position(pos_idx) {
-                Some(p) => (p.qty, p.instrument_idx, p.next_in_account),
-                None => break,
-            };
-
-            if instr_idx == instrument_idx {
-                // Calculate funding payment for this position
-                let payment = calculate_funding_payment(qty, funding_delta, 0);
-                funding_payment += payment;
-                positions_to_update[update_count] = (pos_idx, new_cum_funding);
-                update_count += 1;
+    if let Some(pos) = slab.get_position_mut(pos_idx) {
+        pos.last_funding = cum_funding;
+    }
+    if payment != 0 {
+        if let Some(acc) = slab.get_account_mut(account_idx) {
+            acc.cash = acc.cash.saturating_sub(payment);
+            if payment > 0 {
+                acc.funding_paid += payment as u128;
+            } else {
+                acc.funding_received += payment.unsigned_abs();
             }
-
-            pos_idx = next;
         }
+    }
 
-        // Second pass: update positions
-        for i in 0..update_count {
-            let (idx, cum_funding) = positions_to_update[i];
-            if let Some(pos) = slab.get_position_mut(idx) {
-                pos.last_funding = cum_funding;
-            }
-        }
+    payment
+}
 
-        // Apply funding payment to account cash balance
-        if funding_payment != 0 {
-            if let Some(acc) = slab.get_account_mut(acc_idx as u32) {
-                acc.cash = acc.cash.saturating_sub(funding_payment);
-            }
-        }
+/// Settle accrued funding on every position of an account
+///
+/// # Returns
+/// * Total funding paid by the account (negative = received)
+pub fn settle_account_funding(slab: &mut SlabState, account_idx: u32) -> i128 {
+    let mut total = 0i128;
+    let mut pos_idx = match slab.get_account(account_idx) {
+        Some(acc) => acc.position_head,
+        None => return 0,
+    };
+
+    while let Some(next) = slab.get_position(pos_idx).map(|p| p.next_in_account) {
+        total += settle_position_funding(slab, pos_idx);
+        pos_idx = next;
     }
 
-    Ok(())
+    total
+}
+
+/// Process settle funding instruction
+///
+/// Realizes an account's accrued funding into its cash. Settlement only
+/// books what is already owed, so anyone may crank it.
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `account_idx` - Account to settle
+///
+/// # Returns
+/// * Total funding paid by the account (negative = received)
+pub fn process_settle_funding(slab: &mut SlabState, account_idx: u32) -> Result<i128, PercolatorError> {
+    if slab.get_account(account_idx).is_none() {
+        msg!("Error: Account not found");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let paid = settle_account_funding(slab, account_idx);
+    slab.header.increment_seqno();
+    Ok(paid)
 }
 
 /// Get pending funding payment for a position
@@ -196,27 +284,20 @@ pub fn get_pending_funding(slab: &SlabState, pos_idx: u32) -> Option<i128> {
 ///
 /// # Arguments
 /// * `slab` - The slab state
-/// * `index_prices` - Array of index prices for each instrument
 /// * `current_ts` - Current timestamp
 ///
 /// # Returns
 /// * Number of instruments updated
 pub fn process_batch_funding_update(
     slab: &mut SlabState,
-    index_prices: &[u64],
     current_ts: u64,
 ) -> Result<u16, PercolatorError> {
     let instrument_count = slab.header.instrument_count;
     let mut updated = 0u16;
 
     for i in 0..instrument_count {
-        let idx = i as usize;
-        if idx >= index_prices.len() {
-            break;
-        }
-
         // Try to update this instrument (may fail if interval not elapsed)
-        if process_update_funding(slab, i, index_prices[idx], current_ts).is_ok() {
+        if process_update_funding(slab, i, current_ts).is_ok() {
             updated += 1;
         }
     }
@@ -264,36 +345,174 @@ pub fn get_account_funding_summary(slab: &SlabState, account_idx: u32) -> Fundin
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::{new_test_slab, test_account};
+
+    const PX: i64 = 50_000_000_000;
+
+    fn open_position(slab: &mut SlabState, account_idx: u32, qty: i64) -> u32 {
+        let pos_idx = slab.alloc_position().unwrap();
+        let cum_funding = slab.instruments[0].cum_funding;
+        let head = slab.accounts[account_idx as usize].position_head;
+        let pos = slab.get_position_mut(pos_idx).unwrap();
+        pos.account_idx = account_idx;
+        pos.instrument_idx = 0;
+        pos.qty = qty;
+        pos.entry_px = PX as u64;
+        pos.last_funding = cum_funding;
+        pos.next_in_account = head;
+        slab.accounts[account_idx as usize].position_head = pos_idx;
+        pos_idx
+    }
 
     #[test]
     fn test_funding_rate_calculation() {
         // Mark above index = positive funding (longs pay)
-        let rate = calculate_funding_rate(
-            51_000_000_000, // $51,000 mark
-            50_000_000_000, // $50,000 index
-            FUNDING_INTERVAL_MS, // 1 hour
-        );
-        assert!(rate > 0, "Longs should pay when mark > index");
+        assert_eq!(calculate_premium_bps(51_000_000_000, 50_000_000_000), 200);
+        assert_eq!(calculate_funding_rate(200, 0, 50), 25);
 
         // Mark below index = negative funding (shorts pay)
-        let rate = calculate_funding_rate(
-            49_000_000_000, // $49,000 mark
-            50_000_000_000, // $50,000 index
-            FUNDING_INTERVAL_MS,
-        );
-        assert!(rate < 0, "Shorts should pay when mark < index");
+        assert_eq!(calculate_premium_bps(49_000_000_000, 50_000_000_000), -200);
+        assert_eq!(calculate_funding_rate(-200, 0, 50), -25);
 
-        // Equal prices = zero funding
-        let rate = calculate_funding_rate(
-            50_000_000_000,
-            50_000_000_000,
-            FUNDING_INTERVAL_MS,
-        );
-        assert_eq!(rate, 0);
+        // Equal prices = interest only
+        assert_eq!(calculate_funding_rate(0, 0, 50), 0);
+        assert_eq!(calculate_funding_rate(0, 1, 50), 1);
+
+        // Clamped per interval
+        assert_eq!(calculate_funding_rate(8_000, 0, 50), 50);
+        assert_eq!(calculate_funding_rate(-8_000, 1, 50), -50);
     }
 
     #[test]
     fn test_funding_interval() {
         assert_eq!(FUNDING_INTERVAL_MS, 3_600_000); // 1 hour
     }
+
+    #[test]
+    fn test_premium_twap_weights_by_time() {
+        let mut slab = new_test_slab();
+
+        // First sample only anchors the clock
+        slab.instruments[0].mark_px = PX + PX / 50;
+        sample_premium(&mut slab, 0, 1_000);
+        assert_eq!(slab.instruments[0].premium_acc_ms, 0);
+
+        // 2% premium for 3s, then flat for 1s
+        slab.instruments[0].mark_px = PX;
+        sample_premium(&mut slab, 0, 4_000);
+        sample_premium(&mut slab, 0, 5_000);
+
+        let instr = &slab.instruments[0];
+        assert_eq!(instr.premium_acc_ms, 4_000);
+        assert_eq!(instr.premium_acc / instr.premium_acc_ms as i128, 150);
+    }
+
+    #[test]
+    fn test_spike_before_sample_gets_no_past_weight() {
+        let mut slab = new_test_slab();
+        sample_premium(&mut slab, 0, 1_000);
+
+        // A spike just before a sample an hour later does not cover the hour
+        slab.instruments[0].mark_px = PX + PX / 10;
+        sample_premium(&mut slab, 0, 1_000 + FUNDING_INTERVAL_MS);
+        assert_eq!(slab.instruments[0].premium_acc, 0);
+        assert_eq!(slab.instruments[0].premium_acc_ms, FUNDING_INTERVAL_MS);
+    }
+
+    #[test]
+    fn test_crank_timing_does_not_move_rate() {
+        let mut slab = new_test_slab();
+        process_update_funding(&mut slab, 0, 1_000).unwrap();
+        sample_premium(&mut slab, 0, 1_000);
+        sample_premium(&mut slab, 0, 2_000);
+
+        // A mark spike right before the crank is not sampled
+        slab.instruments[0].mark_px = PX + PX / 10;
+        process_update_funding(&mut slab, 0, 1_000 + FUNDING_INTERVAL_MS).unwrap();
+        assert_eq!(slab.instruments[0].funding_rate, 0);
+    }
+
+    #[test]
+    fn test_late_crank_charges_each_interval_once() {
+        let mut slab = new_test_slab();
+        slab.header.funding_interest_bps = 1;
+        process_update_funding(&mut slab, 0, 1_000).unwrap();
+        assert_eq!(slab.instruments[0].cum_funding, 0);
+
+        // Three and a half intervals late
+        process_update_funding(&mut slab, 0, 1_000 + 7 * FUNDING_INTERVAL_MS / 2).unwrap();
+        assert_eq!(slab.instruments[0].cum_funding, 3);
+        assert_eq!(slab.instruments[0].last_funding_ts, 1_000 + 3 * FUNDING_INTERVAL_MS);
+
+        // The half interval is not charged again
+        assert_eq!(
+            process_update_funding(&mut slab, 0, 1_000 + 39 * FUNDING_INTERVAL_MS / 10),
+            Err(PercolatorError::InvalidInstruction)
+        );
+        process_update_funding(&mut slab, 0, 1_000 + 4 * FUNDING_INTERVAL_MS).unwrap();
+        assert_eq!(slab.instruments[0].cum_funding, 4);
+    }
+
+    #[test]
+    fn test_funding_updates_are_recorded() {
+        let mut slab = new_test_slab();
+        slab.header.funding_interest_bps = 1;
+        process_update_funding(&mut slab, 0, 1_000).unwrap();
+        assert_eq!(slab.header.funding_write_idx, 0);
+
+        slab.instruments[0].mark_px = PX + PX / 100;
+        sample_premium(&mut slab, 0, 1_000);
+        sample_premium(&mut slab, 0, 2_000);
+        process_update_funding(&mut slab, 0, 1_000 + 2 * FUNDING_INTERVAL_MS).unwrap();
+
+        assert_eq!(slab.header.funding_write_idx, 1);
+        let record = slab.funding_history[0];
+        assert_eq!(record.ts, 1_000 + 2 * FUNDING_INTERVAL_MS);
+        assert_eq!(record.premium_bps, 100);
+        assert_eq!(record.rate, 13);
+        assert_eq!(record.intervals, 2);
+        assert_eq!(record.mark_px, PX + PX / 100);
+        assert_eq!(record.index_price, PX as u64);
+    }
+
+    #[test]
+    fn test_crank_does_not_touch_positions() {
+        let mut slab = new_test_slab();
+        slab.header.funding_interest_bps = 2;
+        let long = test_account(&mut slab, 5);
+        let short = test_account(&mut slab, 6);
+        let long_pos = open_position(&mut slab, long, 10);
+        open_position(&mut slab, short, -10);
+
+        process_update_funding(&mut slab, 0, 1_000).unwrap();
+        process_update_funding(&mut slab, 0, 1_000 + FUNDING_INTERVAL_MS).unwrap();
+        assert_eq!(slab.instruments[0].cum_funding, 2);
+        assert_eq!(slab.accounts[long as usize].cash, 0);
+        assert_eq!(get_pending_funding(&slab, long_pos), Some(20));
+
+        // Settling books what is owed, once
+        assert_eq!(process_settle_funding(&mut slab, long), Ok(20));
+        assert_eq!(process_settle_funding(&mut slab, long), Ok(0));
+        assert_eq!(process_settle_funding(&mut slab, short), Ok(-20));
+        assert_eq!(slab.accounts[long as usize].cash, -20);
+        assert_eq!(slab.accounts[short as usize].cash, 20);
+        assert_eq!(slab.accounts[long as usize].funding_paid, 20);
+        assert_eq!(slab.accounts[short as usize].funding_received, 20);
+        assert_eq!(get_pending_funding(&slab, long_pos), Some(0));
+    }
+
+    #[test]
+    fn test_late_position_owes_only_later_funding() {
+        let mut slab = new_test_slab();
+        slab.instruments[0].cum_funding = 100;
+        let acct = test_account(&mut slab, 5);
+        open_position(&mut slab, acct, 10);
+
+        slab.instruments[0].cum_funding = 103;
+        assert_eq!(settle_account_funding(&mut slab, acct), 30);
+        assert_eq!(
+            process_settle_funding(&mut slab, 99),
+            Err(PercolatorError::InvalidAccount)
+        );
+    }
 }
@@ -71,6 +71,7 @@ mod initialize_tests {
         assert!(header.jit_penalty_on);
         assert_eq!(header.maker_rebate_min_ms, 50);
         assert!(header.arg_enabled);
+        assert_eq!(header.stp_mode, percolator_common::SelfTradePrevention::Skip);
     }
 
     #[test]
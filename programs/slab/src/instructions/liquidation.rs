@@ -1,42 +1,70 @@
 //! Liquidation instruction - executes liquidation of underwater positions
 //!
-//! Closes positions when account equity falls below maintenance margin.
-//! Implements price-banded liquidation to prevent excessive slippage.
-
+//! Reduces positions when account equity falls below maintenance margin,
+//! taking only as much as it needs to restore the margin plus a buffer.
+//! Closes trade against LIVE book depth within a price band around mark;
+//! a backstop takes over whatever the book cannot fill, as far as its own
+//! margin allows. Anything left stays open and is reported.
+
+use super::commit::{execute_fills, update_position};
+use super::funding::{get_pending_funding, settle_account_funding};
+use super::oracle::check_mark_fresh;
+use super::reserve::walk_and_reserve;
 use crate::state::SlabState;
 use percolator_common::*;
 use pinocchio::msg;
 
-/// Maximum price impact for liquidation (basis points)
+/// Maximum price impact for liquidation (basis points, caps `liq_band_bps`)
 pub const MAX_LIQUIDATION_IMPACT_BPS: u64 = 500; // 5% max impact
 
 /// Liquidation fee (basis points)
 pub const LIQUIDATION_FEE_BPS: u64 = 50; // 0.5% fee
 
+/// Most closes one liquidation call makes; a crank picks up what is left
+pub const MAX_LIQUIDATION_ROUNDS: u32 = 8;
+
 /// Result of liquidation attempt
 #[derive(Debug, Clone, Copy)]
 pub struct LiquidationResult {
-    /// Total positions liquidated
+    /// Positions closed entirely
     pub positions_closed: u32,
+    /// Partial closes that left a position open
+    pub positions_reduced: u32,
     /// Total quantity liquidated
     pub total_qty_liquidated: u64,
+    /// Part of the liquidated quantity taken by the backstop
+    pub backstop_qty: u64,
+    /// Quantity the last close could not place with the book or the backstop
+    pub last_unfilled_qty: u64,
     /// Total value liquidated
     pub total_value: u128,
     /// Total fees collected
     pub fees_collected: u128,
-    /// Remaining deficit (if any)
+    /// Deficit left uncovered when liquidation stopped (0 if covered)
     pub remaining_deficit: i128,
 }
 
 /// Process liquidation call from router
 ///
-/// Attempts to liquidate positions for an account that is below maintenance margin.
-/// Liquidation proceeds by market-selling positions within price bands.
+/// Attempts to restore an account that is below maintenance margin.
+/// Liquidation is incremental: the riskiest position (largest notional at
+/// mark) is reduced first, by the minimum quantity that brings equity back
+/// above maintenance margin plus `liq_buffer_bps`, and further positions are
+/// only touched if closing it entirely was not enough. Closes pay
+/// `LIQUIDATION_FEE_BPS` on the closed notional.
+///
+/// The quantity is sized as if it closed at mark. Each close sweeps LIVE
+/// depth no further than `liq_band_bps` from mark, and the backstop (the LP
+/// owner's account) takes any remainder at the band limit, up to what its
+/// free collateral carries at initial margin. If the fills come in worse than
+/// mark, the next round closes more, for at most `MAX_LIQUIDATION_ROUNDS`
+/// rounds; what nobody takes stays open.
 ///
 /// # Arguments
 /// * `slab` - The slab state
 /// * `account_idx` - Account to liquidate
-/// * `deficit_target` - Target deficit to cover
+/// * `deficit_target` - Deficit the router asks this slab to cover, if more
+///   than the slab's own margin shortfall (0 unless the router signed)
 /// * `current_ts` - Current timestamp
 ///
 /// # Returns
@@ -48,74 +76,106 @@ pub fn process_liquidation(
     current_ts: u64,
 ) -> Result<LiquidationResult, PercolatorError> {
     // Verify account exists and is underwater
-    let acc = slab.get_account(account_idx)
-        .ok_or_else(|| {
-            msg!("Error: Account not found");
-            PercolatorError::InvalidAccount
-        })?;
+    if slab.get_account(account_idx).is_none() {
+        msg!("Error: Account not found");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    // Accrued funding counts towards equity, so book it first
+    settle_account_funding(slab, account_idx);
+    let acc = slab.get_account(account_idx).ok_or(PercolatorError::InvalidAccount)?;
 
-    // Calculate current equity
+    // Calculate current equity against the current maintenance requirement
     let equity = calculate_account_equity(slab, account_idx);
-    let mm = acc.mm as i128;
+    let (_, mm) = recalculate_margin_requirements(slab, account_idx);
 
-    if equity >= mm {
+    if equity >= mm as i128 {
         msg!("Error: Account not below maintenance margin");
         return Err(PercolatorError::InvalidAccount);
     }
 
+    // Every position is closed against its instrument's mark, which must be fresh
+    let mut pos_idx = acc.position_head;
+    while let Some(pos) = slab.get_position(pos_idx) {
+        check_mark_fresh(slab, pos.instrument_idx, current_ts)?;
+        pos_idx = pos.next_in_account;
+    }
+
     let mut result = LiquidationResult {
         positions_closed: 0,
+        positions_reduced: 0,
         total_qty_liquidated: 0,
+        backstop_qty: 0,
+        last_unfilled_qty: 0,
         total_value: 0,
         fees_collected: 0,
-        remaining_deficit: deficit_target,
+        remaining_deficit: 0,
     };
 
-    // Get position list head
-    let mut pos_idx = acc.position_head;
-    let mark_px = slab.header.mark_px as u64;
-
-    // Iterate through positions and liquidate
-    while pos_idx != SlabState::INVALID_INDEX && result.remaining_deficit > 0 {
-        let pos = match slab.get_position(pos_idx) {
-            Some(p) => p,
-            None => break,
-        };
-
-        let qty = pos.qty;
-        let _instrument_idx = pos.instrument_idx;
-        let entry_px = pos.entry_px;
-        let next_pos = pos.next_in_account;
-
-        if qty == 0 {
-            pos_idx = next_pos;
-            continue;
+    // Liquidation aims for MM plus the buffer; a larger router target is
+    // covered on top of that
+    let target_bps = slab.header.mmr_bps + slab.header.liq_buffer_bps;
+    let extra_deficit = deficit_target
+        .saturating_sub(margin_shortfall(slab, account_idx, target_bps))
+        .max(0);
+
+    for round in 0..=MAX_LIQUIDATION_ROUNDS {
+        let deficit = margin_shortfall(slab, account_idx, target_bps).saturating_add(extra_deficit);
+        if deficit <= 0 {
+            break;
         }
 
-        // Calculate liquidation price with price band
-        let liq_price = calculate_liquidation_price(mark_px, qty > 0);
-
-        // Calculate position value
-        let abs_qty = qty.abs() as u64;
-        let position_value = mul_u64(abs_qty, liq_price);
+        // Fills worse than mark can leave each round barely ahead of the
+        // last; stop and report rather than run out of compute
+        if round == MAX_LIQUIDATION_ROUNDS {
+            msg!("Liquidation round limit reached");
+            result.remaining_deficit = deficit;
+            break;
+        }
 
-        // Calculate realized PnL
-        let realized_pnl = calculate_pnl(qty, entry_px, liq_price);
+        let pos_idx = match riskiest_position(slab, account_idx) {
+            Some(idx) => idx,
+            None => {
+                result.remaining_deficit = deficit;
+                break;
+            }
+        };
+        let pos = slab.get_position(pos_idx).ok_or(PercolatorError::PositionNotFound)?;
+        let abs_qty = pos.qty.unsigned_abs();
+        let instrument_idx = pos.instrument_idx;
+        let mark_px = instrument_mark_px(slab, instrument_idx);
+        let lot = slab.get_instrument(instrument_idx).map(|i| i.lot).unwrap_or(1).max(1);
+
+        // Each unit closed releases its requirement and pays the fee
+        let close_qty = if target_bps > LIQUIDATION_FEE_BPS && mark_px > 0 {
+            let gain_per_unit = mul_u64(mark_px, target_bps - LIQUIDATION_FEE_BPS);
+            let qty = (deficit as u128 * 10_000).div_ceil(gain_per_unit);
+            let lots = qty.div_ceil(lot as u128);
+            u64::try_from(lots * lot as u128).unwrap_or(u64::MAX).min(abs_qty)
+        } else {
+            abs_qty
+        };
 
-        // Calculate liquidation fee
-        let fee = (position_value * LIQUIDATION_FEE_BPS as u128) / 10_000;
+        let (closed_qty, notional, backstop_qty, fee) =
+            close_position_for_liquidation(slab, account_idx, pos_idx, close_qty, current_ts)?;
+        result.last_unfilled_qty = close_qty - closed_qty;
 
-        // Close the position
-        close_position_for_liquidation(slab, account_idx, pos_idx, liq_price, current_ts)?;
+        // Neither the book nor the backstop could take anything
+        if notional == 0 {
+            result.remaining_deficit = deficit;
+            break;
+        }
 
         // Update result
-        result.positions_closed += 1;
-        result.total_qty_liquidated += abs_qty;
-        result.total_value += position_value;
+        if closed_qty == abs_qty {
+            result.positions_closed += 1;
+        } else {
+            result.positions_reduced += 1;
+        }
+        result.total_qty_liquidated += closed_qty;
+        result.backstop_qty += backstop_qty;
+        result.total_value += notional;
         result.fees_collected += fee;
-        result.remaining_deficit -= realized_pnl;
-
-        pos_idx = next_pos;
     }
 
     // Recalculate margin requirements first (needs immutable borrow)
@@ -123,8 +183,6 @@ pub fn process_liquidation(
     
     // Update account state (needs mutable borrow)
     if let Some(acc) = slab.get_account_mut(account_idx) {
-        // Deduct fees from cash
-        acc.cash = acc.cash.saturating_sub(result.fees_collected as i128);
         acc.im = new_im;
         acc.mm = new_mm;
     }
@@ -144,7 +202,6 @@ fn calculate_account_equity(slab: &SlabState, account_idx: u32) -> i128 {
     };
 
     let mut equity = acc.cash;
-    let mark_px = slab.header.mark_px as u64;
 
     // Add unrealized PnL from all positions
     let mut pos_idx = acc.position_head;
@@ -154,68 +211,195 @@ fn calculate_account_equity(slab: &SlabState, account_idx: u32) -> i128 {
             None => break,
         };
 
+        let mark_px = instrument_mark_px(slab, pos.instrument_idx);
         let unrealized_pnl = calculate_pnl(pos.qty, pos.entry_px, mark_px);
         equity += unrealized_pnl;
 
+        // Funding accrued but not yet settled into cash
+        equity -= get_pending_funding(slab, pos_idx).unwrap_or(0);
+
         pos_idx = pos.next_in_account;
     }
 
     equity
 }
 
-/// Calculate liquidation price with price band protection
-fn calculate_liquidation_price(mark_px: u64, is_long: bool) -> u64 {
-    let max_impact = (mark_px * MAX_LIQUIDATION_IMPACT_BPS) / 10_000;
+/// Mark price of a position's instrument (0 if the instrument is unknown)
+fn instrument_mark_px(slab: &SlabState, instrument_idx: u16) -> u64 {
+    slab.get_instrument(instrument_idx)
+        .map(|i| i.mark_px as u64)
+        .unwrap_or(0)
+}
+
+/// Worst price a liquidation close may trade at: `band_bps` below mark when
+/// selling a long, above mark when buying back a short
+fn band_limit_px(mark_px: u64, band_bps: u64, is_long: bool) -> u64 {
+    let max_impact = (mark_px * band_bps) / 10_000;
 
     if is_long {
-        // Selling long position - price may slip down
         mark_px.saturating_sub(max_impact)
     } else {
-        // Buying back short position - price may slip up
         mark_px.saturating_add(max_impact)
     }
 }
 
-/// Close a position during liquidation
+/// Notional of a position at its instrument's mark
+fn position_notional(slab: &SlabState, pos: &Position) -> u128 {
+    mul_u64(pos.qty.unsigned_abs(), instrument_mark_px(slab, pos.instrument_idx))
+}
+
+/// Equity missing to cover a margin requirement of `bps` of the account's
+/// notional (negative if the account has more than enough)
+fn margin_shortfall(slab: &SlabState, account_idx: u32, bps: u64) -> i128 {
+    let acc = match slab.get_account(account_idx) {
+        Some(a) => a,
+        None => return 0,
+    };
+
+    let mut notional = 0u128;
+    let mut pos_idx = acc.position_head;
+    while let Some(pos) = slab.get_position(pos_idx) {
+        notional += position_notional(slab, pos);
+        pos_idx = pos.next_in_account;
+    }
+
+    (notional * bps as u128 / 10_000) as i128 - calculate_account_equity(slab, account_idx)
+}
+
+/// Open position with the largest notional at mark (first in the list on ties)
+fn riskiest_position(slab: &SlabState, account_idx: u32) -> Option<u32> {
+    let acc = slab.get_account(account_idx)?;
+
+    let mut riskiest: Option<(u32, u128)> = None;
+    let mut pos_idx = acc.position_head;
+    while let Some(pos) = slab.get_position(pos_idx) {
+        let notional = position_notional(slab, pos);
+        if pos.qty != 0 && riskiest.is_none_or(|(_, best)| notional > best) {
+            riskiest = Some((pos_idx, notional));
+        }
+        pos_idx = pos.next_in_account;
+    }
+
+    riskiest.map(|(idx, _)| idx)
+}
+
+/// Close `qty` of a position during liquidation
+///
+/// The close is a taker order for the liquidated account that reserves LIVE
+/// depth up to the band limit and fills it through the commit path, so each
+/// maker takes the other side. Whatever the book cannot fill is taken by the
+/// backstop at the band limit, up to its margin capacity (nothing if the LP
+/// owner has no account on the slab or the account is the backstop itself);
+/// the rest is left open. Realized PnL and
+/// the liquidation fee on the closed notional are booked to the account's
+/// cash.
+///
+/// Returns (closed_qty, closed_notional, backstop_qty, fee)
 fn close_position_for_liquidation(
     slab: &mut SlabState,
     account_idx: u32,
     pos_idx: u32,
-    close_price: u64,
+    qty: u64,
     current_ts: u64,
-) -> Result<(), PercolatorError> {
+) -> Result<(u64, u128, u64, u128), PercolatorError> {
     let pos = slab.get_position(pos_idx)
         .ok_or(PercolatorError::PositionNotFound)?;
 
-    let qty = pos.qty;
+    let is_long = pos.qty > 0;
     let instrument_idx = pos.instrument_idx;
-
-    // Record liquidation trade
-    let trade = Trade {
-        ts: current_ts,
-        order_id_maker: 0, // Market order
-        order_id_taker: 0, // Liquidation
+    let (side, backstop_side) = if is_long { (Side::Sell, Side::Buy) } else { (Side::Buy, Side::Sell) };
+    let mark_px = instrument_mark_px(slab, instrument_idx);
+    let limit_px = band_limit_px(mark_px, slab.header.liq_band_bps, is_long);
+
+    // Sweep the book inside the band (the account's own orders are skipped)
+    let (reserved_qty, _, _, slice_head, _) = walk_and_reserve(
+        slab,
+        account_idx,
         instrument_idx,
-        side: if qty > 0 { Side::Sell } else { Side::Buy },
-        _padding: [0; 5],
-        price: close_price,
-        qty: qty.abs() as u64,
-        hash: [0; 32],
-        reveal_ms: 0,
+        side,
+        qty,
+        limit_px,
+        SelfTradePrevention::Skip,
+        current_ts,
+    )?;
+    let (filled_qty, mut notional, _, mut realized_pnl) =
+        execute_fills(slab, slice_head, account_idx, side, 0, reserved_qty, current_ts)?;
+
+    // Backstop: the LP owner's account, if it has one, takes what it can
+    // carry at the band limit
+    let remainder = qty - filled_qty;
+    let backstop_idx = if remainder > 0 {
+        slab.find_account(&slab.header.lp_owner).filter(|&idx| idx != account_idx)
+    } else {
+        None
+    };
+    let mut backstop_qty = match backstop_idx {
+        Some(idx) => remainder.min(backstop_capacity(slab, idx, instrument_idx, mark_px)),
+        None => 0,
     };
-    slab.record_trade(trade);
+    if backstop_qty < remainder {
+        msg!("Liquidation remainder left open");
+    }
+    match backstop_idx {
+        Some(backstop_idx) if backstop_qty > 0 => {
+            let backstop_pnl =
+                update_position(slab, backstop_idx, instrument_idx, backstop_side, backstop_qty as i64, limit_px)?;
+            if let Some(backstop) = slab.get_account_mut(backstop_idx) {
+                backstop.cash += backstop_pnl;
+            }
+            realized_pnl += update_position(slab, account_idx, instrument_idx, side, backstop_qty as i64, limit_px)?;
+            notional += mul_u64(backstop_qty, limit_px);
+
+            // Record backstop trade
+            let trade = Trade {
+                ts: current_ts,
+                order_id_maker: 0, // Backstop
+                order_id_taker: 0, // Liquidation
+                instrument_idx,
+                side,
+                _padding: [0; 5],
+                price: limit_px,
+                qty: backstop_qty,
+                hash: [0; 32],
+                reveal_ms: 0,
+            };
+            slab.record_trade(trade);
+        }
+        _ => backstop_qty = 0,
+    }
+
+    let fee = notional * LIQUIDATION_FEE_BPS as u128 / 10_000;
+    if let Some(acc) = slab.get_account_mut(account_idx) {
+        acc.cash += realized_pnl - fee as i128;
+    }
+    slab.header.increment_seqno();
+    slab.refresh_quote_cache(instrument_idx);
 
-    // Remove position from account's linked list
-    unlink_position_from_account(slab, account_idx, pos_idx);
+    Ok((filled_qty + backstop_qty, notional, backstop_qty, fee))
+}
 
-    // Free the position
-    slab.free_position(pos_idx);
+/// Largest quantity the backstop can take on while staying above initial
+/// margin, in whole lots
+///
+/// Sized at mark on free collateral alone: the band discount it buys at is
+/// not counted, and neither is netting against positions it already holds.
+fn backstop_capacity(slab: &SlabState, backstop_idx: u32, instrument_idx: u16, mark_px: u64) -> u64 {
+    let free = -margin_shortfall(slab, backstop_idx, slab.header.imr_bps);
+    if free <= 0 {
+        return 0;
+    }
 
-    Ok(())
+    let margin_per_unit = mul_u64(mark_px, slab.header.imr_bps);
+    if margin_per_unit == 0 {
+        return u64::MAX;
+    }
+    let lot = slab.get_instrument(instrument_idx).map(|i| i.lot).unwrap_or(1).max(1);
+    let qty = u64::try_from(free as u128 * 10_000 / margin_per_unit).unwrap_or(u64::MAX);
+    qty - qty % lot
 }
 
 /// Remove position from account's position linked list
-fn unlink_position_from_account(slab: &mut SlabState, account_idx: u32, pos_idx: u32) {
+pub(crate) fn unlink_position_from_account(slab: &mut SlabState, account_idx: u32, pos_idx: u32) {
     let acc = match slab.get_account(account_idx) {
         Some(a) => a,
         None => return,
@@ -260,7 +444,6 @@ fn recalculate_margin_requirements(slab: &SlabState, account_idx: u32) -> (u128,
         None => return (0, 0),
     };
 
-    let mark_px = slab.header.mark_px as u64;
     let imr_bps = slab.header.imr_bps;
     let mmr_bps = slab.header.mmr_bps;
 
@@ -274,16 +457,10 @@ fn recalculate_margin_requirements(slab: &SlabState, account_idx: u32) -> (u128,
             None => break,
         };
 
-        // Get instrument contract size (default to 1e6)
-        let contract_size = slab.get_instrument(pos.instrument_idx)
-            .map(|i| i.contract_size)
-            .unwrap_or(1_000_000) as u64;
-
-        let im = calculate_im(pos.qty, contract_size, mark_px, imr_bps);
-        let mm = calculate_mm(pos.qty, contract_size, mark_px, mmr_bps);
-
-        total_im += im;
-        total_mm += mm;
+        // Requirements are a share of the notional at mark, in cash units
+        let notional = position_notional(slab, pos);
+        total_im += notional * imr_bps as u128 / 10_000;
+        total_mm += notional * mmr_bps as u128 / 10_000;
 
         pos_idx = pos.next_in_account;
     }
@@ -293,13 +470,12 @@ fn recalculate_margin_requirements(slab: &SlabState, account_idx: u32) -> (u128,
 
 /// Check if account is liquidatable
 pub fn is_liquidatable(slab: &SlabState, account_idx: u32) -> bool {
-    let acc = match slab.get_account(account_idx) {
-        Some(a) => a,
-        None => return false,
-    };
+    if slab.get_account(account_idx).is_none() {
+        return false;
+    }
 
-    let equity = calculate_account_equity(slab, account_idx);
-    equity < acc.mm as i128
+    let (_, mm) = recalculate_margin_requirements(slab, account_idx);
+    calculate_account_equity(slab, account_idx) < mm as i128
 }
 
 /// Get liquidation preview for an account
@@ -321,10 +497,9 @@ pub struct LiquidationPreview {
 pub fn get_liquidation_preview(slab: &SlabState, account_idx: u32) -> Option<LiquidationPreview> {
     let acc = slab.get_account(account_idx)?;
     let equity = calculate_account_equity(slab, account_idx);
-    let mm = acc.mm;
+    let (_, mm) = recalculate_margin_requirements(slab, account_idx);
     let deficit = (mm as i128) - equity;
 
-    let mark_px = slab.header.mark_px as u64;
     let mut position_count = 0u32;
     let mut total_value = 0u128;
 
@@ -336,7 +511,7 @@ pub fn get_liquidation_preview(slab: &SlabState, account_idx: u32) -> Option<Liq
         };
 
         position_count += 1;
-        total_value += mul_u64(pos.qty.abs() as u64, mark_px);
+        total_value += position_notional(slab, pos);
         pos_idx = pos.next_in_account;
     }
 
@@ -352,25 +527,59 @@ pub fn get_liquidation_preview(slab: &SlabState, account_idx: u32) -> Option<Liq
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instructions::{find_position, process_add_instrument, process_place_order, OrderParams};
+    use crate::tests::{new_test_slab, test_account, test_dlp};
+
+    const BTC: u64 = 1_000_000; // one unit (1e6 scale)
+    const MARK: u64 = 50_000_000_000;
+    const USD: i128 = 1_000_000_000_000; // $1 in cash units
+
+    /// Open a position at the instrument's mark
+    fn open(slab: &mut SlabState, account_idx: u32, instrument_idx: u16, qty: i64) -> u32 {
+        let idx = slab.alloc_position().unwrap();
+        let entry_px = instrument_mark_px(slab, instrument_idx);
+        let head = slab.accounts[account_idx as usize].position_head;
+        let pos = slab.get_position_mut(idx).unwrap();
+        pos.account_idx = account_idx;
+        pos.instrument_idx = instrument_idx;
+        pos.qty = qty;
+        pos.entry_px = entry_px;
+        pos.next_in_account = head;
+        slab.accounts[account_idx as usize].position_head = idx;
+        idx
+    }
 
-    #[test]
-    fn test_liquidation_price_long() {
-        let mark = 50_000_000_000u64; // $50,000
-        let liq_px = calculate_liquidation_price(mark, true);
-        
-        // Should be 5% below mark
-        let expected = mark - (mark * MAX_LIQUIDATION_IMPACT_BPS) / 10_000;
-        assert_eq!(liq_px, expected);
+    /// Rest a BTC bid (a DLP's posts live, anyone else's waits for the batch)
+    fn bid(slab: &mut SlabState, account_idx: u32, price: u64, qty: u64, maker_class: MakerClass) -> u32 {
+        let params = OrderParams {
+            instrument_idx: 0,
+            side: Side::Buy,
+            price,
+            qty,
+            tif: TimeInForce::GTC,
+            maker_class,
+            flags: 0,
+        };
+        process_place_order(slab, account_idx, &params, 0).unwrap().order_idx
+    }
+
+    fn position_qty(slab: &SlabState, account_idx: u32) -> i64 {
+        find_position(slab, account_idx, 0)
+            .and_then(|idx| slab.get_position(idx))
+            .map_or(0, |p| p.qty)
+    }
+
+    /// Positions across all accounts net to zero
+    fn assert_net_zero(slab: &SlabState) {
+        let net: i64 = (0..slab.header.account_count as u32).map(|acct| position_qty(slab, acct)).sum();
+        assert_eq!(net, 0);
     }
 
     #[test]
-    fn test_liquidation_price_short() {
-        let mark = 50_000_000_000u64; // $50,000
-        let liq_px = calculate_liquidation_price(mark, false);
-        
-        // Should be 5% above mark
-        let expected = mark + (mark * MAX_LIQUIDATION_IMPACT_BPS) / 10_000;
-        assert_eq!(liq_px, expected);
+    fn test_band_limit_px() {
+        assert_eq!(band_limit_px(MARK, 500, true), 47_500_000_000);
+        assert_eq!(band_limit_px(MARK, 500, false), 52_500_000_000);
+        assert_eq!(band_limit_px(MARK, 0, true), MARK);
     }
 
     #[test]
@@ -378,4 +587,216 @@ mod tests {
         // 0.5% fee
         assert_eq!(LIQUIDATION_FEE_BPS, 50);
     }
+
+    #[test]
+    fn test_partial_liquidation_restores_buffer() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 20);
+        let order_idx = bid(&mut slab, maker, MARK, 10 * BTC, MakerClass::DLP);
+        let acct = test_account(&mut slab, 10);
+        let pos_idx = open(&mut slab, acct, 0, 10 * BTC as i64);
+        open(&mut slab, maker, 0, -10 * BTC as i64);
+
+        // $500k notional: MM is $12,500, MM plus the 1% buffer $17,500
+        slab.accounts[acct as usize].cash = 12_000 * USD;
+        assert!(is_liquidatable(&slab, acct));
+
+        let result = process_liquidation(&mut slab, acct, 0, 0).unwrap();
+
+        // 3 BTC would leave equity short of the buffer, 4 restores it
+        assert_eq!((result.positions_closed, result.positions_reduced), (0, 1));
+        assert_eq!((result.total_qty_liquidated, result.backstop_qty), (4 * BTC, 0));
+        assert_eq!(result.fees_collected as i128, 1_000 * USD);
+        assert_eq!(result.remaining_deficit, 0);
+        assert_eq!(slab.get_position(pos_idx).unwrap().qty, 6 * BTC as i64);
+        assert_eq!(slab.accounts[acct as usize].cash, 11_000 * USD);
+        assert_eq!(margin_shortfall(&slab, acct, 350), -500 * USD);
+        assert!(!is_liquidatable(&slab, acct));
+
+        // The maker bought what was sold
+        assert_eq!(slab.get_order(order_idx).unwrap().qty, 6 * BTC);
+        assert_eq!(position_qty(&slab, maker), -6 * BTC as i64);
+        assert_net_zero(&slab);
+    }
+
+    #[test]
+    fn test_riskiest_position_reduced_first() {
+        let mut slab = new_test_slab();
+        let eth = process_add_instrument(&mut slab, *b"ETH-PERP", 1_000_000, 1_000_000, 1_000_000, 3_000_000_000).unwrap();
+        let maker = test_dlp(&mut slab, 20);
+        bid(&mut slab, maker, MARK, 10 * BTC, MakerClass::DLP);
+        let acct = test_account(&mut slab, 10);
+        let eth_pos = open(&mut slab, acct, eth, -10 * BTC as i64);
+        let btc_pos = open(&mut slab, acct, 0, 10 * BTC as i64);
+        slab.accounts[acct as usize].cash = 12_000 * USD;
+
+        let result = process_liquidation(&mut slab, acct, 0, 0).unwrap();
+
+        assert_eq!((result.positions_closed, result.positions_reduced), (0, 1));
+        assert!(slab.get_position(btc_pos).unwrap().qty < 10 * BTC as i64);
+        assert_eq!(slab.get_position(eth_pos).unwrap().qty, -10 * BTC as i64);
+        assert!(!is_liquidatable(&slab, acct));
+    }
+
+    #[test]
+    fn test_liquidation_covers_router_deficit() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 20);
+        bid(&mut slab, maker, MARK, 10 * BTC, MakerClass::DLP);
+        let acct = test_account(&mut slab, 10);
+        let pos_idx = open(&mut slab, acct, 0, 10 * BTC as i64);
+        slab.accounts[acct as usize].cash = 12_000 * USD;
+
+        // The router needs $9,000 more than the slab's own $5,500 shortfall
+        let result = process_liquidation(&mut slab, acct, 14_500 * USD, 0).unwrap();
+        assert_eq!(result.total_qty_liquidated, 10 * BTC);
+        assert_eq!(result.positions_closed, 1);
+        assert!(slab.get_position(pos_idx).is_none());
+        assert_eq!(slab.accounts[acct as usize].position_head, SlabState::INVALID_INDEX);
+    }
+
+    #[test]
+    fn test_extreme_deficit_target_does_not_overflow() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 20);
+        bid(&mut slab, maker, MARK, 10 * BTC, MakerClass::DLP);
+        let acct = test_account(&mut slab, 10);
+        open(&mut slab, acct, 0, 10 * BTC as i64);
+        slab.accounts[acct as usize].cash = 12_000 * USD;
+
+        // A negative target is ignored; the slab still covers its own shortfall
+        let result = process_liquidation(&mut slab, acct, i128::MIN, 0).unwrap();
+        assert!(result.total_qty_liquidated > 0);
+        assert!(result.total_qty_liquidated < 10 * BTC);
+    }
+
+    #[test]
+    fn test_sweep_stays_in_band_and_on_live_depth() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 20);
+        let retail = test_account(&mut slab, 30);
+        let near = bid(&mut slab, maker, MARK - BTC * 1_000, 2 * BTC, MakerClass::DLP);
+        let pending = bid(&mut slab, retail, MARK, 10 * BTC, MakerClass::REG);
+        let outside = bid(&mut slab, maker, 45_000_000_000, 10 * BTC, MakerClass::DLP);
+
+        let acct = test_account(&mut slab, 10);
+        open(&mut slab, acct, 0, 5 * BTC as i64);
+        open(&mut slab, maker, 0, -5 * BTC as i64);
+        slab.accounts[acct as usize].cash = -10_000 * USD;
+
+        // The backstop holds enough to carry 4 BTC at initial margin
+        let lp_owner = slab.header.lp_owner;
+        let backstop = slab.get_or_create_account(&lp_owner).unwrap();
+        slab.accounts[backstop as usize].cash = 10_000 * USD;
+
+        let result = process_liquidation(&mut slab, acct, 0, 0).unwrap();
+        assert_eq!(result.positions_closed, 1);
+        assert_eq!((result.total_qty_liquidated, result.backstop_qty), (5 * BTC, 3 * BTC));
+        assert_eq!(result.last_unfilled_qty, 0);
+
+        // Only the live bid inside the band traded
+        assert!(slab.get_order(near).is_none());
+        assert_eq!(slab.get_order(pending).unwrap().qty, 10 * BTC);
+        assert_eq!(slab.get_order(outside).unwrap().qty, 10 * BTC);
+
+        // The backstop (LP owner) bought the rest at the band limit
+        let backstop_pos = slab.get_position(find_position(&slab, backstop, 0).unwrap()).unwrap();
+        assert_eq!((backstop_pos.qty, backstop_pos.entry_px), (3 * BTC as i64, 47_500_000_000));
+        assert_eq!(position_qty(&slab, maker), -3 * BTC as i64);
+        assert_net_zero(&slab);
+
+        // $2,000 + $7,500 lost on the closes, $1,202.50 in fees
+        assert_eq!(result.fees_collected as i128, 1_202_500_000_000_000);
+        assert_eq!(result.remaining_deficit, 20_702_500_000_000_000);
+    }
+
+    #[test]
+    fn test_liquidation_stops_at_round_limit() {
+        let mut slab = new_test_slab();
+        slab.header.liq_buffer_bps = 0;
+        let maker = test_dlp(&mut slab, 20);
+        bid(&mut slab, maker, MARK - MARK / 50, 100 * BTC, MakerClass::DLP);
+
+        // $1,000 short of MM; each BTC sold 2% under mark frees only $5
+        let acct = test_account(&mut slab, 10);
+        let pos_idx = open(&mut slab, acct, 0, 100 * BTC as i64);
+        slab.accounts[acct as usize].cash = 124_000 * USD;
+
+        let result = process_liquidation(&mut slab, acct, 0, 0).unwrap();
+        assert_eq!(result.positions_reduced, MAX_LIQUIDATION_ROUNDS);
+        assert!(result.total_qty_liquidated < 10 * BTC);
+        assert!(result.remaining_deficit > 0);
+        assert_eq!(slab.get_position(pos_idx).unwrap().qty, (100 * BTC - result.total_qty_liquidated) as i64);
+        assert!(is_liquidatable(&slab, acct));
+    }
+
+    #[test]
+    fn test_close_settles_each_fill_at_its_price() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 20);
+        bid(&mut slab, maker, MARK, BTC, MakerClass::DLP);
+        bid(&mut slab, maker, MARK - BTC, 2 * BTC, MakerClass::DLP);
+
+        let acct = test_account(&mut slab, 10);
+        open(&mut slab, acct, 0, 3 * BTC as i64);
+        open(&mut slab, maker, 0, -3 * BTC as i64);
+        slab.accounts[acct as usize].cash = -10_000 * USD;
+
+        // 2 BTC sold $1 under entry: exactly -$2, not the rounded VWAP
+        let result = process_liquidation(&mut slab, acct, 0, 0).unwrap();
+        assert_eq!((result.positions_closed, result.total_qty_liquidated), (1, 3 * BTC));
+        let fee = result.fees_collected as i128;
+        assert_eq!(slab.accounts[acct as usize].cash, -10_000 * USD - 2 * USD - fee);
+        assert_net_zero(&slab);
+    }
+
+    #[test]
+    fn test_backstop_without_margin_leaves_remainder_open() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 20);
+        bid(&mut slab, maker, MARK - BTC * 1_000, 2 * BTC, MakerClass::DLP);
+
+        let acct = test_account(&mut slab, 10);
+        let pos_idx = open(&mut slab, acct, 0, 5 * BTC as i64);
+        open(&mut slab, maker, 0, -5 * BTC as i64);
+        slab.accounts[acct as usize].cash = -10_000 * USD;
+
+        // The book takes 2 BTC; the unfunded backstop takes nothing
+        let result = process_liquidation(&mut slab, acct, 0, 0).unwrap();
+        assert_eq!((result.positions_closed, result.positions_reduced), (0, 1));
+        assert_eq!((result.total_qty_liquidated, result.backstop_qty), (2 * BTC, 0));
+        assert_eq!(result.last_unfilled_qty, 3 * BTC);
+        assert!(result.remaining_deficit > 0);
+        assert_eq!(slab.get_position(pos_idx).unwrap().qty, 3 * BTC as i64);
+
+        // Looking for the backstop does not open an account for it
+        assert!(slab.find_account(&slab.header.lp_owner).is_none());
+        assert_net_zero(&slab);
+    }
+
+    #[test]
+    fn test_backstop_cannot_absorb_itself() {
+        let mut slab = new_test_slab();
+        let lp_owner = slab.header.lp_owner;
+        let acct = slab.get_or_create_account(&lp_owner).unwrap();
+        let pos_idx = open(&mut slab, acct, 0, 5 * BTC as i64);
+        slab.accounts[acct as usize].cash = -10_000 * USD;
+
+        // Nothing on the book and no one else to take it: the position stays
+        let result = process_liquidation(&mut slab, acct, 0, 0).unwrap();
+        assert_eq!(result.total_qty_liquidated, 0);
+        assert!(result.remaining_deficit > 0);
+        assert_eq!(slab.get_position(pos_idx).unwrap().qty, 5 * BTC as i64);
+    }
+
+    #[test]
+    fn test_healthy_account_not_liquidated() {
+        let mut slab = new_test_slab();
+        let acct = test_account(&mut slab, 10);
+        open(&mut slab, acct, 0, 10 * BTC as i64);
+        slab.accounts[acct as usize].cash = 13_000 * USD;
+
+        assert!(!is_liquidatable(&slab, acct));
+        assert_eq!(process_liquidation(&mut slab, acct, 0, 0).unwrap_err(), PercolatorError::InvalidAccount);
+    }
 }
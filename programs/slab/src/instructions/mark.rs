@@ -0,0 +1,262 @@
+//! Composite mark price
+//!
+//! The mark is the median of three inputs, so no single stale or
+//! manipulated source can move it on its own:
+//! - the oracle index plus an EMA of the book basis (mid - index)
+//! - the mid of the best available bid and ask in the quote cache
+//! - the last trade price
+//!
+//! A missing or crossed book side or a stale last trade falls back to the
+//! index-based price, which then decides the median together with the
+//! remaining input.
+//!
+//! Basis samples are clamped to a band around the index and weighted by the
+//! time since the previous sample, so cranking the mark repeatedly cannot
+//! drag the EMA faster than the clock allows.
+
+use crate::state::{SlabState, POOL_TRADES};
+
+/// Time for a basis sample to reach full weight in the EMA (ms)
+pub const BASIS_EMA_WINDOW_MS: u64 = 60_000;
+
+/// Largest basis a sample may carry, relative to the index (basis points)
+pub const MAX_BASIS_BPS: i128 = 500;
+
+/// Number of most recent trades searched for the instrument's last trade
+pub const LAST_TRADE_SCAN: usize = 64;
+
+/// Compute an instrument's composite mark from a fresh index price
+///
+/// Feeds the current book basis into the instrument's EMA, weighted by the
+/// time since the last sample, and records the index price. The first sample
+/// only starts the EMA clock.
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `instrument_idx` - Instrument to price
+/// * `index_px` - Oracle index price (1e6 scale)
+/// * `current_ts` - Current timestamp (ms)
+///
+/// # Returns
+/// * The composite mark (1e6 scale), or `None` for an unknown instrument
+pub fn update_composite_mark(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    index_px: u64,
+    current_ts: u64,
+) -> Option<u64> {
+    let mid = book_mid(slab, instrument_idx);
+    let last = last_trade_px(slab, instrument_idx, current_ts, slab.header.oracle_max_staleness_ms);
+
+    let instr = slab.get_instrument_mut(instrument_idx)?;
+    if let Some(mid) = mid {
+        let max_basis = index_px as i128 * MAX_BASIS_BPS / 10_000;
+        let sample = (mid as i128 - index_px as i128).clamp(-max_basis, max_basis);
+        if instr.basis_ts != 0 {
+            let elapsed = current_ts.saturating_sub(instr.basis_ts);
+            instr.basis_ema = update_basis_ema(instr.basis_ema as i128, sample, elapsed) as i64;
+        }
+        if current_ts > instr.basis_ts {
+            instr.basis_ts = current_ts;
+        }
+    }
+    instr.index_price = index_px;
+
+    let fair = (index_px as i128 + instr.basis_ema as i128).max(1) as u64;
+    Some(median3(fair, mid.unwrap_or(fair), last.unwrap_or(fair)))
+}
+
+/// Mid of the best available bid and ask, if both sides are quoted
+///
+/// Reads the instrument's quote cache, which skips fully reserved levels.
+/// A crossed or locked book has no meaningful mid and yields `None`.
+pub fn book_mid(slab: &SlabState, instrument_idx: u16) -> Option<u64> {
+    slab.get_instrument(instrument_idx)?;
+    let cache = &slab.quote_caches[instrument_idx as usize];
+    let (bid, ask) = (cache.best_bids[0], cache.best_asks[0]);
+    if bid.avail_qty <= 0 || ask.avail_qty <= 0 || bid.px >= ask.px {
+        return None;
+    }
+    Some((bid.px + (ask.px - bid.px) / 2) as u64)
+}
+
+/// Price of the instrument's most recent trade, if it is no older than `max_age_ms`
+pub fn last_trade_px(
+    slab: &SlabState,
+    instrument_idx: u16,
+    current_ts: u64,
+    max_age_ms: u64,
+) -> Option<u64> {
+    let written = slab.header.trade_write_idx as usize;
+    let scan = written.min(POOL_TRADES).min(LAST_TRADE_SCAN);
+
+    (1..=scan)
+        .map(|back| &slab.trades[(written - back) % POOL_TRADES])
+        .find(|trade| trade.instrument_idx == instrument_idx)
+        .filter(|trade| current_ts.saturating_sub(trade.ts) <= max_age_ms)
+        .map(|trade| trade.price)
+}
+
+/// Fold a basis sample into the EMA, weighted by the time it covers
+fn update_basis_ema(ema: i128, sample: i128, elapsed_ms: u64) -> i128 {
+    let weight = elapsed_ms.min(BASIS_EMA_WINDOW_MS) as i128;
+    ema + (sample - ema) * weight / BASIS_EMA_WINDOW_MS as i128
+}
+
+/// Median of three prices
+fn median3(a: u64, b: u64, c: u64) -> u64 {
+    a.max(b).min(a.min(b).max(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{process_place_order, process_reserve, OrderParams, TakerOptions};
+    use crate::tests::{new_test_slab, test_dlp};
+    use percolator_common::*;
+
+    const PX: u64 = 50_000_000_000;
+    const TICK: u64 = 1_000_000;
+
+    fn post(slab: &mut SlabState, side: Side, price: u64) {
+        let maker = test_dlp(slab, 10);
+        let params = OrderParams {
+            instrument_idx: 0,
+            side,
+            price,
+            qty: 1_000_000,
+            tif: TimeInForce::GTC,
+            maker_class: MakerClass::DLP,
+            flags: 0,
+        };
+        process_place_order(slab, maker, &params, 0).unwrap();
+    }
+
+    fn trade(slab: &mut SlabState, price: u64, ts: u64) {
+        slab.record_trade(Trade {
+            ts,
+            order_id_maker: 1,
+            order_id_taker: 1,
+            instrument_idx: 0,
+            side: Side::Buy,
+            _padding: [0; 5],
+            price,
+            qty: 1_000_000,
+            hash: [0; 32],
+            reveal_ms: 0,
+        });
+    }
+
+    #[test]
+    fn test_median3() {
+        assert_eq!(median3(1, 2, 3), 2);
+        assert_eq!(median3(3, 1, 2), 2);
+        assert_eq!(median3(2, 3, 1), 2);
+        assert_eq!(median3(5, 5, 1), 5);
+    }
+
+    #[test]
+    fn test_mark_without_book_or_trades_is_index() {
+        let mut slab = new_test_slab();
+        assert_eq!(update_composite_mark(&mut slab, 0, PX, 0), Some(PX));
+        assert_eq!(slab.instruments[0].basis_ema, 0);
+        assert_eq!(slab.instruments[0].index_price, PX);
+    }
+
+    #[test]
+    fn test_mark_follows_book_and_trades() {
+        let mut slab = new_test_slab();
+        post(&mut slab, Side::Buy, PX + 90 * TICK);
+        post(&mut slab, Side::Sell, PX + 110 * TICK);
+        trade(&mut slab, PX + 100 * TICK, 7_000);
+
+        // The first sample starts the clock; 6s later a basis of $100 enters
+        // the EMA at 10%, and book and trade agree on +$100
+        update_composite_mark(&mut slab, 0, PX, 1_000);
+        assert_eq!(slab.instruments[0].basis_ema, 0);
+        assert_eq!(update_composite_mark(&mut slab, 0, PX, 7_000), Some(PX + 100 * TICK));
+        assert_eq!(slab.instruments[0].basis_ema, 10 * TICK as i64);
+    }
+
+    #[test]
+    fn test_basis_ema_weighted_by_time() {
+        let mut slab = new_test_slab();
+        post(&mut slab, Side::Buy, PX + 90 * TICK);
+        post(&mut slab, Side::Sell, PX + 110 * TICK);
+        update_composite_mark(&mut slab, 0, PX, 1_000);
+
+        // Cranking again within the same millisecond adds no weight
+        for _ in 0..10 {
+            update_composite_mark(&mut slab, 0, PX, 1_000);
+        }
+        assert_eq!(slab.instruments[0].basis_ema, 0);
+
+        // A full window carries the whole sample
+        update_composite_mark(&mut slab, 0, PX, 1_000 + BASIS_EMA_WINDOW_MS);
+        assert_eq!(slab.instruments[0].basis_ema, 100 * TICK as i64);
+    }
+
+    #[test]
+    fn test_basis_sample_is_clamped() {
+        let mut slab = new_test_slab();
+        post(&mut slab, Side::Buy, PX + PX / 5);
+        post(&mut slab, Side::Sell, PX + PX / 5 + 2 * TICK);
+        update_composite_mark(&mut slab, 0, PX, 1_000);
+        update_composite_mark(&mut slab, 0, PX, 1_000 + BASIS_EMA_WINDOW_MS);
+
+        // A 20% basis enters as the 5% cap
+        let max_basis = PX as i128 * MAX_BASIS_BPS / 10_000;
+        assert_eq!(slab.instruments[0].basis_ema as i128, max_basis);
+    }
+
+    #[test]
+    fn test_single_manipulated_input_is_ignored() {
+        let mut slab = new_test_slab();
+        post(&mut slab, Side::Buy, PX - TICK);
+        post(&mut slab, Side::Sell, PX + TICK);
+
+        // A wash trade 10% away does not move the mark
+        trade(&mut slab, PX + PX / 10, 0);
+        assert_eq!(update_composite_mark(&mut slab, 0, PX, 0), Some(PX));
+    }
+
+    #[test]
+    fn test_book_mid_skips_reserved_levels() {
+        let mut slab = new_test_slab();
+        post(&mut slab, Side::Buy, PX - TICK);
+        post(&mut slab, Side::Sell, PX + TICK);
+        post(&mut slab, Side::Sell, PX + 3 * TICK);
+        assert_eq!(book_mid(&slab, 0), Some(PX));
+
+        // Reserving the whole best ask moves the mid to the next level
+        let taker = test_dlp(&mut slab, 20);
+        let opts = TakerOptions { tif: TimeInForce::IOC, flags: 0, stp: SelfTradePrevention::SlabDefault };
+        process_reserve(&mut slab, taker, 0, Side::Buy, 1_000_000, PX + TICK, 1_000, [0; 32], 1, &opts, 0).unwrap();
+        assert_eq!(book_mid(&slab, 0), Some(PX + TICK));
+    }
+
+    #[test]
+    fn test_crossed_book_has_no_mid() {
+        let mut slab = new_test_slab();
+        post(&mut slab, Side::Buy, PX - TICK);
+        post(&mut slab, Side::Sell, PX + TICK);
+
+        // A crossed or locked top of book is ignored, not read as the bid
+        slab.quote_caches[0].best_bids[0].px = (PX + 2 * TICK) as i64;
+        assert_eq!(book_mid(&slab, 0), None);
+        slab.quote_caches[0].best_bids[0].px = (PX + TICK) as i64;
+        assert_eq!(book_mid(&slab, 0), None);
+        assert_eq!(update_composite_mark(&mut slab, 0, PX, 0), Some(PX));
+    }
+
+    #[test]
+    fn test_stale_last_trade_is_ignored() {
+        let mut slab = new_test_slab();
+        trade(&mut slab, PX + PX / 10, 0);
+        let max_age = slab.header.oracle_max_staleness_ms;
+
+        assert_eq!(last_trade_px(&slab, 0, max_age, max_age), Some(PX + PX / 10));
+        assert_eq!(last_trade_px(&slab, 0, max_age + 1, max_age), None);
+        assert_eq!(last_trade_px(&slab, 1, 0, max_age), None);
+    }
+}
@@ -0,0 +1,277 @@
+//! Mass quote instruction - replace an account's ladder on one instrument
+//!
+//! Cancels every resting order the account has on the instrument (reserved
+//! quantity and orders inside the top-K freeze stay on the book) and then
+//! posts a fresh set of levels. A level that fails validation is reported
+//! and skipped; the rest of the ladder still goes up.
+
+use super::batch_open::is_order_frozen;
+use super::place_order::{post_order, OrderParams};
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::msg;
+
+/// Maximum number of levels in one mass quote (keeps the call inside the CU budget)
+pub const MAX_QUOTE_LEVELS: usize = 32;
+
+/// One level of a mass quote ladder
+#[derive(Debug, Clone, Copy)]
+pub struct QuoteLevel {
+    /// Order side
+    pub side: Side,
+    /// Limit price (1e6 scale)
+    pub price: u64,
+    /// Quantity (1e6 scale)
+    pub qty: u64,
+    /// Order flags (ORDER_FLAG_POST_ONLY, ORDER_FLAG_REDUCE_ONLY)
+    pub flags: u8,
+}
+
+/// Result of a mass quote operation
+#[derive(Debug, Clone, Copy)]
+pub struct MassQuoteResult {
+    /// Number of orders removed from the book
+    pub cancelled: u32,
+    /// Number of orders cut back to their reserved quantity
+    pub trimmed: u32,
+    /// Number of levels posted
+    pub posted: u32,
+    /// Per-level outcome, in the order the levels were given
+    pub level_errors: [Option<PercolatorError>; MAX_QUOTE_LEVELS],
+}
+
+/// Process mass quote instruction
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `account_idx` - Account index of the maker
+/// * `instrument_idx` - Instrument to quote
+/// * `maker_class` - Maker class the new levels are posted with
+/// * `levels` - New ladder (at most MAX_QUOTE_LEVELS)
+/// * `current_ts` - Current timestamp (ms)
+///
+/// # Returns
+/// * `MassQuoteResult` with cancel counts and the outcome of each level
+pub fn process_mass_quote(
+    slab: &mut SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    maker_class: MakerClass,
+    levels: &[QuoteLevel],
+    current_ts: u64,
+) -> Result<MassQuoteResult, PercolatorError> {
+    if levels.len() > MAX_QUOTE_LEVELS {
+        msg!("Error: Too many quote levels");
+        return Err(PercolatorError::QuoteLevelLimitExceeded);
+    }
+
+    if slab.get_account(account_idx).is_none() {
+        msg!("Error: Invalid account index");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    if slab.get_instrument(instrument_idx).is_none() {
+        msg!("Error: Invalid instrument index");
+        return Err(PercolatorError::InvalidInstrument);
+    }
+
+    let (cancelled, trimmed) = cancel_account_orders(slab, account_idx, instrument_idx, current_ts);
+
+    let mut result = MassQuoteResult {
+        cancelled,
+        trimmed,
+        posted: 0,
+        level_errors: [None; MAX_QUOTE_LEVELS],
+    };
+
+    for (i, level) in levels.iter().enumerate() {
+        let params = OrderParams {
+            instrument_idx,
+            side: level.side,
+            price: level.price,
+            qty: level.qty,
+            tif: TimeInForce::GTC,
+            maker_class,
+            flags: level.flags,
+        };
+
+        match post_order(slab, account_idx, &params, current_ts) {
+            Ok(_) => result.posted += 1,
+            Err(e) => {
+                msg!("Mass quote level rejected");
+                result.level_errors[i] = Some(e);
+            }
+        }
+    }
+
+    slab.header.increment_seqno();
+    slab.refresh_quote_cache(instrument_idx);
+    Ok(result)
+}
+
+/// Cancel the unreserved part of every order an account has on an instrument
+///
+/// Walks the account's own order list, so the cost is bounded by the
+/// maker's orders rather than the book. Frozen orders are left untouched.
+/// Returns (orders removed, orders cut back to their reserved quantity).
+fn cancel_account_orders(
+    slab: &mut SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    current_ts: u64,
+) -> (u32, u32) {
+    let mut cancelled = 0u32;
+    let mut trimmed = 0u32;
+
+    let mut order_idx = match slab.get_account(account_idx) {
+        Some(acc) => acc.order_head,
+        None => return (0, 0),
+    };
+
+    while let Some(order) = slab.get_order(order_idx).copied() {
+        let next = order.next_in_account;
+
+        if order.instrument_idx == instrument_idx && !is_order_frozen(&order, current_ts) {
+            if order.reserved_qty == 0 {
+                slab.remove_order_from_book(order_idx);
+                slab.free_order(order_idx);
+                cancelled += 1;
+            } else if order.qty > order.reserved_qty {
+                slab.set_order_qty(order_idx, order.reserved_qty, order.reserved_qty);
+                trimmed += 1;
+            }
+        }
+
+        order_idx = next;
+    }
+
+    (cancelled, trimmed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{process_add_instrument, process_reserve, TakerOptions};
+    use crate::tests::{new_test_slab, test_dlp};
+
+    const PX: u64 = 50_000_000_000;
+    const TICK: u64 = 1_000_000;
+    const QTY: u64 = 1_000_000;
+
+    fn level(side: Side, price: u64, qty: u64) -> QuoteLevel {
+        QuoteLevel { side, price, qty, flags: 0 }
+    }
+
+    fn ladder() -> [QuoteLevel; 4] {
+        [
+            level(Side::Buy, PX - TICK, QTY),
+            level(Side::Buy, PX - 2 * TICK, 2 * QTY),
+            level(Side::Sell, PX + TICK, QTY),
+            level(Side::Sell, PX + 2 * TICK, 2 * QTY),
+        ]
+    }
+
+    #[test]
+    fn test_mass_quote_replaces_ladder() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+
+        let first = process_mass_quote(&mut slab, maker, 0, MakerClass::DLP, &ladder(), 0).unwrap();
+        assert_eq!(first.posted, 4);
+        assert_eq!(first.cancelled, 0);
+
+        let second = process_mass_quote(&mut slab, maker, 0, MakerClass::DLP, &ladder()[..2], 0).unwrap();
+        assert_eq!(second.cancelled, 4);
+        assert_eq!(second.posted, 2);
+        assert_eq!(slab.instruments[0].asks_head, SlabState::INVALID_INDEX);
+        assert_eq!(slab.get_order(slab.instruments[0].bids_head).unwrap().price, PX - TICK);
+    }
+
+    #[test]
+    fn test_mass_quote_leaves_other_accounts() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let other = test_dlp(&mut slab, 20);
+
+        process_mass_quote(&mut slab, other, 0, MakerClass::DLP, &ladder()[..1], 0).unwrap();
+        let result = process_mass_quote(&mut slab, maker, 0, MakerClass::DLP, &[], 0).unwrap();
+        assert_eq!(result.cancelled, 0);
+        assert_ne!(slab.instruments[0].bids_head, SlabState::INVALID_INDEX);
+    }
+
+    /// Number of orders on an account's order list
+    fn account_orders(slab: &SlabState, account_idx: u32) -> usize {
+        let mut count = 0;
+        let mut order_idx = slab.accounts[account_idx as usize].order_head;
+        while let Some(order) = slab.get_order(order_idx) {
+            assert_eq!(order.account_idx, account_idx);
+            count += 1;
+            order_idx = order.next_in_account;
+        }
+        count
+    }
+
+    #[test]
+    fn test_mass_quote_walks_only_the_account_on_the_instrument() {
+        let mut slab = new_test_slab();
+        let eth = process_add_instrument(&mut slab, *b"ETH-PERP", 1_000_000, TICK, QTY, 3_000_000_000).unwrap();
+        let maker = test_dlp(&mut slab, 10);
+
+        process_mass_quote(&mut slab, maker, 0, MakerClass::DLP, &ladder(), 0).unwrap();
+        let eth_levels = [level(Side::Buy, 2_900_000_000, QTY), level(Side::Sell, 3_100_000_000, QTY)];
+        process_mass_quote(&mut slab, maker, eth, MakerClass::DLP, &eth_levels, 0).unwrap();
+        assert_eq!(account_orders(&slab, maker), 6);
+
+        let result = process_mass_quote(&mut slab, maker, eth, MakerClass::DLP, &[], 0).unwrap();
+        assert_eq!(result.cancelled, 2);
+        assert_eq!(account_orders(&slab, maker), 4);
+        assert_ne!(slab.instruments[0].asks_head, SlabState::INVALID_INDEX);
+    }
+
+    #[test]
+    fn test_mass_quote_keeps_reserved_qty() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+
+        let levels = [level(Side::Sell, PX, 3 * QTY)];
+        process_mass_quote(&mut slab, maker, 0, MakerClass::DLP, &levels, 0).unwrap();
+        let order_idx = slab.instruments[0].asks_head;
+        process_reserve(&mut slab, taker, 0, Side::Buy, QTY, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+
+        let result = process_mass_quote(&mut slab, maker, 0, MakerClass::DLP, &[], 0).unwrap();
+        assert_eq!(result.trimmed, 1);
+        let order = slab.get_order(order_idx).unwrap();
+        assert_eq!(order.qty, QTY);
+        assert_eq!(order.reserved_qty, QTY);
+    }
+
+    #[test]
+    fn test_mass_quote_reports_bad_levels() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+
+        let levels = [
+            level(Side::Buy, PX - TICK, QTY),
+            level(Side::Buy, PX - TICK / 2, QTY),
+            level(Side::Sell, PX + TICK, 0),
+        ];
+        let result = process_mass_quote(&mut slab, maker, 0, MakerClass::DLP, &levels, 0).unwrap();
+        assert_eq!(result.posted, 1);
+        assert_eq!(result.level_errors[0], None);
+        assert_eq!(result.level_errors[1], Some(PercolatorError::PriceNotAligned));
+        assert_eq!(result.level_errors[2], Some(PercolatorError::InvalidQuantity));
+    }
+
+    #[test]
+    fn test_mass_quote_level_limit() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+
+        let levels = [level(Side::Buy, PX - TICK, QTY); MAX_QUOTE_LEVELS + 1];
+        assert_eq!(
+            process_mass_quote(&mut slab, maker, 0, MakerClass::DLP, &levels, 0).unwrap_err(),
+            PercolatorError::QuoteLevelLimitExceeded
+        );
+    }
+}
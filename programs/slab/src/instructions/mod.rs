@@ -9,6 +9,13 @@ pub mod liquidation;
 pub mod funding;
 pub mod add_instrument;
 pub mod insurance;
+pub mod place_order;
+pub mod update_param;
+pub mod amend_order;
+pub mod mass_quote;
+pub mod dlp;
+pub mod oracle;
+pub mod mark;
 
 pub use initialize::*;
 pub use reserve::*;
@@ -19,6 +26,13 @@ pub use liquidation::*;
 pub use funding::*;
 pub use add_instrument::*;
 pub use insurance::*;
+pub use place_order::*;
+pub use update_param::*;
+pub use amend_order::*;
+pub use mass_quote::*;
+pub use dlp::*;
+pub use oracle::*;
+pub use mark::*;
 
 /// Instruction discriminator
 #[repr(u8)]
@@ -52,4 +66,26 @@ pub enum SlabInstruction {
     CancelInsuranceWithdrawal = 12,
     /// Update insurance config (Phase 5)
     UpdateInsuranceConfig = 13,
+    /// Place a resting maker order
+    PlaceOrder = 14,
+    /// Update a slab-wide setting (LP owner only)
+    UpdateParam = 15,
+    /// Amend a resting maker order's price or size
+    AmendOrder = 16,
+    /// Replace an account's resting orders on an instrument with a new ladder
+    MassQuote = 17,
+    /// Register a designated LP (LP owner only)
+    AddDlp = 18,
+    /// Unregister a designated LP (LP owner only)
+    RemoveDlp = 19,
+    /// Bind an instrument to an oracle price account (LP owner only)
+    SetOracle = 20,
+    /// Refresh an instrument's mark from its oracle
+    UpdateMark = 21,
+    /// Settle an account's accrued funding into its cash
+    SettleFunding = 22,
+    /// Release expired reservations (permissionless keeper crank)
+    SweepExpired = 23,
+    /// Cancel a single resting maker order (order owner only)
+    CancelOrder = 24,
 }
@@ -0,0 +1,250 @@
+//! Oracle instructions - bind instruments to price feeds and refresh marks
+//!
+//! Each instrument names one oracle price account. Marks are only ever
+//! computed from that account's index price (see `mark`), subject to the
+//! slab's staleness and confidence limits.
+
+use super::mark::update_composite_mark;
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::{msg, pubkey::Pubkey};
+
+/// Process set oracle instruction
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `authority` - Signer of the instruction, must be the LP owner
+/// * `instrument_idx` - Instrument to bind
+/// * `oracle` - Oracle price account for the instrument
+/// * `oracle_program` - Program that owns the oracle price account
+pub fn process_set_oracle(
+    slab: &mut SlabState,
+    authority: &Pubkey,
+    instrument_idx: u16,
+    oracle: &Pubkey,
+    oracle_program: &Pubkey,
+) -> Result<(), PercolatorError> {
+    if *authority != slab.header.lp_owner {
+        msg!("Error: Only the LP owner can set an oracle");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    if *oracle == Pubkey::default() {
+        msg!("Error: Oracle account cannot be empty");
+        return Err(PercolatorError::InvalidOracle);
+    }
+
+    let instr = slab.get_instrument_mut(instrument_idx).ok_or_else(|| {
+        msg!("Error: Invalid instrument index");
+        PercolatorError::InvalidInstrument
+    })?;
+    instr.oracle = *oracle;
+    instr.oracle_program = *oracle_program;
+
+    slab.header.increment_seqno();
+    Ok(())
+}
+
+/// Read an instrument's oracle price, checking the account and the slab's limits
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `instrument_idx` - Instrument whose oracle is read
+/// * `oracle_key` - Key of the supplied oracle account
+/// * `oracle_owner` - Owner of the supplied oracle account
+/// * `oracle_data` - Data of the supplied oracle account
+/// * `current_ts` - Current timestamp (ms)
+pub fn read_instrument_oracle(
+    slab: &SlabState,
+    instrument_idx: u16,
+    oracle_key: &Pubkey,
+    oracle_owner: &Pubkey,
+    oracle_data: &[u8],
+    current_ts: u64,
+) -> Result<OraclePrice, PercolatorError> {
+    let instr = slab.get_instrument(instrument_idx).ok_or_else(|| {
+        msg!("Error: Invalid instrument index");
+        PercolatorError::InvalidInstrument
+    })?;
+
+    if instr.oracle == Pubkey::default()
+        || instr.oracle != *oracle_key
+        || instr.oracle_program != *oracle_owner
+    {
+        msg!("Error: Oracle account does not match the instrument");
+        return Err(PercolatorError::InvalidOracle);
+    }
+
+    load_oracle_price(oracle_owner, oracle_data, current_ts, &slab.header.oracle_limits()).inspect_err(|e| {
+        match e {
+            PercolatorError::OracleStale => msg!("Error: Oracle price is stale"),
+            PercolatorError::OracleConfidenceTooWide => msg!("Error: Oracle confidence too wide"),
+            _ => msg!("Error: Oracle price unavailable"),
+        }
+    })
+}
+
+/// Process update mark instruction
+///
+/// Refreshes an instrument's composite mark from its oracle index. Anyone
+/// may crank it.
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `instrument_idx` - Instrument to update
+/// * `oracle_key` - Key of the supplied oracle account
+/// * `oracle_owner` - Owner of the supplied oracle account
+/// * `oracle_data` - Data of the supplied oracle account
+/// * `current_ts` - Current timestamp (ms)
+///
+/// # Returns
+/// * The new mark price (1e6 scale)
+pub fn process_update_mark(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    oracle_key: &Pubkey,
+    oracle_owner: &Pubkey,
+    oracle_data: &[u8],
+    current_ts: u64,
+) -> Result<u64, PercolatorError> {
+    let price = read_instrument_oracle(slab, instrument_idx, oracle_key, oracle_owner, oracle_data, current_ts)?;
+
+    // An older publish than the current mark would step the mark back in time
+    if price.publish_ms < slab.instruments[instrument_idx as usize].mark_ts {
+        msg!("Error: Oracle price older than current mark");
+        return Err(PercolatorError::OracleStale);
+    }
+
+    let mark = update_composite_mark(slab, instrument_idx, price.price, current_ts)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+    if let Some(instr) = slab.get_instrument_mut(instrument_idx) {
+        instr.update_mark_px(mark as i64, price.publish_ms);
+    }
+
+    slab.header.increment_seqno();
+    Ok(mark)
+}
+
+/// Check that an instrument's mark is fresh enough to risk-manage against
+///
+/// Instruments without an oracle keep the mark they were listed with.
+pub fn check_mark_fresh(
+    slab: &SlabState,
+    instrument_idx: u16,
+    current_ts: u64,
+) -> Result<(), PercolatorError> {
+    let instr = slab.get_instrument(instrument_idx).ok_or_else(|| {
+        msg!("Error: Invalid instrument index");
+        PercolatorError::InvalidInstrument
+    })?;
+
+    if instr.oracle != Pubkey::default()
+        && current_ts.saturating_sub(instr.mark_ts) > slab.header.oracle_max_staleness_ms
+    {
+        msg!("Error: Mark price is stale");
+        return Err(PercolatorError::OracleStale);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::new_test_slab;
+
+    const LP_OWNER: Pubkey = [1; 32];
+    const ORACLE: Pubkey = [50; 32];
+    const ORACLE_PROGRAM: Pubkey = [60; 32];
+
+    fn oracle_data(price: i64, publish_ms: u64) -> [u8; TestOracle::LEN] {
+        TestOracle::new(price, 1_000_000, -6, publish_ms).to_bytes()
+    }
+
+    #[test]
+    fn test_set_oracle_requires_lp_owner() {
+        let mut slab = new_test_slab();
+
+        assert_eq!(
+            process_set_oracle(&mut slab, &ORACLE, 0, &ORACLE, &ORACLE_PROGRAM).unwrap_err(),
+            PercolatorError::Unauthorized
+        );
+        process_set_oracle(&mut slab, &LP_OWNER, 0, &ORACLE, &ORACLE_PROGRAM).unwrap();
+        assert_eq!(slab.instruments[0].oracle, ORACLE);
+    }
+
+    #[test]
+    fn test_update_mark_from_oracle() {
+        let mut slab = new_test_slab();
+        process_set_oracle(&mut slab, &LP_OWNER, 0, &ORACLE, &ORACLE_PROGRAM).unwrap();
+
+        let mark = process_update_mark(&mut slab, 0, &ORACLE, &ORACLE_PROGRAM, &oracle_data(51_000_000_000, 1_000), 1_500).unwrap();
+        assert_eq!(mark, 51_000_000_000);
+        assert_eq!(slab.instruments[0].mark_px, 51_000_000_000);
+        assert_eq!(slab.instruments[0].prev_mark_px, 50_000_000_000);
+        assert_eq!(slab.instruments[0].mark_ts, 1_000);
+
+        // Going back in time is refused
+        assert_eq!(
+            process_update_mark(&mut slab, 0, &ORACLE, &ORACLE_PROGRAM, &oracle_data(52_000_000_000, 900), 1_500).unwrap_err(),
+            PercolatorError::OracleStale
+        );
+    }
+
+    #[test]
+    fn test_update_mark_rejects_wrong_account() {
+        let mut slab = new_test_slab();
+        let data = oracle_data(51_000_000_000, 1_000);
+
+        // No oracle bound yet
+        assert_eq!(
+            process_update_mark(&mut slab, 0, &ORACLE, &ORACLE_PROGRAM, &data, 1_000).unwrap_err(),
+            PercolatorError::InvalidOracle
+        );
+
+        process_set_oracle(&mut slab, &LP_OWNER, 0, &ORACLE, &ORACLE_PROGRAM).unwrap();
+        assert_eq!(
+            process_update_mark(&mut slab, 0, &[51; 32], &ORACLE_PROGRAM, &data, 1_000).unwrap_err(),
+            PercolatorError::InvalidOracle
+        );
+
+        // Same key, different owner
+        assert_eq!(
+            process_update_mark(&mut slab, 0, &ORACLE, &[61; 32], &data, 1_000).unwrap_err(),
+            PercolatorError::InvalidOracle
+        );
+    }
+
+    #[test]
+    fn test_update_mark_enforces_limits() {
+        let mut slab = new_test_slab();
+        process_set_oracle(&mut slab, &LP_OWNER, 0, &ORACLE, &ORACLE_PROGRAM).unwrap();
+        let max_age = slab.header.oracle_max_staleness_ms;
+
+        assert_eq!(
+            process_update_mark(&mut slab, 0, &ORACLE, &ORACLE_PROGRAM, &oracle_data(51_000_000_000, 1_000), 1_001 + max_age).unwrap_err(),
+            PercolatorError::OracleStale
+        );
+
+        let wide = TestOracle::new(51_000_000_000, 5_100_000_000, -6, 1_000).to_bytes();
+        assert_eq!(
+            process_update_mark(&mut slab, 0, &ORACLE, &ORACLE_PROGRAM, &wide, 1_000).unwrap_err(),
+            PercolatorError::OracleConfidenceTooWide
+        );
+        assert_eq!(slab.instruments[0].mark_px, 50_000_000_000);
+    }
+
+    #[test]
+    fn test_check_mark_fresh() {
+        let mut slab = new_test_slab();
+        let max_age = slab.header.oracle_max_staleness_ms;
+
+        // Without an oracle the listing mark is used as is
+        assert!(check_mark_fresh(&slab, 0, 10 * max_age).is_ok());
+
+        process_set_oracle(&mut slab, &LP_OWNER, 0, &ORACLE, &ORACLE_PROGRAM).unwrap();
+        process_update_mark(&mut slab, 0, &ORACLE, &ORACLE_PROGRAM, &oracle_data(51_000_000_000, 1_000), 1_000).unwrap();
+        assert!(check_mark_fresh(&slab, 0, 1_000 + max_age).is_ok());
+        assert_eq!(check_mark_fresh(&slab, 0, 1_001 + max_age), Err(PercolatorError::OracleStale));
+    }
+}
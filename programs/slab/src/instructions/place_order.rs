@@ -0,0 +1,434 @@
+//! Place order instruction - post a resting maker order
+//!
+//! DLP orders go straight to the live book. Regular orders are parked in the
+//! pending queue and become eligible at the next batch (anti-JIT, plan §4.4).
+//! Posting as DLP requires the maker to be in the slab's DLP registry.
+//! Resting orders never match, so one that would cross the live contra side
+//! is rejected.
+
+use super::batch_open::jumps_frozen_levels;
+use super::commit::reducible_qty;
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::msg;
+
+/// Parameters of a resting order
+#[derive(Debug, Clone, Copy)]
+pub struct OrderParams {
+    /// Instrument index
+    pub instrument_idx: u16,
+    /// Order side
+    pub side: Side,
+    /// Limit price (1e6 scale)
+    pub price: u64,
+    /// Quantity (1e6 scale)
+    pub qty: u64,
+    /// Time in force
+    pub tif: TimeInForce,
+    /// Maker class (DLP posts live, REG posts pending)
+    pub maker_class: MakerClass,
+    /// Order flags (ORDER_FLAG_POST_ONLY, ORDER_FLAG_REDUCE_ONLY)
+    pub flags: u8,
+}
+
+/// Result of a place order operation
+#[derive(Debug, Clone, Copy)]
+pub struct PlaceOrderResult {
+    /// Index of the order in the order pool
+    pub order_idx: u32,
+    /// Order ID (monotonic)
+    pub order_id: u64,
+    /// State the order was posted in
+    pub state: OrderState,
+    /// Epoch at which a pending order becomes live
+    pub eligible_epoch: u16,
+}
+
+/// Process place order instruction
+///
+/// Validates the order against the instrument's tick and lot sizes, assigns
+/// an order ID and inserts it into the book with price-time priority.
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `account_idx` - Account index of the maker
+/// * `params` - Order parameters
+/// * `current_ts` - Current timestamp (ms)
+///
+/// # Returns
+/// * `PlaceOrderResult` with the order's pool index, ID and state
+pub fn process_place_order(
+    slab: &mut SlabState,
+    account_idx: u32,
+    params: &OrderParams,
+    current_ts: u64,
+) -> Result<PlaceOrderResult, PercolatorError> {
+    let result = post_order(slab, account_idx, params, current_ts)?;
+
+    slab.header.increment_seqno();
+    slab.refresh_quote_cache(params.instrument_idx);
+    Ok(result)
+}
+
+/// Validate and book a resting order
+///
+/// Leaves the seqno and quote cache to the caller, so instructions that post
+/// several orders refresh the cache once.
+pub(crate) fn post_order(
+    slab: &mut SlabState,
+    account_idx: u32,
+    params: &OrderParams,
+    current_ts: u64,
+) -> Result<PlaceOrderResult, PercolatorError> {
+    // Validate account
+    let account = slab.get_account(account_idx).ok_or_else(|| {
+        msg!("Error: Invalid account index");
+        PercolatorError::InvalidAccount
+    })?;
+
+    // Only registered DLPs may skip the pending queue
+    if params.maker_class == MakerClass::DLP && !slab.is_dlp(&account.key) {
+        msg!("Error: Account is not a registered DLP");
+        return Err(PercolatorError::DlpNotRegistered);
+    }
+
+    // Validate instrument
+    let instr = slab.get_instrument(params.instrument_idx).ok_or_else(|| {
+        msg!("Error: Invalid instrument index");
+        PercolatorError::InvalidInstrument
+    })?;
+
+    // Validate price
+    if params.price == 0 {
+        msg!("Error: Price must be positive");
+        return Err(PercolatorError::InvalidPrice);
+    }
+
+    if !is_tick_aligned(params.price, instr.tick) {
+        msg!("Error: Price not aligned to tick size");
+        return Err(PercolatorError::PriceNotAligned);
+    }
+
+    // Validate quantity
+    if params.qty == 0 {
+        msg!("Error: Quantity must be positive");
+        return Err(PercolatorError::InvalidQuantity);
+    }
+
+    if !is_lot_aligned(params.qty, instr.lot) {
+        msg!("Error: Quantity not aligned to lot size");
+        return Err(PercolatorError::QuantityNotAligned);
+    }
+
+    // Resting orders never match on insert, so only GTC makes sense here.
+    // Takers get IOC/FOK semantics through reserve.
+    if params.tif != TimeInForce::GTC {
+        msg!("Error: Resting orders must be GTC");
+        return Err(PercolatorError::InvalidTimeInForce);
+    }
+
+    check_not_crossing(slab, params.instrument_idx, params.side, params.price, params.flags)?;
+
+    // Reduce-only: the order must fit inside the position it reduces
+    if params.flags & ORDER_FLAG_REDUCE_ONLY != 0
+        && params.qty > reducible_qty(slab, account_idx, params.instrument_idx, params.side)
+    {
+        msg!("Error: Reduce-only order would increase position");
+        return Err(PercolatorError::ReduceOnlyViolation);
+    }
+
+    // Allocate the order
+    let order_idx = slab.alloc_order().ok_or_else(|| {
+        msg!("Error: Order pool full");
+        PercolatorError::PoolFull
+    })?;
+
+    let order_id = slab.header.next_order_id();
+
+    // DLPs post live immediately; everyone else waits for the next batch.
+    // A DLP order that would jump the frozen top-K levels also waits.
+    let jumps_freeze = jumps_frozen_levels(slab, params.instrument_idx, params.side, params.price, current_ts);
+    let (state, eligible_epoch) = match params.maker_class {
+        MakerClass::DLP if !jumps_freeze => (OrderState::LIVE, slab.header.current_epoch as u16),
+        _ => (
+            OrderState::PENDING,
+            slab.header.current_epoch.wrapping_add(1) as u16,
+        ),
+    };
+
+    if let Some(order) = slab.get_order_mut(order_idx) {
+        order.order_id = order_id;
+        order.account_idx = account_idx;
+        order.instrument_idx = params.instrument_idx;
+        order.side = params.side;
+        order.tif = params.tif;
+        order.maker_class = params.maker_class;
+        order.state = state;
+        order.eligible_epoch = eligible_epoch;
+        order.created_ms = current_ts;
+        order.frozen_until_ms = 0;
+        order.price = params.price;
+        order.qty = params.qty;
+        order.reserved_qty = 0;
+        order.qty_orig = params.qty;
+        order.next = SlabState::INVALID_INDEX;
+        order.prev = SlabState::INVALID_INDEX;
+        order.flags = params.flags;
+    }
+    slab.link_account_order(order_idx);
+
+    slab.insert_order_into_book(order_idx, params.instrument_idx);
+
+    Ok(PlaceOrderResult {
+        order_idx,
+        order_id,
+        state,
+        eligible_epoch,
+    })
+}
+
+/// Reject a resting price that would cross the live contra side
+///
+/// Resting orders never match on insert, so a crossing order would leave
+/// the book crossed. Post-only orders keep their own error.
+pub(crate) fn check_not_crossing(
+    slab: &SlabState,
+    instrument_idx: u16,
+    side: Side,
+    price: u64,
+    flags: u8,
+) -> Result<(), PercolatorError> {
+    if !slab.crosses_book(instrument_idx, side, price) {
+        return Ok(());
+    }
+    if flags & ORDER_FLAG_POST_ONLY != 0 {
+        msg!("Error: Post-only order would cross the book");
+        Err(PercolatorError::PostOnlyWouldCross)
+    } else {
+        msg!("Error: Resting order would cross the book");
+        Err(PercolatorError::OrderWouldCross)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{new_test_slab, test_account, test_dlp};
+
+    fn params(side: Side, price: u64, qty: u64, maker_class: MakerClass) -> OrderParams {
+        OrderParams {
+            instrument_idx: 0,
+            side,
+            price,
+            qty,
+            tif: TimeInForce::GTC,
+            maker_class,
+            flags: 0,
+        }
+    }
+
+    #[test]
+    fn test_dlp_order_posts_live() {
+        let mut slab = new_test_slab();
+        let acct = test_dlp(&mut slab, 10);
+
+        let result = process_place_order(
+            &mut slab,
+            acct,
+            &params(Side::Sell, 50_000_000_000, 5_000_000, MakerClass::DLP),
+            1_000,
+        )
+        .unwrap();
+
+        assert_eq!(result.state, OrderState::LIVE);
+        assert_eq!(result.order_id, 1);
+        assert_eq!(slab.instruments[0].asks_head, result.order_idx);
+
+        let order = slab.get_order(result.order_idx).unwrap();
+        assert_eq!(order.created_ms, 1_000);
+        assert_eq!(order.qty_orig, 5_000_000);
+        assert_eq!(order.account_idx, acct);
+    }
+
+    #[test]
+    fn test_unregistered_dlp_rejected() {
+        let mut slab = new_test_slab();
+        let acct = test_account(&mut slab, 10);
+
+        assert_eq!(
+            process_place_order(&mut slab, acct, &params(Side::Sell, 50_000_000_000, 1_000_000, MakerClass::DLP), 0)
+                .unwrap_err(),
+            PercolatorError::DlpNotRegistered
+        );
+        assert_eq!(slab.header.order_count, 0);
+    }
+
+    #[test]
+    fn test_reg_order_goes_pending() {
+        let mut slab = new_test_slab();
+        let acct = test_account(&mut slab, 10);
+        slab.header.current_epoch = 7;
+
+        let result = process_place_order(
+            &mut slab,
+            acct,
+            &params(Side::Buy, 49_000_000_000, 1_000_000, MakerClass::REG),
+            1_000,
+        )
+        .unwrap();
+
+        assert_eq!(result.state, OrderState::PENDING);
+        assert_eq!(result.eligible_epoch, 8);
+        assert_eq!(slab.instruments[0].bids_head, SlabState::INVALID_INDEX);
+        assert_eq!(slab.instruments[0].bids_pending_head, result.order_idx);
+    }
+
+    #[test]
+    fn test_order_ids_are_monotonic_and_book_is_price_time() {
+        let mut slab = new_test_slab();
+        let acct = test_dlp(&mut slab, 10);
+
+        let a = process_place_order(&mut slab, acct, &params(Side::Buy, 49_000_000_000, 1_000_000, MakerClass::DLP), 1).unwrap();
+        let b = process_place_order(&mut slab, acct, &params(Side::Buy, 50_000_000_000, 1_000_000, MakerClass::DLP), 2).unwrap();
+        let c = process_place_order(&mut slab, acct, &params(Side::Buy, 49_000_000_000, 1_000_000, MakerClass::DLP), 3).unwrap();
+
+        assert!(a.order_id < b.order_id && b.order_id < c.order_id);
+
+        // Best price first, then time priority within the level
+        let head = slab.instruments[0].bids_head;
+        assert_eq!(head, b.order_idx);
+        assert_eq!(slab.get_order(head).unwrap().next, a.order_idx);
+        assert_eq!(slab.get_order(a.order_idx).unwrap().next, c.order_idx);
+    }
+
+    #[test]
+    fn test_place_order_validation() {
+        let mut slab = new_test_slab();
+        let acct = test_dlp(&mut slab, 10);
+
+        // Price off the tick grid
+        assert_eq!(
+            process_place_order(&mut slab, acct, &params(Side::Buy, 50_000_500_000, 1_000_000, MakerClass::DLP), 0).unwrap_err(),
+            PercolatorError::PriceNotAligned
+        );
+
+        // Quantity off the lot grid
+        assert_eq!(
+            process_place_order(&mut slab, acct, &params(Side::Buy, 50_000_000_000, 1_500_000, MakerClass::DLP), 0).unwrap_err(),
+            PercolatorError::QuantityNotAligned
+        );
+
+        // Zero quantity
+        assert_eq!(
+            process_place_order(&mut slab, acct, &params(Side::Buy, 50_000_000_000, 0, MakerClass::DLP), 0).unwrap_err(),
+            PercolatorError::InvalidQuantity
+        );
+
+        // Unknown instrument
+        let mut bad = params(Side::Buy, 50_000_000_000, 1_000_000, MakerClass::DLP);
+        bad.instrument_idx = 5;
+        assert_eq!(
+            process_place_order(&mut slab, acct, &bad, 0).unwrap_err(),
+            PercolatorError::InvalidInstrument
+        );
+
+        // Nothing was allocated by the rejected orders
+        assert_eq!(slab.header.order_count, 0);
+    }
+
+    #[test]
+    fn test_resting_orders_never_cross() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let other = test_account(&mut slab, 20);
+        process_place_order(&mut slab, maker, &params(Side::Sell, 50_000_000_000, 1_000_000, MakerClass::DLP), 0).unwrap();
+
+        // At or through the best ask, live or pending
+        assert_eq!(
+            process_place_order(&mut slab, maker, &params(Side::Buy, 50_000_000_000, 1_000_000, MakerClass::DLP), 0).unwrap_err(),
+            PercolatorError::OrderWouldCross
+        );
+        assert_eq!(
+            process_place_order(&mut slab, other, &params(Side::Buy, 51_000_000_000, 1_000_000, MakerClass::REG), 0).unwrap_err(),
+            PercolatorError::OrderWouldCross
+        );
+
+        // Post-only keeps its own error
+        let mut post_only = params(Side::Buy, 50_000_000_000, 1_000_000, MakerClass::DLP);
+        post_only.flags = ORDER_FLAG_POST_ONLY;
+        assert_eq!(
+            process_place_order(&mut slab, maker, &post_only, 0).unwrap_err(),
+            PercolatorError::PostOnlyWouldCross
+        );
+
+        // One tick below rests
+        process_place_order(&mut slab, maker, &params(Side::Buy, 49_999_000_000, 1_000_000, MakerClass::DLP), 0).unwrap();
+        assert_eq!(slab.header.order_count, 2);
+    }
+
+    #[test]
+    fn test_resting_orders_must_be_gtc() {
+        let mut slab = new_test_slab();
+        let acct = test_dlp(&mut slab, 10);
+
+        let mut ioc = params(Side::Buy, 50_000_000_000, 1_000_000, MakerClass::DLP);
+        ioc.tif = TimeInForce::IOC;
+        assert_eq!(
+            process_place_order(&mut slab, acct, &ioc, 0).unwrap_err(),
+            PercolatorError::InvalidTimeInForce
+        );
+    }
+
+    #[test]
+    fn test_post_only_rejects_crossing_order() {
+        let mut slab = new_test_slab();
+        let acct = test_dlp(&mut slab, 10);
+
+        process_place_order(&mut slab, acct, &params(Side::Sell, 50_000_000_000, 1_000_000, MakerClass::DLP), 0).unwrap();
+
+        let mut crossing = params(Side::Buy, 50_000_000_000, 1_000_000, MakerClass::DLP);
+        crossing.flags = ORDER_FLAG_POST_ONLY;
+        assert_eq!(
+            process_place_order(&mut slab, acct, &crossing, 0).unwrap_err(),
+            PercolatorError::PostOnlyWouldCross
+        );
+
+        // One tick inside is fine
+        let mut passive = params(Side::Buy, 49_999_000_000, 1_000_000, MakerClass::DLP);
+        passive.flags = ORDER_FLAG_POST_ONLY;
+        assert!(process_place_order(&mut slab, acct, &passive, 0).is_ok());
+    }
+
+    #[test]
+    fn test_reduce_only_requires_opposite_position() {
+        let mut slab = new_test_slab();
+        let acct = test_dlp(&mut slab, 10);
+
+        // Flat: nothing to reduce
+        let mut sell = params(Side::Sell, 50_000_000_000, 1_000_000, MakerClass::DLP);
+        sell.flags = ORDER_FLAG_REDUCE_ONLY;
+        assert_eq!(
+            process_place_order(&mut slab, acct, &sell, 0).unwrap_err(),
+            PercolatorError::ReduceOnlyViolation
+        );
+
+        // Long 2: a reduce-only sell of up to 2 is accepted
+        let pos_idx = slab.alloc_position().unwrap();
+        let pos = slab.get_position_mut(pos_idx).unwrap();
+        pos.account_idx = acct;
+        pos.instrument_idx = 0;
+        pos.qty = 2_000_000;
+        pos.next_in_account = SlabState::INVALID_INDEX;
+        slab.accounts[acct as usize].position_head = pos_idx;
+
+        sell.qty = 2_000_000;
+        let result = process_place_order(&mut slab, acct, &sell, 0).unwrap();
+        assert_eq!(slab.get_order(result.order_idx).unwrap().flags, ORDER_FLAG_REDUCE_ONLY);
+
+        sell.qty = 3_000_000;
+        assert_eq!(
+            process_place_order(&mut slab, acct, &sell, 0).unwrap_err(),
+            PercolatorError::ReduceOnlyViolation
+        );
+    }
+}
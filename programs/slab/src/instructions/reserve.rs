@@ -3,6 +3,9 @@
 //! Walks the orderbook, locks slices at each price level, and calculates
 //! VWAP and max_charge for the Router to use in escrow/cap creation.
 
+use super::batch_open::is_order_frozen;
+use super::cancel::release_slices;
+use super::commit::reducible_qty;
 use crate::state::SlabState;
 use percolator_common::*;
 use pinocchio::msg;
@@ -26,6 +29,20 @@ pub struct ReserveResult {
     pub book_seqno: u64,
 }
 
+/// Execution options of a taker order
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TakerOptions {
+    /// Time in force: FOK fails unless the full quantity can be reserved;
+    /// IOC and GTC reserve what is available and drop the remainder
+    pub tif: TimeInForce,
+    /// Order flags (ORDER_FLAG_REDUCE_ONLY caps the quantity at the position
+    /// being reduced; post-only is rejected as a reservation always crosses)
+    pub flags: u8,
+    /// Self-trade prevention override (SlabDefault uses the header's mode;
+    /// Allow only takes effect if the header allows self-trades)
+    pub stp: SelfTradePrevention,
+}
+
 /// Process reserve instruction
 ///
 /// Walks the orderbook on the contra side, locking slices up to the requested
@@ -39,9 +56,11 @@ pub struct ReserveResult {
 /// * `side` - Side of the order (Buy/Sell)
 /// * `qty` - Requested quantity (1e6 scale)
 /// * `limit_px` - Worst acceptable price (1e6 scale)
-/// * `ttl_ms` - Time-to-live for reservation in milliseconds
+/// * `ttl_ms` - Time-to-live for reservation in milliseconds (positive, clamped to `max_ttl_ms`)
 /// * `commitment_hash` - Hash for commit-reveal (optional)
 /// * `route_id` - Route ID from router
+/// * `opts` - Time in force, order flags and self-trade prevention
+/// * `current_ts` - Current timestamp (ms)
 ///
 /// # Returns
 /// * `ReserveResult` with reservation details
@@ -55,12 +74,17 @@ pub fn process_reserve(
     ttl_ms: u64,
     commitment_hash: [u8; 32],
     route_id: u64,
+    opts: &TakerOptions,
+    current_ts: u64,
 ) -> Result<ReserveResult, PercolatorError> {
-    // Validate instrument
-    if slab.get_instrument(instrument_idx).is_none() {
-        msg!("Error: Invalid instrument index");
-        return Err(PercolatorError::InvalidInstrument);
-    }
+    // Validate instrument and snapshot its mark for the commit kill band check
+    let (mark_px, mark_ts) = match slab.get_instrument(instrument_idx) {
+        Some(instr) => (instr.mark_px, instr.mark_ts),
+        None => {
+            msg!("Error: Invalid instrument index");
+            return Err(PercolatorError::InvalidInstrument);
+        }
+    };
 
     // Validate quantity
     if qty == 0 {
@@ -74,6 +98,38 @@ pub fn process_reserve(
         return Err(PercolatorError::InvalidPrice);
     }
 
+    // A reservation always takes liquidity
+    if opts.flags & ORDER_FLAG_POST_ONLY != 0 {
+        msg!("Error: Post-only order would cross the book");
+        return Err(PercolatorError::PostOnlyWouldCross);
+    }
+
+    // Commit-reveal: the slab may insist on a commitment
+    if slab.header.commit_reveal_required && commitment_hash == [0; 32] {
+        msg!("Error: Reservation requires a commitment hash");
+        return Err(PercolatorError::InvalidCommitment);
+    }
+
+    // Reduce-only: never ask for more than closes the position
+    let req_qty = qty;
+    let qty = if opts.flags & ORDER_FLAG_REDUCE_ONLY != 0 {
+        let reducible = reducible_qty(slab, account_idx, instrument_idx, side);
+        if reducible == 0 {
+            msg!("Error: Reduce-only order would increase position");
+            return Err(PercolatorError::ReduceOnlyViolation);
+        }
+        qty.min(reducible)
+    } else {
+        qty
+    };
+
+    // Every hold expires, no later than the slab's maximum TTL
+    if ttl_ms == 0 {
+        msg!("Error: Reservation TTL must be positive");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+    let ttl_ms = ttl_ms.min(slab.header.max_ttl_ms);
+
     // Allocate a new reservation
     let resv_idx = slab.alloc_reservation()
         .ok_or_else(|| {
@@ -83,18 +139,22 @@ pub fn process_reserve(
 
     // Get the hold ID
     let hold_id = slab.header.next_hold_id();
-    let book_seqno = slab.header.seqno as u64;
+    let book_seqno = slab.quote_caches[instrument_idx as usize].seqno_snapshot as u64;
 
     // Calculate expiry
-    // Note: In production, would use Clock sysvar for current time
-    let expiry_ms = ttl_ms; // Relative TTL for now
+    let expiry_ms = current_ts.saturating_add(ttl_ms);
 
     // Walk the book and reserve slices
-    let (filled_qty, total_notional, worst_px, slice_head) = 
-        walk_and_reserve(slab, instrument_idx, side, qty, limit_px, resv_idx)?;
+    let stp = slab.header.resolve_stp(opts.stp);
+    let (filled_qty, total_notional, worst_px, slice_head, stp_decremented) =
+        walk_and_reserve(slab, account_idx, instrument_idx, side, qty, limit_px, stp, current_ts)?;
 
-    // If no liquidity was found, free the reservation and return error
-    if filled_qty == 0 {
+    // If no liquidity was found, free the reservation and return error.
+    // FOK is all-or-nothing: a partial walk is rolled back. Quantity removed
+    // by decrement-both self-trade prevention counts as done.
+    let fok_short = filled_qty + stp_decremented < qty;
+    if filled_qty == 0 || (opts.tif == TimeInForce::FOK && fok_short) {
+        release_slices(slab, slice_head);
         slab.free_reservation(resv_idx);
         msg!("Error: Insufficient liquidity");
         return Err(PercolatorError::InsufficientLiquidity);
@@ -115,20 +175,28 @@ pub fn process_reserve(
         resv.account_idx = account_idx;
         resv.instrument_idx = instrument_idx;
         resv.side = side;
+        resv.tif = opts.tif;
+        resv.flags = opts.flags;
         resv.qty = filled_qty;
         resv.vwap_px = vwap_px;
         resv.worst_px = worst_px;
+        resv.req_qty = req_qty;
+        resv.limit_px = limit_px;
+        resv.mark_px = mark_px;
+        resv.mark_ts = mark_ts;
         resv.max_charge = max_charge;
         resv.commitment_hash = commitment_hash;
-        resv.salt = [0; 16]; // Will be filled by Router
+        resv.salt = [0; SALT_LEN]; // Revealed by the router at commit
         resv.book_seqno = book_seqno;
         resv.expiry_ms = expiry_ms;
         resv.slice_head = slice_head;
         resv.committed = false;
     }
+    slab.index_expiry(resv_idx);
 
     // Increment seqno
     slab.header.increment_seqno();
+    slab.refresh_quote_cache(instrument_idx);
 
     Ok(ReserveResult {
         hold_id,
@@ -143,25 +211,32 @@ pub fn process_reserve(
 
 /// Walk the orderbook and reserve slices
 ///
-/// Returns (filled_qty, total_notional, worst_px, slice_head)
-fn walk_and_reserve(
+/// Resting orders owned by the taker are handled according to `stp`. Frozen
+/// own orders cannot be cancelled, so modes that would cancel them skip them.
+///
+/// Returns (filled_qty, total_notional, worst_px, slice_head, stp_decremented)
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn walk_and_reserve(
     slab: &mut SlabState,
+    account_idx: u32,
     instrument_idx: u16,
     side: Side,
     mut qty_remaining: u64,
     limit_px: u64,
-    _resv_idx: u32,
-) -> Result<(u64, u128, u64, u32), PercolatorError> {
+    stp: SelfTradePrevention,
+    current_ts: u64,
+) -> Result<(u64, u128, u64, u32, u64), PercolatorError> {
     let mut total_qty = 0u64;
     let mut total_notional = 0u128;
     let mut worst_px = 0u64;
     let mut slice_head = SlabState::INVALID_INDEX;
     let mut prev_slice_idx = SlabState::INVALID_INDEX;
+    let mut stp_decremented = 0u64;
 
     // Get the head of the contra side
     let mut order_idx = match slab.get_best_contra(instrument_idx, side) {
         Some(idx) => idx,
-        None => return Ok((0, 0, 0, SlabState::INVALID_INDEX)),
+        None => return Ok((0, 0, 0, SlabState::INVALID_INDEX, 0)),
     };
 
     // Walk the book
@@ -182,14 +257,51 @@ fn walk_and_reserve(
         }
 
         // Calculate available quantity (not already reserved)
-        let available = order.qty.saturating_sub(order.reserved_qty);
+        let mut available = order.qty.saturating_sub(order.reserved_qty);
+
+        // Reduce-only makers only offer what still closes their position
+        if order.flags & ORDER_FLAG_REDUCE_ONLY != 0 {
+            let maker_side = match side {
+                Side::Buy => Side::Sell,
+                Side::Sell => Side::Buy,
+            };
+            let reducible = reducible_qty(slab, order.account_idx, instrument_idx, maker_side);
+            available = available.min(reducible.saturating_sub(order.reserved_qty));
+        }
+
         if available == 0 {
             order_idx = order.next;
             continue;
         }
 
+        // Self-trade prevention
+        if order.account_idx == account_idx && stp != SelfTradePrevention::Allow {
+            let next = order.next;
+            let stp = match stp {
+                SelfTradePrevention::CancelResting | SelfTradePrevention::DecrementBoth
+                    if is_order_frozen(order, current_ts) => SelfTradePrevention::Skip,
+                stp => stp,
+            };
+            match stp {
+                SelfTradePrevention::CancelTaking => break,
+                SelfTradePrevention::CancelResting => {
+                    cancel_unreserved(slab, order_idx, available);
+                }
+                SelfTradePrevention::DecrementBoth => {
+                    let dec = available.min(qty_remaining);
+                    cancel_unreserved(slab, order_idx, dec);
+                    qty_remaining -= dec;
+                    stp_decremented += dec;
+                }
+                _ => {} // Skip
+            }
+            order_idx = next;
+            continue;
+        }
+
         // Take what we can from this order
         let take = available.min(qty_remaining);
+        let order_id = order.order_id;
         
         // Allocate a slice
         let slice_idx = slab.alloc_slice()
@@ -201,6 +313,7 @@ fn walk_and_reserve(
         // Fill in the slice
         if let Some(slice) = slab.get_slice_mut(slice_idx) {
             slice.order_idx = order_idx;
+            slice.order_id = order_id;
             slice.qty = take;
             slice.next = SlabState::INVALID_INDEX;
         }
@@ -214,8 +327,8 @@ fn walk_and_reserve(
         prev_slice_idx = slice_idx;
 
         // Update order's reserved quantity
-        if let Some(order) = slab.get_order_mut(order_idx) {
-            order.reserved_qty += take;
+        if let Some(order) = slab.get_order(order_idx).copied() {
+            slab.set_order_qty(order_idx, order.qty, order.reserved_qty + take);
         }
 
         // Update totals
@@ -230,18 +343,224 @@ fn walk_and_reserve(
         order_idx = next;
     }
 
-    Ok((total_qty, total_notional, worst_px, slice_head))
+    Ok((total_qty, total_notional, worst_px, slice_head, stp_decremented))
+}
+
+/// Remove `qty` of unreserved quantity from a resting order
+///
+/// The order leaves the book once nothing (reserved or not) is left on it.
+fn cancel_unreserved(slab: &mut SlabState, order_idx: u32, qty: u64) {
+    let order = match slab.get_order(order_idx) {
+        Some(o) => *o,
+        None => return,
+    };
+
+    let remaining = order.qty.saturating_sub(qty);
+    if remaining == 0 {
+        slab.remove_order_from_book(order_idx);
+        slab.free_order(order_idx);
+    } else {
+        slab.set_order_qty(order_idx, remaining, order.reserved_qty);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instructions::{process_commit, process_place_order, OrderParams};
+    use crate::tests::{new_test_slab, test_dlp};
 
-    // Note: Full tests require setting up book state which needs more infrastructure
-    // These are placeholder tests
+    const PX: u64 = 50_000_000_000;
+    const QTY: u64 = 1_000_000;
+
+    fn post(slab: &mut SlabState, acct: u32, side: Side, qty: u64) -> u32 {
+        let params = OrderParams {
+            instrument_idx: 0,
+            side,
+            price: PX,
+            qty,
+            tif: TimeInForce::GTC,
+            maker_class: MakerClass::DLP,
+            flags: 0,
+        };
+        process_place_order(slab, acct, &params, 0).unwrap().order_idx
+    }
+
+    fn reserve(slab: &mut SlabState, acct: u32, side: Side, qty: u64, tif: TimeInForce, flags: u8) -> Result<ReserveResult, PercolatorError> {
+        let opts = TakerOptions { tif, flags, stp: SelfTradePrevention::SlabDefault };
+        process_reserve(slab, acct, 0, side, qty, PX, 1_000, [0; 32], 1, &opts, 0)
+    }
+
+    fn reserve_stp(slab: &mut SlabState, acct: u32, qty: u64, tif: TimeInForce, stp: SelfTradePrevention) -> Result<ReserveResult, PercolatorError> {
+        let opts = TakerOptions { tif, flags: 0, stp };
+        process_reserve(slab, acct, 0, Side::Buy, qty, PX, 1_000, [0; 32], 1, &opts, 0)
+    }
+
+    /// Own ask of 2 ahead of a foreign ask of 3
+    fn self_trade_book(slab: &mut SlabState) -> (u32, u32, u32) {
+        let me = test_dlp(slab, 10);
+        let other = test_dlp(slab, 20);
+        let own = post(slab, me, Side::Sell, 2 * QTY);
+        let foreign = post(slab, other, Side::Sell, 3 * QTY);
+        (me, own, foreign)
+    }
 
     #[test]
     fn test_reserve_result_size() {
         assert!(core::mem::size_of::<ReserveResult>() <= 64);
     }
+
+    #[test]
+    fn test_fok_is_all_or_nothing() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        let order_idx = post(&mut slab, maker, Side::Sell, 2 * QTY);
+
+        assert_eq!(
+            reserve(&mut slab, taker, Side::Buy, 3 * QTY, TimeInForce::FOK, 0).unwrap_err(),
+            PercolatorError::InsufficientLiquidity
+        );
+
+        // Nothing stays locked after the rollback
+        assert_eq!(slab.get_order(order_idx).unwrap().reserved_qty, 0);
+        assert_eq!(slab.header.reservation_count, 0);
+        assert_eq!(slab.header.slice_count, 0);
+
+        // Exactly the available size fills
+        let result = reserve(&mut slab, taker, Side::Buy, 2 * QTY, TimeInForce::FOK, 0).unwrap();
+        assert_eq!(result.filled_qty, 2 * QTY);
+    }
+
+    #[test]
+    fn test_ioc_reserves_what_is_available() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+        let order_idx = post(&mut slab, maker, Side::Sell, 2 * QTY);
+
+        let result = reserve(&mut slab, taker, Side::Buy, 3 * QTY, TimeInForce::IOC, 0).unwrap();
+        assert_eq!(result.filled_qty, 2 * QTY);
+        assert_eq!(slab.get_order(order_idx).unwrap().reserved_qty, 2 * QTY);
+    }
+
+    #[test]
+    fn test_reserve_flags() {
+        let mut slab = new_test_slab();
+        let maker = test_dlp(&mut slab, 10);
+        let taker = test_dlp(&mut slab, 20);
+
+        // Post-only never takes
+        assert_eq!(
+            reserve(&mut slab, taker, Side::Buy, QTY, TimeInForce::IOC, ORDER_FLAG_POST_ONLY).unwrap_err(),
+            PercolatorError::PostOnlyWouldCross
+        );
+
+        // Reduce-only while flat
+        assert_eq!(
+            reserve(&mut slab, taker, Side::Sell, QTY, TimeInForce::IOC, ORDER_FLAG_REDUCE_ONLY).unwrap_err(),
+            PercolatorError::ReduceOnlyViolation
+        );
+
+        // Short 2, then a reduce-only buy of 5 is capped at 2
+        post(&mut slab, maker, Side::Buy, 2 * QTY);
+        let resv = reserve(&mut slab, taker, Side::Sell, 2 * QTY, TimeInForce::IOC, 0).unwrap();
+        process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 0).unwrap();
+        post(&mut slab, maker, Side::Sell, 5 * QTY);
+
+        let result = reserve(&mut slab, taker, Side::Buy, 5 * QTY, TimeInForce::IOC, ORDER_FLAG_REDUCE_ONLY).unwrap();
+        assert_eq!(result.filled_qty, 2 * QTY);
+    }
+
+    #[test]
+    fn test_stp_skip_is_slab_default() {
+        let mut slab = new_test_slab();
+        let (me, own, foreign) = self_trade_book(&mut slab);
+
+        let result = reserve_stp(&mut slab, me, 4 * QTY, TimeInForce::IOC, SelfTradePrevention::SlabDefault).unwrap();
+        assert_eq!(result.filled_qty, 3 * QTY);
+        assert_eq!(slab.get_order(own).unwrap().reserved_qty, 0);
+        assert_eq!(slab.get_order(foreign).unwrap().reserved_qty, 3 * QTY);
+    }
+
+    #[test]
+    fn test_stp_allow_self_trades() {
+        let mut slab = new_test_slab();
+        let (me, own, foreign) = self_trade_book(&mut slab);
+
+        // A taker cannot allow what the slab prevents
+        let result = reserve_stp(&mut slab, me, QTY, TimeInForce::IOC, SelfTradePrevention::Allow).unwrap();
+        assert_eq!(result.filled_qty, QTY);
+        assert_eq!(slab.get_order(own).unwrap().reserved_qty, 0);
+        assert_eq!(slab.get_order(foreign).unwrap().reserved_qty, QTY);
+
+        slab.header.stp_mode = SelfTradePrevention::Allow;
+        let result = reserve_stp(&mut slab, me, QTY, TimeInForce::IOC, SelfTradePrevention::Allow).unwrap();
+        assert_eq!(result.filled_qty, QTY);
+        assert_eq!(slab.get_order(own).unwrap().reserved_qty, QTY);
+    }
+
+    #[test]
+    fn test_stp_cancel_resting() {
+        let mut slab = new_test_slab();
+        let (me, own, foreign) = self_trade_book(&mut slab);
+
+        let result = reserve_stp(&mut slab, me, 3 * QTY, TimeInForce::IOC, SelfTradePrevention::CancelResting).unwrap();
+        assert_eq!(result.filled_qty, 3 * QTY);
+        assert!(slab.get_order(own).is_none());
+        assert_eq!(slab.instruments[0].asks_head, foreign);
+    }
+
+    #[test]
+    fn test_stp_cancel_taking() {
+        let mut slab = new_test_slab();
+        let (me, own, _) = self_trade_book(&mut slab);
+
+        // The walk stops at the own order, nothing was reserved before it
+        assert_eq!(
+            reserve_stp(&mut slab, me, 3 * QTY, TimeInForce::IOC, SelfTradePrevention::CancelTaking).unwrap_err(),
+            PercolatorError::InsufficientLiquidity
+        );
+        assert_eq!(slab.get_order(own).unwrap().qty, 2 * QTY);
+    }
+
+    #[test]
+    fn test_stp_decrement_both() {
+        let mut slab = new_test_slab();
+        let (me, own, foreign) = self_trade_book(&mut slab);
+
+        // 2 of the 3 cancel against the own order, 1 fills from the foreign one
+        let result = reserve_stp(&mut slab, me, 3 * QTY, TimeInForce::FOK, SelfTradePrevention::DecrementBoth).unwrap();
+        assert_eq!(result.filled_qty, QTY);
+        assert!(slab.get_order(own).is_none());
+        assert_eq!(slab.get_order(foreign).unwrap().reserved_qty, QTY);
+    }
+
+    #[test]
+    fn test_stp_skips_frozen_own_order() {
+        let mut slab = new_test_slab();
+        let (me, own, foreign) = self_trade_book(&mut slab);
+        slab.orders[own as usize].frozen_until_ms = 100;
+
+        reserve_stp(&mut slab, me, QTY, TimeInForce::IOC, SelfTradePrevention::CancelResting).unwrap();
+        let result = reserve_stp(&mut slab, me, 2 * QTY, TimeInForce::FOK, SelfTradePrevention::DecrementBoth).unwrap();
+        assert_eq!(result.filled_qty, 2 * QTY);
+        assert_eq!(slab.get_order(own).unwrap().qty, 2 * QTY);
+        assert_eq!(slab.get_order(foreign).unwrap().reserved_qty, 3 * QTY);
+    }
+
+    #[test]
+    fn test_stp_never_touches_reserved_qty() {
+        let mut slab = new_test_slab();
+        let (me, own, _) = self_trade_book(&mut slab);
+        let other = test_dlp(&mut slab, 30);
+
+        // Someone else holds 1 of the own order
+        reserve(&mut slab, other, Side::Buy, QTY, TimeInForce::IOC, 0).unwrap();
+
+        reserve_stp(&mut slab, me, QTY, TimeInForce::IOC, SelfTradePrevention::CancelResting).unwrap();
+        let order = slab.get_order(own).unwrap();
+        assert_eq!(order.qty, QTY);
+        assert_eq!(order.reserved_qty, QTY);
+    }
 }
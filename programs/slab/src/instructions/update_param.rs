@@ -0,0 +1,337 @@
+//! Update param instruction - LP owner adjusts a slab-wide setting
+//!
+//! Each setting is addressed by a `SlabParam` id and carries a u64 value that
+//! is validated and narrowed by the setting itself.
+
+use super::liquidation::MAX_LIQUIDATION_IMPACT_BPS;
+use crate::state::{ArgMode, ShortfallPolicy, SlabState};
+use percolator_common::*;
+use pinocchio::{msg, pubkey::Pubkey};
+
+/// Slab-wide settings that the LP owner can change after initialization
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlabParam {
+    /// Default self-trade prevention mode (value: SelfTradePrevention as u8)
+    SelfTradePrevention = 0,
+    /// Require a commit-reveal commitment on every reservation (value: 0 or 1)
+    RequireCommitment = 1,
+    /// Aggressor Roundtrip Guard on or off (value: 0 or 1)
+    ArgEnabled = 2,
+    /// ARG action on a detected roundtrip (value: ArgMode as u8)
+    ArgMode = 3,
+    /// ARG tax on the overlapping notional (value: basis points, at most 10,000)
+    ArgTaxBps = 4,
+    /// Maximum oracle price age (value: milliseconds)
+    OracleMaxStalenessMs = 5,
+    /// Maximum oracle confidence interval (value: basis points of price, at most 10,000)
+    OracleMaxConfBps = 6,
+    /// Funding interest-rate component (value: basis points per interval, at most 10,000)
+    FundingInterestBps = 7,
+    /// Funding rate cap (value: basis points per interval, at most 10,000)
+    FundingCapBps = 8,
+    /// Keeper reward per swept reservation (value: cash units, qty * price scale)
+    SweepReward = 9,
+    /// Commit action on unbacked reserved slices (value: ShortfallPolicy as u8)
+    ShortfallPolicy = 10,
+    /// Margin above MM that liquidation restores (value: basis points, at most 10,000)
+    LiqBufferBps = 11,
+    /// Liquidation price band around mark (value: basis points, at most MAX_LIQUIDATION_IMPACT_BPS)
+    LiqBandBps = 12,
+    /// Longest reservation TTL (value: milliseconds, positive)
+    MaxTtlMs = 13,
+}
+
+impl TryFrom<u8> for SlabParam {
+    type Error = PercolatorError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SlabParam::SelfTradePrevention),
+            1 => Ok(SlabParam::RequireCommitment),
+            2 => Ok(SlabParam::ArgEnabled),
+            3 => Ok(SlabParam::ArgMode),
+            4 => Ok(SlabParam::ArgTaxBps),
+            5 => Ok(SlabParam::OracleMaxStalenessMs),
+            6 => Ok(SlabParam::OracleMaxConfBps),
+            7 => Ok(SlabParam::FundingInterestBps),
+            8 => Ok(SlabParam::FundingCapBps),
+            9 => Ok(SlabParam::SweepReward),
+            10 => Ok(SlabParam::ShortfallPolicy),
+            11 => Ok(SlabParam::LiqBufferBps),
+            12 => Ok(SlabParam::LiqBandBps),
+            13 => Ok(SlabParam::MaxTtlMs),
+            _ => Err(PercolatorError::InvalidInstruction),
+        }
+    }
+}
+
+/// Process update param instruction
+///
+/// # Arguments
+/// * `slab` - The slab state
+/// * `authority` - Signer of the instruction, must be the LP owner
+/// * `param` - Setting to change
+/// * `value` - New value of the setting
+pub fn process_update_param(
+    slab: &mut SlabState,
+    authority: &Pubkey,
+    param: SlabParam,
+    value: u64,
+) -> Result<(), PercolatorError> {
+    if *authority != slab.header.lp_owner {
+        msg!("Error: Only the LP owner can update slab params");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    match param {
+        SlabParam::SelfTradePrevention => {
+            let mode = u8::try_from(value)
+                .map_err(|_| PercolatorError::InvalidSelfTradeMode)
+                .and_then(SelfTradePrevention::try_from)?;
+            // The slab default cannot defer to itself
+            if mode == SelfTradePrevention::SlabDefault {
+                msg!("Error: Slab self-trade mode must be explicit");
+                return Err(PercolatorError::InvalidSelfTradeMode);
+            }
+            slab.header.stp_mode = mode;
+        }
+        SlabParam::RequireCommitment => {
+            slab.header.commit_reveal_required = read_flag(value)?;
+        }
+        SlabParam::ArgEnabled => {
+            slab.header.arg_enabled = read_flag(value)?;
+        }
+        SlabParam::ArgMode => {
+            slab.header.arg_mode = u8::try_from(value)
+                .map_err(|_| PercolatorError::InvalidInstruction)
+                .and_then(ArgMode::try_from)?;
+        }
+        SlabParam::ArgTaxBps => {
+            slab.header.arg_tax_bps = read_bps(value)?;
+        }
+        SlabParam::OracleMaxStalenessMs => {
+            if value == 0 {
+                msg!("Error: Oracle staleness limit must be positive");
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            slab.header.oracle_max_staleness_ms = value;
+        }
+        SlabParam::OracleMaxConfBps => {
+            slab.header.oracle_max_conf_bps = read_bps(value)?;
+        }
+        SlabParam::FundingInterestBps => {
+            slab.header.funding_interest_bps = read_bps(value)?;
+        }
+        SlabParam::FundingCapBps => {
+            slab.header.funding_cap_bps = read_bps(value)?;
+        }
+        SlabParam::SweepReward => {
+            slab.header.sweep_reward = value;
+        }
+        SlabParam::ShortfallPolicy => {
+            slab.header.shortfall_policy = u8::try_from(value)
+                .map_err(|_| PercolatorError::InvalidInstruction)
+                .and_then(ShortfallPolicy::try_from)?;
+        }
+        SlabParam::LiqBufferBps => {
+            slab.header.liq_buffer_bps = read_bps(value)?;
+        }
+        SlabParam::LiqBandBps => {
+            if value > MAX_LIQUIDATION_IMPACT_BPS {
+                msg!("Error: Liquidation band exceeds the maximum impact");
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            slab.header.liq_band_bps = value;
+        }
+        SlabParam::MaxTtlMs => {
+            if value == 0 {
+                msg!("Error: Maximum reservation TTL must be positive");
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            slab.header.max_ttl_ms = value;
+        }
+    }
+
+    slab.header.increment_seqno();
+    Ok(())
+}
+
+/// Interpret a boolean setting value
+fn read_flag(value: u64) -> Result<bool, PercolatorError> {
+    match value {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => {
+            msg!("Error: Flag value must be 0 or 1");
+            Err(PercolatorError::InvalidInstruction)
+        }
+    }
+}
+
+/// Interpret a basis-point setting value (at most 100%)
+fn read_bps(value: u64) -> Result<u64, PercolatorError> {
+    if value > 10_000 {
+        msg!("Error: Basis points must not exceed 10,000");
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::new_test_slab;
+
+    #[test]
+    fn test_update_stp_mode() {
+        let mut slab = new_test_slab();
+        let lp_owner = Pubkey::from([1; 32]);
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::SelfTradePrevention, 2).unwrap();
+        assert_eq!(slab.header.stp_mode, SelfTradePrevention::CancelResting);
+
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::SelfTradePrevention, 0),
+            Err(PercolatorError::InvalidSelfTradeMode)
+        );
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::SelfTradePrevention, 256),
+            Err(PercolatorError::InvalidSelfTradeMode)
+        );
+    }
+
+    #[test]
+    fn test_update_param_requires_lp_owner() {
+        let mut slab = new_test_slab();
+
+        assert_eq!(
+            process_update_param(&mut slab, &Pubkey::from([9; 32]), SlabParam::SelfTradePrevention, 1),
+            Err(PercolatorError::Unauthorized)
+        );
+        assert_eq!(slab.header.stp_mode, SelfTradePrevention::Skip);
+    }
+
+    #[test]
+    fn test_update_require_commitment() {
+        let mut slab = new_test_slab();
+        let lp_owner = Pubkey::from([1; 32]);
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::RequireCommitment, 1).unwrap();
+        assert!(slab.header.commit_reveal_required);
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::RequireCommitment, 2),
+            Err(PercolatorError::InvalidInstruction)
+        );
+    }
+
+    #[test]
+    fn test_update_arg_params() {
+        let mut slab = new_test_slab();
+        let lp_owner = Pubkey::from([1; 32]);
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::ArgEnabled, 0).unwrap();
+        process_update_param(&mut slab, &lp_owner, SlabParam::ArgMode, 2).unwrap();
+        process_update_param(&mut slab, &lp_owner, SlabParam::ArgTaxBps, 100).unwrap();
+        assert!(!slab.header.arg_enabled);
+        assert_eq!(slab.header.arg_mode, ArgMode::Reject);
+        assert_eq!(slab.header.arg_tax_bps, 100);
+
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::ArgMode, 3),
+            Err(PercolatorError::InvalidInstruction)
+        );
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::ArgTaxBps, 10_001),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+    }
+
+    #[test]
+    fn test_update_oracle_limits() {
+        let mut slab = new_test_slab();
+        let lp_owner = Pubkey::from([1; 32]);
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::OracleMaxStalenessMs, 5_000).unwrap();
+        process_update_param(&mut slab, &lp_owner, SlabParam::OracleMaxConfBps, 25).unwrap();
+        assert_eq!(slab.header.oracle_max_staleness_ms, 5_000);
+        assert_eq!(slab.header.oracle_max_conf_bps, 25);
+
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::OracleMaxStalenessMs, 0),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+    }
+
+    #[test]
+    fn test_update_funding_params() {
+        let mut slab = new_test_slab();
+        let lp_owner = Pubkey::from([1; 32]);
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::FundingInterestBps, 1).unwrap();
+        process_update_param(&mut slab, &lp_owner, SlabParam::FundingCapBps, 75).unwrap();
+        assert_eq!(slab.header.funding_interest_bps, 1);
+        assert_eq!(slab.header.funding_cap_bps, 75);
+
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::FundingCapBps, 10_001),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+    }
+
+    #[test]
+    fn test_update_sweep_reward() {
+        let mut slab = new_test_slab();
+        let lp_owner = Pubkey::from([1; 32]);
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::SweepReward, 0).unwrap();
+        assert_eq!(slab.header.sweep_reward, 0);
+        assert_eq!(SlabParam::try_from(9), Ok(SlabParam::SweepReward));
+    }
+
+    #[test]
+    fn test_update_max_ttl() {
+        let mut slab = new_test_slab();
+        let lp_owner = Pubkey::from([1; 32]);
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::MaxTtlMs, 5_000).unwrap();
+        assert_eq!(slab.header.max_ttl_ms, 5_000);
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::MaxTtlMs, 0),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+    }
+
+    #[test]
+    fn test_update_shortfall_policy() {
+        let mut slab = new_test_slab();
+        let lp_owner = Pubkey::from([1; 32]);
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::ShortfallPolicy, 1).unwrap();
+        assert_eq!(slab.header.shortfall_policy, ShortfallPolicy::StrictFail);
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::ShortfallPolicy, 2),
+            Err(PercolatorError::InvalidInstruction)
+        );
+    }
+
+    #[test]
+    fn test_update_liquidation_params() {
+        let mut slab = new_test_slab();
+        let lp_owner = Pubkey::from([1; 32]);
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::LiqBufferBps, 200).unwrap();
+        assert_eq!(slab.header.liq_buffer_bps, 200);
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::LiqBufferBps, 10_001),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+
+        process_update_param(&mut slab, &lp_owner, SlabParam::LiqBandBps, 200).unwrap();
+        assert_eq!(slab.header.liq_band_bps, 200);
+        assert_eq!(
+            process_update_param(&mut slab, &lp_owner, SlabParam::LiqBandBps, MAX_LIQUIDATION_IMPACT_BPS + 1),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+    }
+}
@@ -1,4 +1,4 @@
-#![no_std]
+#![cfg_attr(target_os = "solana", no_std)]
 
 pub mod state;
 pub mod instructions;
@@ -10,8 +10,8 @@ mod entrypoint;
 #[cfg(test)]
 mod tests;
 
-// Panic handler for no_std builds (not needed in tests)
-#[cfg(not(test))]
+// Panic handler for on-chain builds (host builds link std)
+#[cfg(target_os = "solana")]
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     loop {}
@@ -0,0 +1,168 @@
+//! Expiry index - reservations ordered by expiry time
+//!
+//! A binary min-heap over the live reservations, keyed by expiry, so the
+//! sweeper finds the next expired hold at the root instead of scanning the
+//! whole reservation pool. Each reservation remembers its heap position
+//! (`expiry_pos`), which lets `free_reservation` drop it from the index in
+//! O(log n) however it ends (commit, cancel or sweep).
+
+use super::{SlabState, POOL_RESERVATIONS};
+
+/// One indexed reservation
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExpiryEntry {
+    /// Expiry timestamp of the reservation (ms)
+    pub expiry_ms: u64,
+    /// Reservation index
+    pub resv_idx: u32,
+    /// Padding
+    pub _padding: u32,
+}
+
+impl SlabState {
+    /// Add a reservation to the expiry index
+    pub fn index_expiry(&mut self, resv_idx: u32) {
+        let expiry_ms = match self.get_reservation(resv_idx) {
+            Some(resv) if resv.expiry_pos == Self::INVALID_INDEX => resv.expiry_ms,
+            _ => return,
+        };
+
+        let pos = self.header.expiry_count as usize;
+        if pos >= POOL_RESERVATIONS {
+            return;
+        }
+        self.expiry_index[pos] = ExpiryEntry { expiry_ms, resv_idx, _padding: 0 };
+        self.reservations[resv_idx as usize].expiry_pos = pos as u32;
+        self.header.expiry_count += 1;
+        self.sift_up(pos);
+    }
+
+    /// Remove a reservation from the expiry index, if it is indexed
+    pub(crate) fn unindex_expiry(&mut self, resv_idx: u32) {
+        let pos = self.reservations[resv_idx as usize].expiry_pos;
+        if pos == Self::INVALID_INDEX || pos >= self.header.expiry_count {
+            return;
+        }
+        self.reservations[resv_idx as usize].expiry_pos = Self::INVALID_INDEX;
+
+        // Move the last entry into the hole and restore the heap around it
+        let last = self.header.expiry_count as usize - 1;
+        self.header.expiry_count -= 1;
+        let pos = pos as usize;
+        if pos == last {
+            return;
+        }
+        self.place_entry(pos, self.expiry_index[last]);
+        let pos = self.sift_up(pos);
+        self.sift_down(pos);
+    }
+
+    /// Reservation that expires first, if it expired before `current_ts`
+    pub fn next_expired(&self, current_ts: u64) -> Option<u32> {
+        if self.header.expiry_count == 0 {
+            return None;
+        }
+        let root = &self.expiry_index[0];
+        (current_ts > root.expiry_ms).then_some(root.resv_idx)
+    }
+
+    /// Write an entry at a heap position and point its reservation at it
+    fn place_entry(&mut self, pos: usize, entry: ExpiryEntry) {
+        self.expiry_index[pos] = entry;
+        self.reservations[entry.resv_idx as usize].expiry_pos = pos as u32;
+    }
+
+    /// Move an entry towards the root while it expires before its parent
+    fn sift_up(&mut self, mut pos: usize) -> usize {
+        let entry = self.expiry_index[pos];
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if self.expiry_index[parent].expiry_ms <= entry.expiry_ms {
+                break;
+            }
+            self.place_entry(pos, self.expiry_index[parent]);
+            pos = parent;
+        }
+        self.place_entry(pos, entry);
+        pos
+    }
+
+    /// Move an entry away from the root while a child expires before it
+    fn sift_down(&mut self, mut pos: usize) {
+        let count = self.header.expiry_count as usize;
+        let entry = self.expiry_index[pos];
+        loop {
+            let left = 2 * pos + 1;
+            if left >= count {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < count && self.expiry_index[right].expiry_ms < self.expiry_index[left].expiry_ms {
+                right
+            } else {
+                left
+            };
+            if entry.expiry_ms <= self.expiry_index[child].expiry_ms {
+                break;
+            }
+            self.place_entry(pos, self.expiry_index[child]);
+            pos = child;
+        }
+        self.place_entry(pos, entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::new_test_slab;
+
+    fn reserve_with_expiry(slab: &mut SlabState, expiry_ms: u64) -> u32 {
+        let idx = slab.alloc_reservation().unwrap();
+        slab.reservations[idx as usize].expiry_ms = expiry_ms;
+        slab.index_expiry(idx);
+        idx
+    }
+
+    /// Every entry expires no earlier than its parent and points back at itself
+    fn assert_heap(slab: &SlabState) {
+        for pos in 0..slab.header.expiry_count as usize {
+            let entry = slab.expiry_index[pos];
+            assert_eq!(slab.reservations[entry.resv_idx as usize].expiry_pos, pos as u32);
+            if pos > 0 {
+                assert!(slab.expiry_index[(pos - 1) / 2].expiry_ms <= entry.expiry_ms);
+            }
+        }
+    }
+
+    #[test]
+    fn test_expiry_index_orders_by_expiry() {
+        let mut slab = new_test_slab();
+        let mut idx = [0u32; 8];
+        for (i, expiry) in [800u64, 300, 500, 100, 700, 200, 600, 400].iter().enumerate() {
+            idx[i] = reserve_with_expiry(&mut slab, *expiry);
+        }
+        assert_eq!(slab.header.expiry_count, 8);
+        assert_heap(&slab);
+
+        assert_eq!(slab.next_expired(100), None);
+        assert_eq!(slab.next_expired(101), Some(idx[3]));
+
+        // Freeing pops from anywhere in the heap
+        slab.free_reservation(idx[3]);
+        slab.free_reservation(idx[6]);
+        assert_heap(&slab);
+        assert_eq!(slab.header.expiry_count, 6);
+
+        let mut order = [0u64; 6];
+        for slot in order.iter_mut() {
+            let next = slab.next_expired(u64::MAX).unwrap();
+            *slot = slab.reservations[next as usize].expiry_ms;
+            slab.free_reservation(next);
+            assert_heap(&slab);
+        }
+        assert_eq!(order, [200, 300, 400, 500, 700, 800]);
+        assert_eq!(slab.next_expired(u64::MAX), None);
+    }
+}
@@ -1,7 +1,62 @@
 //! Slab header - metadata and anti-toxicity parameters
 
+use percolator_common::{
+    ClockAnchor, OracleLimits, PercolatorError, SelfTradePrevention, DEFAULT_ORACLE_MAX_CONF_BPS,
+    DEFAULT_ORACLE_MAX_STALENESS_MS,
+};
 use pinocchio::pubkey::Pubkey;
 
+/// What the Aggressor Roundtrip Guard does with a taker trading back
+/// against its own earlier legs in the same batch
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArgMode {
+    /// Fill, but charge `arg_tax_bps` on the overlapping notional
+    #[default]
+    Tax = 0,
+    /// Fill only the part that does not overlap
+    Clip = 1,
+    /// Reject the commit with RoundtripDetected
+    Reject = 2,
+}
+
+impl TryFrom<u8> for ArgMode {
+    type Error = PercolatorError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ArgMode::Tax),
+            1 => Ok(ArgMode::Clip),
+            2 => Ok(ArgMode::Reject),
+            _ => Err(PercolatorError::InvalidInstruction),
+        }
+    }
+}
+
+/// What a commit does when a reserved slice is no longer backed by its
+/// maker order (the order left the slab or shrank below the slice)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShortfallPolicy {
+    /// Fill what is still backed and report the rest as shortfall
+    #[default]
+    AutoReduce = 0,
+    /// Reject the commit with ReservedSliceMissing
+    StrictFail = 1,
+}
+
+impl TryFrom<u8> for ShortfallPolicy {
+    type Error = PercolatorError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ShortfallPolicy::AutoReduce),
+            1 => Ok(ShortfallPolicy::StrictFail),
+            _ => Err(PercolatorError::InvalidInstruction),
+        }
+    }
+}
+
 /// Slab header with full anti-toxicity parameters
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -29,13 +84,35 @@ pub struct SlabHeader {
     pub maker_fee_bps: i64,
     /// Taker fee (basis points)
     pub taker_fee_bps: u64,
+    /// Margin above MM that liquidation restores (basis points of notional)
+    pub liq_buffer_bps: u64,
+    /// Furthest liquidation fills may trade from mark (basis points)
+    pub liq_band_bps: u64,
+
+    // === Oracle Limits ===
+    /// Maximum age of an oracle price (milliseconds)
+    pub oracle_max_staleness_ms: u64,
+    /// Maximum oracle confidence interval relative to price (basis points)
+    pub oracle_max_conf_bps: u64,
+
+    // === Funding Parameters ===
+    /// Interest-rate component added to every funding interval (basis points)
+    pub funding_interest_bps: u64,
+    /// Maximum absolute funding rate per interval (basis points)
+    pub funding_cap_bps: u64,
+
+    // === Keeper Parameters ===
+    /// Reward per expired reservation swept, paid by its taker (cash units, qty * price scale)
+    pub sweep_reward: u64,
+    /// Longest reservation TTL; longer requests are clamped to it (milliseconds)
+    pub max_ttl_ms: u64,
 
     // === Anti-Toxicity Parameters ===
     /// Batch window in milliseconds (e.g., 50-100 ms)
     pub batch_ms: u64,
-    /// Kill band in basis points (reject if mark moved > this amount)
+    /// Default kill band for new instruments in basis points (reject if mark moved > this amount)
     pub kill_band_bps: u64,
-    /// Freeze level count (top-K orders to freeze in batch)
+    /// Freeze level count (top-K price levels per side frozen at batch open)
     pub freeze_levels: u16,
     /// JIT penalty enabled flag
     pub jit_penalty_on: bool,
@@ -45,6 +122,14 @@ pub struct SlabHeader {
     pub arg_enabled: bool,
     /// ARG tax rate (basis points)
     pub arg_tax_bps: u64,
+    /// ARG action on a detected roundtrip
+    pub arg_mode: ArgMode,
+    /// Self-trade prevention mode (reservations may override it)
+    pub stp_mode: SelfTradePrevention,
+    /// Reject reservations without a commitment hash
+    pub commit_reveal_required: bool,
+    /// Commit action on reserved slices whose maker order no longer backs them
+    pub shortfall_policy: ShortfallPolicy,
 
     // === State Tracking ===
     /// Current epoch
@@ -57,16 +142,16 @@ pub struct SlabHeader {
     pub last_batch_open_ts: u64,
     /// Last funding update timestamp
     pub last_funding_ts: u64,
-    /// Mark price from oracle (1e6 scale)
-    pub mark_px: i64,
-    /// Previous mark price (for kill band check)
-    pub prev_mark_px: i64,
+    /// Last Clock sysvar reading, used for millisecond timestamps
+    pub clock: ClockAnchor,
 
     // === Pool Counts ===
     /// Number of active instruments
     pub instrument_count: u16,
     /// Number of active accounts
     pub account_count: u16,
+    /// Number of registered designated LPs
+    pub dlp_count: u16,
     /// Number of active orders
     pub order_count: u32,
     /// Number of active positions
@@ -75,8 +160,14 @@ pub struct SlabHeader {
     pub reservation_count: u32,
     /// Number of active slices
     pub slice_count: u32,
+    /// Number of price levels in use
+    pub level_count: u32,
+    /// Number of reservations in the expiry index
+    pub expiry_count: u32,
     /// Trade ring write index
     pub trade_write_idx: u32,
+    /// Funding history ring write index
+    pub funding_write_idx: u32,
 
     // === Freelist Heads ===
     /// Order pool freelist head
@@ -87,11 +178,15 @@ pub struct SlabHeader {
     pub reservation_freelist_head: u32,
     /// Slice pool freelist head
     pub slice_freelist_head: u32,
+    /// Price level pool freelist head
+    pub level_freelist_head: u32,
+    /// Aggressor ledger freelist head
+    pub aggressor_freelist_head: u32,
 
     /// Bump seed
     pub bump: u8,
     /// Padding for alignment
-    pub _padding: [u8; 7],
+    pub _padding: [u8; 3],
 }
 
 impl SlabHeader {
@@ -124,6 +219,17 @@ impl SlabHeader {
             mmr_bps,
             maker_fee_bps,
             taker_fee_bps,
+            liq_buffer_bps: 100, // Restore MM + 1%
+            liq_band_bps: 500,   // Fill within 5% of mark
+            // Oracle defaults
+            oracle_max_staleness_ms: DEFAULT_ORACLE_MAX_STALENESS_MS,
+            oracle_max_conf_bps: DEFAULT_ORACLE_MAX_CONF_BPS,
+            // Funding defaults
+            funding_interest_bps: 0,
+            funding_cap_bps: 50, // 0.5% per interval
+            // Keeper defaults
+            sweep_reward: 10_000_000_000, // $0.01 per swept reservation
+            max_ttl_ms: 30_000,           // Holds lapse within 30s
             // Anti-toxicity defaults
             batch_ms,
             kill_band_bps: 100, // 1% default kill band
@@ -132,29 +238,38 @@ impl SlabHeader {
             maker_rebate_min_ms: 50, // 50ms min age for rebate
             arg_enabled: true,
             arg_tax_bps: 50, // 0.5% ARG tax
+            arg_mode: ArgMode::Tax,
+            stp_mode: SelfTradePrevention::Skip,
+            commit_reveal_required: false,
+            shortfall_policy: ShortfallPolicy::AutoReduce,
             // State
             current_epoch: 0,
             next_order_id: 1,
             next_hold_id: 1,
             last_batch_open_ts: 0,
             last_funding_ts: 0,
-            mark_px: 0,
-            prev_mark_px: 0,
+            clock: ClockAnchor::default(),
             // Pool counts
             instrument_count: 0,
             account_count: 0,
+            dlp_count: 0,
             order_count: 0,
             position_count: 0,
             reservation_count: 0,
             slice_count: 0,
+            level_count: 0,
+            expiry_count: 0,
             trade_write_idx: 0,
+            funding_write_idx: 0,
             // Freelist heads (initialized to INVALID_INDEX = empty)
             order_freelist_head: Self::INVALID_INDEX,
             position_freelist_head: Self::INVALID_INDEX,
             reservation_freelist_head: Self::INVALID_INDEX,
             slice_freelist_head: Self::INVALID_INDEX,
+            level_freelist_head: Self::INVALID_INDEX,
+            aggressor_freelist_head: Self::INVALID_INDEX,
             bump,
-            _padding: [0; 7],
+            _padding: [0; 3],
         }
     }
 
@@ -183,16 +298,12 @@ impl SlabHeader {
         id
     }
 
-    /// Check if mark price moved beyond kill band
-    pub fn check_kill_band(&self, new_mark_px: i64) -> bool {
-        if self.prev_mark_px == 0 {
-            return true; // No previous price, allow
+    /// Oracle acceptance limits
+    pub fn oracle_limits(&self) -> OracleLimits {
+        OracleLimits {
+            max_staleness_ms: self.oracle_max_staleness_ms,
+            max_conf_bps: self.oracle_max_conf_bps,
         }
-
-        let diff = (new_mark_px - self.prev_mark_px).abs();
-        let threshold = (self.prev_mark_px.abs() as u64 * self.kill_band_bps) / 10_000;
-        
-        diff <= threshold as i64
     }
 
     /// Check if an order qualifies for JIT penalty (posted too recently)
@@ -203,10 +314,15 @@ impl SlabHeader {
         current_ts.saturating_sub(order_created_ts) < self.maker_rebate_min_ms
     }
 
-    /// Update mark price (stores previous for kill band check)
-    pub fn update_mark_px(&mut self, new_mark_px: i64) {
-        self.prev_mark_px = self.mark_px;
-        self.mark_px = new_mark_px;
+    /// Resolve the self-trade prevention mode for a taker order
+    ///
+    /// The slab's mode is a floor: a taker may pick another way of preventing
+    /// self-trades, but may only ask for `Allow` if the slab allows them too.
+    pub fn resolve_stp(&self, requested: SelfTradePrevention) -> SelfTradePrevention {
+        match requested {
+            SelfTradePrevention::SlabDefault | SelfTradePrevention::Allow => self.stp_mode,
+            mode => mode,
+        }
     }
 }
 
@@ -277,7 +393,7 @@ mod tests {
     }
 
     #[test]
-    fn test_kill_band_check() {
+    fn test_resolve_stp() {
         let mut header = SlabHeader::new(
             Pubkey::default(),
             Pubkey::default(),
@@ -285,15 +401,16 @@ mod tests {
             500, 250, -5, 20, 100, 255,
         );
 
-        // Set initial mark price
-        header.update_mark_px(50_000_000_000); // $50,000
-        
-        // Move within kill band (1%)
-        assert!(header.check_kill_band(50_400_000_000)); // $50,400 (0.8% move)
-        
-        // Move beyond kill band (>1%)
-        header.update_mark_px(50_400_000_000);
-        assert!(!header.check_kill_band(51_500_000_000)); // $51,500 (2.2% from prev)
+        header.stp_mode = SelfTradePrevention::CancelResting;
+        assert_eq!(header.resolve_stp(SelfTradePrevention::SlabDefault), SelfTradePrevention::CancelResting);
+        assert_eq!(header.resolve_stp(SelfTradePrevention::Skip), SelfTradePrevention::Skip);
+
+        // Takers cannot loosen the slab's mode
+        assert_eq!(header.resolve_stp(SelfTradePrevention::Allow), SelfTradePrevention::CancelResting);
+
+        header.stp_mode = SelfTradePrevention::Allow;
+        assert_eq!(header.resolve_stp(SelfTradePrevention::Allow), SelfTradePrevention::Allow);
+        assert_eq!(header.resolve_stp(SelfTradePrevention::DecrementBoth), SelfTradePrevention::DecrementBoth);
     }
 
     #[test]
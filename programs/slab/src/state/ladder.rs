@@ -0,0 +1,621 @@
+//! Price-level index - per book side AVL tree over distinct prices
+//!
+//! Every book side (live/pending bids/asks of an instrument) keeps its
+//! orders in one list sorted by price-time priority. The index adds one node
+//! per distinct price on the side, pointing at the oldest and newest order
+//! at that price. Inserts find their place in O(log levels) instead of
+//! walking the side, and a level's orders can be visited without scanning
+//! the ones ahead of it. Each level also keeps the total resting and reserved
+//! quantity of its orders, so its takeable size is read without a walk.
+//! Matching still walks the list from the head.
+
+use super::SlabState;
+use percolator_common::{OrderState, Side};
+
+/// Level pool size (one level per order in the worst case)
+pub const POOL_LEVELS: usize = super::POOL_ORDERS;
+
+/// Deepest AVL path for POOL_LEVELS nodes (1.44 * log2(n) + 2, rounded up)
+const MAX_LEVEL_DEPTH: usize = 32;
+
+/// One distinct price on a book side
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriceLevel {
+    /// Price of every order on the level
+    pub price: u64,
+    /// Total resting quantity of the level's orders
+    pub qty: u64,
+    /// Total reserved quantity of the level's orders
+    pub reserved_qty: u64,
+    /// Oldest order at this price
+    pub head: u32,
+    /// Newest order at this price
+    pub tail: u32,
+    /// Subtree of lower prices (next free level while unused)
+    pub left: u32,
+    /// Subtree of higher prices
+    pub right: u32,
+    /// Number of orders at this price
+    pub order_count: u32,
+    /// AVL subtree height (1 for a leaf)
+    pub height: u8,
+    /// Used flag
+    pub used: bool,
+    /// Padding
+    pub _padding: [u8; 2],
+}
+
+impl SlabState {
+    // === Level Pool Operations ===
+
+    /// Allocate a level from the freelist
+    fn alloc_level(&mut self) -> Option<u32> {
+        let head = self.header.level_freelist_head;
+        if head == Self::INVALID_INDEX {
+            return None;
+        }
+
+        let level = &mut self.levels[head as usize];
+        self.header.level_freelist_head = level.left;
+        *level = PriceLevel {
+            left: Self::INVALID_INDEX,
+            right: Self::INVALID_INDEX,
+            height: 1,
+            used: true,
+            ..PriceLevel::default()
+        };
+        self.header.level_count += 1;
+        Some(head)
+    }
+
+    /// Return a level to the freelist
+    fn free_level(&mut self, idx: u32) {
+        let level = &mut self.levels[idx as usize];
+        level.used = false;
+        level.left = self.header.level_freelist_head;
+        self.header.level_freelist_head = idx;
+        self.header.level_count -= 1;
+    }
+
+    /// Get level by index
+    pub fn get_level(&self, idx: u32) -> Option<&PriceLevel> {
+        if idx as usize >= POOL_LEVELS {
+            return None;
+        }
+        let level = &self.levels[idx as usize];
+        if level.used { Some(level) } else { None }
+    }
+
+    /// Find the level of a book side at `price`
+    pub fn find_level(&self, instrument_idx: u16, state: OrderState, side: Side, price: u64) -> Option<&PriceLevel> {
+        let root = self.level_root(instrument_idx, state, side);
+        self.get_level(self.find_level_idx(root, price))
+    }
+
+    /// Quantity resting on a level that is not locked by reservations
+    pub fn level_available_qty(&self, level: &PriceLevel) -> u64 {
+        level.qty.saturating_sub(level.reserved_qty)
+    }
+
+    /// Set a booked order's resting and reserved quantity
+    ///
+    /// Keeps the totals of the order's price level in step. Orders outside
+    /// the book (being placed or re-inserted) are written directly instead.
+    pub fn set_order_qty(&mut self, order_idx: u32, qty: u64, reserved_qty: u64) {
+        let order = match self.get_order(order_idx) {
+            Some(o) => *o,
+            None => return,
+        };
+
+        let root = self.level_root(order.instrument_idx, order.state, order.side);
+        let level_idx = self.find_level_idx(root, order.price);
+        if let Some(level) = self.levels.get_mut(level_idx as usize).filter(|l| l.used) {
+            level.qty = level.qty.saturating_sub(order.qty).saturating_add(qty);
+            level.reserved_qty = level
+                .reserved_qty
+                .saturating_sub(order.reserved_qty)
+                .saturating_add(reserved_qty);
+        }
+
+        let order = &mut self.orders[order_idx as usize];
+        order.qty = qty;
+        order.reserved_qty = reserved_qty;
+    }
+
+    // === Book Linkage ===
+
+    /// Neighbours a new order at `price` is linked between
+    ///
+    /// Registers the order's price level, creating it if needed. Returns
+    /// (prev, next) in the side's list: after the newest order at the same
+    /// price, or after the newest order of the next better price.
+    pub(crate) fn link_level(
+        &mut self,
+        instrument_idx: u16,
+        state: OrderState,
+        side: Side,
+        price: u64,
+        order_idx: u32,
+        side_head: u32,
+    ) -> Option<(u32, u32)> {
+        let root = self.level_root(instrument_idx, state, side);
+        let existing = self.find_level_idx(root, price);
+        let (qty, reserved_qty) = {
+            let order = &self.orders[order_idx as usize];
+            (order.qty, order.reserved_qty)
+        };
+
+        if existing != Self::INVALID_INDEX {
+            let level = &mut self.levels[existing as usize];
+            let prev = level.tail;
+            level.tail = order_idx;
+            level.order_count += 1;
+            level.qty = level.qty.saturating_add(qty);
+            level.reserved_qty = level.reserved_qty.saturating_add(reserved_qty);
+            return Some((prev, self.orders[prev as usize].next));
+        }
+
+        let better = self.better_level_idx(root, price, side);
+        let prev = match self.get_level(better) {
+            Some(level) => level.tail,
+            None => Self::INVALID_INDEX,
+        };
+        let next = match self.get_order(prev) {
+            Some(order) => order.next,
+            None => side_head,
+        };
+
+        let new_idx = self.alloc_level()?;
+        let level = &mut self.levels[new_idx as usize];
+        level.price = price;
+        level.head = order_idx;
+        level.tail = order_idx;
+        level.order_count = 1;
+        level.qty = qty;
+        level.reserved_qty = reserved_qty;
+
+        let new_root = self.insert_level(root, new_idx);
+        self.set_level_root(instrument_idx, state, side, new_root);
+        Some((prev, next))
+    }
+
+    /// Drop an order from its price level, removing the level once empty
+    ///
+    /// Must be called while the order's list links are still intact.
+    pub(crate) fn unlink_level(&mut self, instrument_idx: u16, state: OrderState, side: Side, order_idx: u32) {
+        let (price, prev, next, qty, reserved_qty) = match self.get_order(order_idx) {
+            Some(o) => (o.price, o.prev, o.next, o.qty, o.reserved_qty),
+            None => return,
+        };
+
+        let root = self.level_root(instrument_idx, state, side);
+        let level_idx = self.find_level_idx(root, price);
+        let level = match self.levels.get_mut(level_idx as usize) {
+            Some(l) if l.used => l,
+            _ => return,
+        };
+
+        level.order_count -= 1;
+        level.qty = level.qty.saturating_sub(qty);
+        level.reserved_qty = level.reserved_qty.saturating_sub(reserved_qty);
+        if level.order_count == 0 {
+            let new_root = self.remove_level(root, price);
+            self.set_level_root(instrument_idx, state, side, new_root);
+            return;
+        }
+        if level.head == order_idx {
+            level.head = next;
+        }
+        if level.tail == order_idx {
+            level.tail = prev;
+        }
+    }
+
+    /// Root of a book side's level tree
+    fn level_root(&self, instrument_idx: u16, state: OrderState, side: Side) -> u32 {
+        let instr = match self.get_instrument(instrument_idx) {
+            Some(i) => i,
+            None => return Self::INVALID_INDEX,
+        };
+        match (state, side) {
+            (OrderState::LIVE, Side::Buy) => instr.bids_root,
+            (OrderState::LIVE, Side::Sell) => instr.asks_root,
+            (OrderState::PENDING, Side::Buy) => instr.bids_pending_root,
+            (OrderState::PENDING, Side::Sell) => instr.asks_pending_root,
+        }
+    }
+
+    fn set_level_root(&mut self, instrument_idx: u16, state: OrderState, side: Side, root: u32) {
+        if let Some(instr) = self.get_instrument_mut(instrument_idx) {
+            match (state, side) {
+                (OrderState::LIVE, Side::Buy) => instr.bids_root = root,
+                (OrderState::LIVE, Side::Sell) => instr.asks_root = root,
+                (OrderState::PENDING, Side::Buy) => instr.bids_pending_root = root,
+                (OrderState::PENDING, Side::Sell) => instr.asks_pending_root = root,
+            }
+        }
+    }
+
+    // === AVL Tree ===
+
+    fn find_level_idx(&self, root: u32, price: u64) -> u32 {
+        let mut idx = root;
+        while let Some(level) = self.get_level(idx) {
+            if price == level.price {
+                return idx;
+            }
+            idx = if price < level.price { level.left } else { level.right };
+        }
+        Self::INVALID_INDEX
+    }
+
+    /// Closest level that ranks ahead of `price` on `side`
+    ///
+    /// The highest price below it for asks, the lowest price above it for bids.
+    fn better_level_idx(&self, root: u32, price: u64, side: Side) -> u32 {
+        let mut best = Self::INVALID_INDEX;
+        let mut idx = root;
+        while let Some(level) = self.get_level(idx) {
+            let ahead = match side {
+                Side::Buy => level.price > price,
+                Side::Sell => level.price < price,
+            };
+            if ahead {
+                best = idx;
+            }
+            idx = if price < level.price { level.left } else { level.right };
+        }
+        best
+    }
+
+    fn level_height(&self, idx: u32) -> u8 {
+        self.get_level(idx).map(|l| l.height).unwrap_or(0)
+    }
+
+    fn update_level_height(&mut self, idx: u32) {
+        let level = self.levels[idx as usize];
+        let height = self.level_height(level.left).max(self.level_height(level.right)) + 1;
+        self.levels[idx as usize].height = height;
+    }
+
+    fn balance_factor(&self, idx: u32) -> i16 {
+        let level = &self.levels[idx as usize];
+        self.level_height(level.left) as i16 - self.level_height(level.right) as i16
+    }
+
+    fn rotate_right(&mut self, idx: u32) -> u32 {
+        let pivot = self.levels[idx as usize].left;
+        self.levels[idx as usize].left = self.levels[pivot as usize].right;
+        self.levels[pivot as usize].right = idx;
+        self.update_level_height(idx);
+        self.update_level_height(pivot);
+        pivot
+    }
+
+    fn rotate_left(&mut self, idx: u32) -> u32 {
+        let pivot = self.levels[idx as usize].right;
+        self.levels[idx as usize].right = self.levels[pivot as usize].left;
+        self.levels[pivot as usize].left = idx;
+        self.update_level_height(idx);
+        self.update_level_height(pivot);
+        pivot
+    }
+
+    /// Restore the AVL invariant at `idx`, returning the subtree's new root
+    fn rebalance_level(&mut self, idx: u32) -> u32 {
+        self.update_level_height(idx);
+        let balance = self.balance_factor(idx);
+
+        if balance > 1 {
+            let left = self.levels[idx as usize].left;
+            if self.balance_factor(left) < 0 {
+                self.levels[idx as usize].left = self.rotate_left(left);
+            }
+            return self.rotate_right(idx);
+        }
+        if balance < -1 {
+            let right = self.levels[idx as usize].right;
+            if self.balance_factor(right) > 0 {
+                self.levels[idx as usize].right = self.rotate_right(right);
+            }
+            return self.rotate_left(idx);
+        }
+        idx
+    }
+
+    /// Point `parent` (or the root) at a rebuilt subtree
+    fn relink_level(&mut self, path: &[u32], depth: usize, child: u32, root: &mut u32) {
+        if depth == 0 {
+            *root = child;
+            return;
+        }
+        let parent = path[depth - 1];
+        let child_price = self.levels[child as usize].price;
+        if child_price < self.levels[parent as usize].price {
+            self.levels[parent as usize].left = child;
+        } else {
+            self.levels[parent as usize].right = child;
+        }
+    }
+
+    /// Rebalance every node on `path`, bottom up
+    fn rebalance_path(&mut self, path: &[u32], mut root: u32) -> u32 {
+        for depth in (0..path.len()).rev() {
+            let subtree = self.rebalance_level(path[depth]);
+            self.relink_level(path, depth, subtree, &mut root);
+        }
+        root
+    }
+
+    /// Insert a fresh level node, returning the new root
+    fn insert_level(&mut self, root: u32, new_idx: u32) -> u32 {
+        let price = self.levels[new_idx as usize].price;
+        let mut path = [Self::INVALID_INDEX; MAX_LEVEL_DEPTH];
+        let mut depth = 0usize;
+
+        let mut idx = root;
+        while let Some(level) = self.get_level(idx) {
+            path[depth] = idx;
+            depth += 1;
+            idx = if price < level.price { level.left } else { level.right };
+        }
+
+        if depth == 0 {
+            return new_idx;
+        }
+        let parent = path[depth - 1];
+        if price < self.levels[parent as usize].price {
+            self.levels[parent as usize].left = new_idx;
+        } else {
+            self.levels[parent as usize].right = new_idx;
+        }
+
+        self.rebalance_path(&path[..depth], root)
+    }
+
+    /// Remove the level at `price`, returning the new root
+    fn remove_level(&mut self, root: u32, price: u64) -> u32 {
+        let mut path = [Self::INVALID_INDEX; MAX_LEVEL_DEPTH];
+        let mut depth = 0usize;
+
+        let mut idx = root;
+        while let Some(level) = self.get_level(idx) {
+            if level.price == price {
+                break;
+            }
+            path[depth] = idx;
+            depth += 1;
+            idx = if price < level.price { level.left } else { level.right };
+        }
+        if self.get_level(idx).is_none() {
+            return root;
+        }
+
+        let target = self.levels[idx as usize];
+        let mut root = root;
+
+        if target.left != Self::INVALID_INDEX && target.right != Self::INVALID_INDEX {
+            // Two children: move the next higher level's contents into this
+            // node and unlink that level instead. Nothing outside the tree
+            // refers to level indices, so the swap is invisible.
+            path[depth] = idx;
+            depth += 1;
+            let mut succ = target.right;
+            while self.levels[succ as usize].left != Self::INVALID_INDEX {
+                path[depth] = succ;
+                depth += 1;
+                succ = self.levels[succ as usize].left;
+            }
+
+            let moved = self.levels[succ as usize];
+            let node = &mut self.levels[idx as usize];
+            node.price = moved.price;
+            node.head = moved.head;
+            node.tail = moved.tail;
+            node.order_count = moved.order_count;
+            node.qty = moved.qty;
+            node.reserved_qty = moved.reserved_qty;
+
+            // The successor has no left child; its parent takes its right one
+            let parent = path[depth - 1];
+            if parent == idx {
+                self.levels[parent as usize].right = moved.right;
+            } else {
+                self.levels[parent as usize].left = moved.right;
+            }
+            self.free_level(succ);
+        } else {
+            let child = if target.left != Self::INVALID_INDEX { target.left } else { target.right };
+            if depth == 0 {
+                root = child;
+            } else {
+                let parent = path[depth - 1];
+                if self.levels[parent as usize].left == idx {
+                    self.levels[parent as usize].left = child;
+                } else {
+                    self.levels[parent as usize].right = child;
+                }
+            }
+            self.free_level(idx);
+        }
+
+        self.rebalance_path(&path[..depth], root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{process_cancel_order, process_place_order, process_reserve, OrderParams, TakerOptions};
+    use crate::tests::{new_test_slab, test_account, test_dlp};
+    use percolator_common::*;
+
+    const PX: u64 = 50_000_000_000;
+    const TICK: u64 = 1_000_000;
+
+    fn post(slab: &mut SlabState, side: Side, price: u64) -> u32 {
+        let maker = test_dlp(slab, 10);
+        let params = OrderParams {
+            instrument_idx: 0,
+            side,
+            price,
+            qty: 1_000_000,
+            tif: TimeInForce::GTC,
+            maker_class: MakerClass::DLP,
+            flags: 0,
+        };
+        process_place_order(slab, maker, &params, 0).unwrap().order_idx
+    }
+
+    /// Walk a side and check it is sorted and agrees with the level tree
+    fn check_side(slab: &SlabState, side: Side) -> usize {
+        let instr = &slab.instruments[0];
+        let (mut idx, root) = match side {
+            Side::Buy => (instr.bids_head, instr.bids_root),
+            Side::Sell => (instr.asks_head, instr.asks_root),
+        };
+        let mut prev_px: Option<u64> = None;
+        let mut levels = 0usize;
+
+        while let Some(order) = slab.get_order(idx) {
+            if prev_px != Some(order.price) {
+                if let Some(px) = prev_px {
+                    match side {
+                        Side::Buy => assert!(order.price < px),
+                        Side::Sell => assert!(order.price > px),
+                    }
+                }
+                let level = slab.find_level(0, OrderState::LIVE, side, order.price).unwrap();
+                assert_eq!(level.head, idx);
+                levels += 1;
+                prev_px = Some(order.price);
+            }
+            let level = slab.find_level(0, OrderState::LIVE, side, order.price).unwrap();
+            if order.next == SlabState::INVALID_INDEX
+                || slab.get_order(order.next).unwrap().price != order.price
+            {
+                assert_eq!(level.tail, idx);
+            }
+            idx = order.next;
+        }
+
+        // Balanced: height within AVL bounds of the level count
+        let height = slab.get_level(root).map(|l| l.height).unwrap_or(0) as usize;
+        assert!(height <= 2 * (usize::BITS - levels.leading_zeros()) as usize + 1);
+        levels
+    }
+
+    #[test]
+    fn test_price_level_size() {
+        assert_eq!(core::mem::size_of::<PriceLevel>(), 48);
+    }
+
+    #[test]
+    fn test_inserts_keep_price_time_priority() {
+        let mut slab = new_test_slab();
+        let prices = [7, 3, 9, 3, 1, 8, 7, 2, 10, 5, 3, 6, 4];
+
+        let mut first_at_3 = None;
+        for &p in &prices {
+            let idx = post(&mut slab, Side::Sell, PX + p * TICK);
+            if p == 3 && first_at_3.is_none() {
+                first_at_3 = Some(idx);
+            }
+        }
+        assert_eq!(check_side(&slab, Side::Sell), 10);
+
+        let level = slab.find_level(0, OrderState::LIVE, Side::Sell, PX + 3 * TICK).unwrap();
+        assert_eq!(level.order_count, 3);
+        assert_eq!(level.head, first_at_3.unwrap());
+        assert_eq!(slab.level_available_qty(level), 3_000_000);
+
+        let best = slab.get_order(slab.instruments[0].asks_head).unwrap();
+        assert_eq!(best.price, PX + TICK);
+    }
+
+    #[test]
+    fn test_removals_drop_empty_levels() {
+        let mut slab = new_test_slab();
+        let mut orders = [0u32; 128];
+        for p in 0..64u64 {
+            // Interleave prices so the tree sees rotations both ways
+            let px = PX - ((p * 37) % 64) * TICK;
+            orders[2 * p as usize] = post(&mut slab, Side::Buy, px);
+            orders[2 * p as usize + 1] = post(&mut slab, Side::Buy, px);
+        }
+        assert_eq!(check_side(&slab, Side::Buy), 64);
+        assert_eq!(slab.header.level_count, 64);
+
+        // Remove one of each pair, then every other remaining order
+        for (i, &idx) in orders.iter().enumerate() {
+            if i % 2 == 0 {
+                slab.remove_order_from_book(idx);
+            }
+        }
+        assert_eq!(check_side(&slab, Side::Buy), 64);
+
+        for (i, &idx) in orders.iter().enumerate() {
+            if i % 4 == 1 {
+                slab.remove_order_from_book(idx);
+            }
+        }
+        assert_eq!(check_side(&slab, Side::Buy), 32);
+        assert_eq!(slab.header.level_count, 32);
+
+        for (i, &idx) in orders.iter().enumerate() {
+            if i % 4 == 3 {
+                slab.remove_order_from_book(idx);
+            }
+        }
+        assert_eq!(check_side(&slab, Side::Buy), 0);
+        assert_eq!(slab.header.level_count, 0);
+        assert_eq!(slab.instruments[0].bids_root, SlabState::INVALID_INDEX);
+    }
+
+    #[test]
+    fn test_pending_orders_have_their_own_levels() {
+        let mut slab = new_test_slab();
+        let live = post(&mut slab, Side::Buy, PX);
+        let taker = test_account(&mut slab, 11);
+        let params = OrderParams {
+            instrument_idx: 0,
+            side: Side::Buy,
+            price: PX,
+            qty: 1_000_000,
+            tif: TimeInForce::GTC,
+            maker_class: MakerClass::REG,
+            flags: 0,
+        };
+        let pending = process_place_order(&mut slab, taker, &params, 0).unwrap().order_idx;
+
+        assert_eq!(slab.find_level(0, OrderState::LIVE, Side::Buy, PX).unwrap().head, live);
+        assert_eq!(slab.find_level(0, OrderState::PENDING, Side::Buy, PX).unwrap().head, pending);
+
+        // Promotion moves the order to the back of the live level
+        slab.promote_pending_orders(0, u64::MAX);
+        let level = slab.find_level(0, OrderState::LIVE, Side::Buy, PX).unwrap();
+        assert_eq!((level.head, level.tail, level.order_count), (live, pending, 2));
+        assert!(slab.find_level(0, OrderState::PENDING, Side::Buy, PX).is_none());
+    }
+
+    #[test]
+    fn test_level_totals_follow_reservations() {
+        let mut slab = new_test_slab();
+        post(&mut slab, Side::Sell, PX);
+        let second = post(&mut slab, Side::Sell, PX);
+        let taker = test_account(&mut slab, 11);
+
+        process_reserve(&mut slab, taker, 0, Side::Buy, 1_500_000, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+        let level = *slab.find_level(0, OrderState::LIVE, Side::Sell, PX).unwrap();
+        assert_eq!((level.qty, level.reserved_qty), (2_000_000, 1_500_000));
+        assert_eq!(slab.level_available_qty(&level), 500_000);
+
+        // Cancelling a partly reserved order cuts it back to its reservation
+        let maker = slab.orders[second as usize].account_idx;
+        process_cancel_order(&mut slab, second, maker, 0).unwrap();
+        let level = *slab.find_level(0, OrderState::LIVE, Side::Sell, PX).unwrap();
+        assert_eq!((level.qty, level.reserved_qty), (1_500_000, 1_500_000));
+        assert_eq!(slab.level_available_qty(&level), 0);
+    }
+}
@@ -3,9 +3,13 @@ pub mod slab;
 pub mod quote_cache;
 pub mod fill_receipt;
 pub mod insurance;
+pub mod ladder;
+pub mod expiry;
 
 pub use header::*;
 pub use slab::*;
 pub use quote_cache::*;
 pub use fill_receipt::*;
 pub use insurance::*;
+pub use ladder::*;
+pub use expiry::*;
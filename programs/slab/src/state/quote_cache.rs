@@ -1,4 +1,17 @@
 //! Quote cache - router-readable best bid/ask levels
+//!
+//! Every instrument has its own cache at a fixed offset in the slab account
+//! (`SlabState::quote_cache_offset`), rebuilt from the live book whenever the
+//! book changes. The cache's `seqno_snapshot` is the instrument's book
+//! sequence number: the `header.seqno` of the last change to that book. Slab
+//! changes that leave the book alone (funding, marks, params, other
+//! instruments) do not move it, and reservations report it as `book_seqno`.
+
+use super::SlabState;
+use percolator_common::{OrderState, Side};
+
+/// Number of levels cached per side
+pub const QUOTE_LEVELS: usize = 4;
 
 /// Single price level in the book
 #[repr(C)]
@@ -21,14 +34,14 @@ impl Default for QuoteLevel {
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct QuoteCache {
-    /// Snapshot of header.seqno when cache was last written
+    /// Book sequence number: header.seqno when the instrument's book last changed
     pub seqno_snapshot: u32,
     /// Padding
     pub _padding: u32,
     /// Best 4 bid levels (sorted descending by price)
-    pub best_bids: [QuoteLevel; 4],
+    pub best_bids: [QuoteLevel; QUOTE_LEVELS],
     /// Best 4 ask levels (sorted ascending by price)
-    pub best_asks: [QuoteLevel; 4],
+    pub best_asks: [QuoteLevel; QUOTE_LEVELS],
 }
 
 impl QuoteCache {
@@ -39,8 +52,8 @@ impl QuoteCache {
         Self {
             seqno_snapshot: 0,
             _padding: 0,
-            best_bids: [QuoteLevel::default(); 4],
-            best_asks: [QuoteLevel::default(); 4],
+            best_bids: [QuoteLevel::default(); QUOTE_LEVELS],
+            best_asks: [QuoteLevel::default(); QUOTE_LEVELS],
         }
     }
 
@@ -49,7 +62,7 @@ impl QuoteCache {
         self.seqno_snapshot = seqno;
 
         // Copy up to 4 best levels
-        for i in 0..4 {
+        for i in 0..QUOTE_LEVELS {
             if i < bids.len() {
                 self.best_bids[i] = bids[i];
             } else {
@@ -75,9 +88,102 @@ impl QuoteCache {
     }
 }
 
+impl SlabState {
+    /// Get an instrument's quote cache
+    pub fn get_quote_cache(&self, instrument_idx: u16) -> Option<&QuoteCache> {
+        if instrument_idx >= self.header.instrument_count {
+            return None;
+        }
+        Some(&self.quote_caches[instrument_idx as usize])
+    }
+
+    /// Rebuild an instrument's quote cache from its live book
+    ///
+    /// Caches the best levels with quantity left to take (qty - reserved_qty)
+    /// and stamps them with the current `header.seqno` as the book's sequence
+    /// number. Call after the seqno of a book change has been taken.
+    pub fn refresh_quote_cache(&mut self, instrument_idx: u16) {
+        let (bids_head, asks_head) = match self.get_instrument(instrument_idx) {
+            Some(instr) => (instr.bids_head, instr.asks_head),
+            None => return,
+        };
+
+        let mut bids = [QuoteLevel::default(); QUOTE_LEVELS];
+        let mut asks = [QuoteLevel::default(); QUOTE_LEVELS];
+        let bid_count = self.best_levels(instrument_idx, Side::Buy, bids_head, &mut bids);
+        let ask_count = self.best_levels(instrument_idx, Side::Sell, asks_head, &mut asks);
+
+        let seqno = self.header.seqno;
+        self.quote_caches[instrument_idx as usize].update(seqno, &bids[..bid_count], &asks[..ask_count]);
+    }
+
+    /// Rebuild the quote cache of every instrument
+    pub fn refresh_all_quote_caches(&mut self) {
+        for i in 0..self.header.instrument_count {
+            self.refresh_quote_cache(i);
+        }
+    }
+
+    /// Collect the best levels of a live side, skipping fully reserved ones
+    fn best_levels(&self, instrument_idx: u16, side: Side, head: u32, out: &mut [QuoteLevel; QUOTE_LEVELS]) -> usize {
+        let mut count = 0;
+        let mut order_idx = head;
+
+        while count < QUOTE_LEVELS {
+            let price = match self.get_order(order_idx) {
+                Some(order) => order.price,
+                None => break,
+            };
+            let level = match self.find_level(instrument_idx, OrderState::LIVE, side, price) {
+                Some(level) => level,
+                None => break,
+            };
+
+            let avail = self.level_available_qty(level);
+            if avail > 0 {
+                out[count] = QuoteLevel { px: price as i64, avail_qty: avail as i64 };
+                count += 1;
+            }
+
+            // Skip to the first order of the next level
+            order_idx = match self.get_order(level.tail) {
+                Some(order) => order.next,
+                None => break,
+            };
+        }
+
+        count
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instructions::{
+        process_cancel, process_commit, process_place_order, process_reserve, process_update_param, OrderParams, SlabParam,
+        TakerOptions,
+    };
+    use pinocchio::pubkey::Pubkey;
+    use crate::tests::{new_test_slab, test_account, test_dlp};
+    use percolator_common::*;
+
+    const PX: u64 = 50_000_000_000;
+    const TICK: u64 = 1_000_000;
+    const LOT: u64 = 1_000_000;
+
+    fn post(slab: &mut SlabState, side: Side, price: u64, qty: u64) -> u32 {
+        let maker = test_dlp(slab, 10);
+        let params = OrderParams {
+            instrument_idx: 0,
+            side,
+            price,
+            qty,
+            tif: TimeInForce::GTC,
+            maker_class: MakerClass::DLP,
+            flags: 0,
+        };
+        process_place_order(slab, maker, &params, 0).unwrap().order_idx
+    }
 
     #[test]
     fn test_quote_cache_creation() {
@@ -108,4 +214,80 @@ mod tests {
         assert_eq!(cache.total_bid_qty(), 3_000_000);
         assert_eq!(cache.total_ask_qty(), 1_500_000);
     }
+
+    #[test]
+    fn test_cache_tracks_best_levels() {
+        let mut slab = new_test_slab();
+        for p in 1..=6 {
+            post(&mut slab, Side::Sell, PX + p * TICK, LOT);
+            post(&mut slab, Side::Buy, PX - p * TICK, LOT);
+        }
+        post(&mut slab, Side::Sell, PX + TICK, 2 * LOT);
+
+        let cache = slab.get_quote_cache(0).unwrap();
+        assert_eq!(cache.seqno_snapshot, slab.header.seqno);
+        assert_eq!(cache.best_asks[0].px, (PX + TICK) as i64);
+        assert_eq!(cache.best_asks[0].avail_qty, 3 * LOT as i64);
+        assert_eq!(cache.best_asks[3].px, (PX + 4 * TICK) as i64);
+        assert_eq!(cache.best_bids[0].px, (PX - TICK) as i64);
+        assert_eq!(cache.best_bids[3].px, (PX - 4 * TICK) as i64);
+        assert!(slab.get_quote_cache(1).is_none());
+    }
+
+    #[test]
+    fn test_cache_follows_reserve_commit_and_cancel() {
+        let mut slab = new_test_slab();
+        post(&mut slab, Side::Sell, PX, LOT);
+        post(&mut slab, Side::Sell, PX + TICK, 2 * LOT);
+        let taker = test_account(&mut slab, 11);
+
+        // A reservation locks the best level out of the cache
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, 2 * LOT, PX + TICK, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+        let cache = *slab.get_quote_cache(0).unwrap();
+        assert_eq!(cache.seqno_snapshot, slab.header.seqno);
+        assert_eq!(cache.best_asks[0].px, (PX + TICK) as i64);
+        assert_eq!(cache.best_asks[0].avail_qty, LOT as i64);
+        assert_eq!(cache.best_asks[1].avail_qty, 0);
+
+        // Cancelling gives it back
+        process_cancel(&mut slab, resv.hold_id).unwrap();
+        let cache = *slab.get_quote_cache(0).unwrap();
+        assert_eq!(cache.seqno_snapshot, slab.header.seqno);
+        assert_eq!(cache.best_asks[0].px, PX as i64);
+        assert_eq!(cache.total_ask_qty(), 3 * LOT as i64);
+
+        // Committing consumes it
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, 2 * LOT, PX + TICK, 1_000, [0; 32], 2, &TakerOptions::default(), 0).unwrap();
+        process_commit(&mut slab, resv.hold_id, &[0; SALT_LEN], 0, 0).unwrap();
+        let cache = *slab.get_quote_cache(0).unwrap();
+        assert_eq!(cache.seqno_snapshot, slab.header.seqno);
+        assert_eq!(cache.best_asks[0].px, (PX + TICK) as i64);
+        assert_eq!(cache.total_ask_qty(), LOT as i64);
+    }
+
+    #[test]
+    fn test_book_seqno_ignores_other_changes() {
+        let mut slab = new_test_slab();
+        post(&mut slab, Side::Sell, PX, 2 * LOT);
+        let book_seqno = slab.get_quote_cache(0).unwrap().seqno_snapshot;
+
+        // A param change moves the slab seqno but not the book's
+        process_update_param(&mut slab, &Pubkey::from([1; 32]), SlabParam::SweepReward, 0).unwrap();
+        assert!(slab.header.seqno > book_seqno);
+        assert_eq!(slab.get_quote_cache(0).unwrap().seqno_snapshot, book_seqno);
+
+        // Reservations report the book version they were taken against
+        let taker = test_account(&mut slab, 11);
+        let resv = process_reserve(&mut slab, taker, 0, Side::Buy, LOT, PX, 1_000, [0; 32], 1, &TakerOptions::default(), 0).unwrap();
+        assert_eq!(resv.book_seqno, book_seqno as u64);
+        assert_eq!(slab.get_quote_cache(0).unwrap().seqno_snapshot, slab.header.seqno);
+    }
+
+    #[test]
+    fn test_cache_offset() {
+        // Routers read caches at this offset (sdk SLAB_QUOTE_CACHE_OFFSET)
+        assert_eq!(SlabState::quote_cache_offset(0), 392);
+        assert_eq!(SlabState::quote_cache_offset(1) - SlabState::quote_cache_offset(0), QuoteCache::LEN);
+        assert_eq!(QuoteCache::LEN, 136);
+    }
 }
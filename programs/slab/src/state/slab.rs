@@ -1,10 +1,11 @@
 //! Slab state - full orderbook with pools
 
-use super::{SlabHeader, QuoteCache};
+use super::{SlabHeader, QuoteCache, PriceLevel, ExpiryEntry, POOL_LEVELS};
 use percolator_common::{
     Order, Position, Reservation, Slice, Trade, Instrument, AccountState, AggressorEntry,
-    Side, OrderState,
+    FundingRecord, Side, OrderState, MAX_DLP,
 };
+use pinocchio::pubkey::Pubkey;
 
 /// Pool sizes for different account tiers
 /// Standard tier fits in ~3.5MB
@@ -16,6 +17,7 @@ pub const POOL_TRADES: usize = 10_000;
 pub const POOL_ACCOUNTS: usize = 5_000;
 pub const POOL_INSTRUMENTS: usize = 32;
 pub const POOL_AGGRESSOR: usize = 4_000;
+pub const POOL_FUNDING_HISTORY: usize = 4_096;
 
 /// Main slab state - full orderbook with pools
 /// Target size: ~10MB with all pools
@@ -24,8 +26,11 @@ pub struct SlabState {
     /// Header with metadata and risk parameters
     pub header: SlabHeader,
     
-    /// Quote cache (router-readable best levels)
-    pub quote_cache: QuoteCache,
+    /// Per-instrument quote caches (router-readable best levels)
+    pub quote_caches: [QuoteCache; POOL_INSTRUMENTS],
+
+    /// Designated LP registry (MAX_DLP = 100), first `header.dlp_count` are valid
+    pub dlps: [Pubkey; MAX_DLP],
     
     /// Instrument pool (MAX_INSTRUMENTS = 32)
     pub instruments: [Instrument; POOL_INSTRUMENTS],
@@ -47,9 +52,18 @@ pub struct SlabState {
     
     /// Trade ring buffer (POOL_TRADES = 10,000)
     pub trades: [Trade; POOL_TRADES],
+
+    /// Funding history ring buffer (POOL_FUNDING_HISTORY = 4,096)
+    pub funding_history: [FundingRecord; POOL_FUNDING_HISTORY],
     
     /// Aggressor ledger for ARG (POOL_AGGRESSOR = 4,000)
     pub aggressors: [AggressorEntry; POOL_AGGRESSOR],
+
+    /// Price level pool for the book index (POOL_LEVELS = 30,000)
+    pub levels: [PriceLevel; POOL_LEVELS],
+
+    /// Expiry index over reservations, a min-heap of `header.expiry_count` entries
+    pub expiry_index: [ExpiryEntry; POOL_RESERVATIONS],
 }
 
 impl SlabState {
@@ -57,6 +71,15 @@ impl SlabState {
     pub const LEN: usize = core::mem::size_of::<Self>();
     /// Invalid pool index marker
     pub const INVALID_INDEX: u32 = u32::MAX;
+    /// Byte offset of the funding history ring, read directly by clients
+    pub const FUNDING_HISTORY_OFFSET: usize = core::mem::offset_of!(Self, funding_history);
+    /// Byte offset of the first quote cache, read directly by routers
+    pub const QUOTE_CACHE_OFFSET: usize = core::mem::offset_of!(Self, quote_caches);
+
+    /// Byte offset of an instrument's quote cache
+    pub const fn quote_cache_offset(instrument_idx: u16) -> usize {
+        Self::QUOTE_CACHE_OFFSET + instrument_idx as usize * QuoteCache::LEN
+    }
 
     /// Initialize freelists for all pools
     pub fn initialize_pools(&mut self) {
@@ -103,6 +126,28 @@ impl SlabState {
             self.slices[i].used = false;
         }
         self.header.slice_freelist_head = 0;
+
+        // Initialize price level freelist (left child doubles as next free)
+        for i in 0..POOL_LEVELS {
+            self.levels[i].left = if i + 1 < POOL_LEVELS {
+                (i + 1) as u32
+            } else {
+                Self::INVALID_INDEX
+            };
+            self.levels[i].used = false;
+        }
+        self.header.level_freelist_head = 0;
+
+        // Initialize aggressor ledger freelist
+        for i in 0..POOL_AGGRESSOR {
+            self.aggressors[i].next = if i + 1 < POOL_AGGRESSOR {
+                (i + 1) as u32
+            } else {
+                Self::INVALID_INDEX
+            };
+            self.aggressors[i].used = false;
+        }
+        self.header.aggressor_freelist_head = 0;
     }
 
     // === Order Pool Operations ===
@@ -124,22 +169,59 @@ impl SlabState {
     }
     
     /// Free an order back to the freelist
+    ///
+    /// Also drops it from its account's order list.
     pub fn free_order(&mut self, idx: u32) {
         if idx as usize >= POOL_ORDERS {
             return;
         }
         
-        let order = &mut self.orders[idx as usize];
+        let order = self.orders[idx as usize];
         if !order.used {
             return; // Already free
         }
-        
+
+        if let Some(next) = self.get_order_mut(order.next_in_account) {
+            next.prev_in_account = order.prev_in_account;
+        }
+        if let Some(prev) = self.get_order_mut(order.prev_in_account) {
+            prev.next_in_account = order.next_in_account;
+        } else if let Some(acc) = self.get_account_mut(order.account_idx) {
+            if acc.order_head == idx {
+                acc.order_head = order.next_in_account;
+            }
+        }
+
+        let order = &mut self.orders[idx as usize];
         order.used = false;
         order.next_free = self.header.order_freelist_head;
         self.header.order_freelist_head = idx;
         self.header.order_count = self.header.order_count.saturating_sub(1);
     }
     
+    /// Put an order at the head of its account's order list
+    pub fn link_account_order(&mut self, idx: u32) {
+        let account_idx = match self.get_order(idx) {
+            Some(o) => o.account_idx,
+            None => return,
+        };
+        let head = match self.get_account(account_idx) {
+            Some(acc) => acc.order_head,
+            None => return,
+        };
+
+        if let Some(old_head) = self.get_order_mut(head) {
+            old_head.prev_in_account = idx;
+        }
+        if let Some(order) = self.get_order_mut(idx) {
+            order.next_in_account = head;
+            order.prev_in_account = Self::INVALID_INDEX;
+        }
+        if let Some(acc) = self.get_account_mut(account_idx) {
+            acc.order_head = idx;
+        }
+    }
+
     /// Get order by index
     pub fn get_order(&self, idx: u32) -> Option<&Order> {
         if idx as usize >= POOL_ORDERS {
@@ -158,6 +240,14 @@ impl SlabState {
         if order.used { Some(order) } else { None }
     }
 
+    /// Get the order a slice was reserved from
+    ///
+    /// Returns None once that order has left the slab, even if its slot now
+    /// holds a different order.
+    pub fn get_slice_order(&self, slice: &Slice) -> Option<&Order> {
+        self.get_order(slice.order_idx).filter(|o| o.order_id == slice.order_id)
+    }
+
     // === Position Pool Operations ===
     
     /// Allocate a position from the freelist
@@ -224,6 +314,7 @@ impl SlabState {
         self.header.reservation_freelist_head = resv.index;
         resv.used = true;
         resv.index = head;
+        resv.expiry_pos = Self::INVALID_INDEX;
         self.header.reservation_count += 1;
         
         Some(head)
@@ -235,11 +326,12 @@ impl SlabState {
             return;
         }
         
-        let resv = &mut self.reservations[idx as usize];
-        if !resv.used {
+        if !self.reservations[idx as usize].used {
             return;
         }
-        
+        self.unindex_expiry(idx);
+
+        let resv = &mut self.reservations[idx as usize];
         resv.used = false;
         resv.index = self.header.reservation_freelist_head;
         self.header.reservation_freelist_head = idx;
@@ -336,6 +428,13 @@ impl SlabState {
         self.header.trade_write_idx = self.header.trade_write_idx.wrapping_add(1);
     }
 
+    /// Record a funding update in the history ring buffer
+    pub fn record_funding(&mut self, record: FundingRecord) {
+        let idx = self.header.funding_write_idx as usize % POOL_FUNDING_HISTORY;
+        self.funding_history[idx] = record;
+        self.header.funding_write_idx = self.header.funding_write_idx.wrapping_add(1);
+    }
+
     // === Instrument Operations ===
     
     /// Get instrument by index
@@ -356,13 +455,18 @@ impl SlabState {
 
     // === Account Operations ===
     
+    /// Find an existing account by pubkey
+    pub fn find_account(&self, key: &Pubkey) -> Option<u32> {
+        (0..self.header.account_count as usize)
+            .find(|&i| self.accounts[i].active && &self.accounts[i].key == key)
+            .map(|i| i as u32)
+    }
+
     /// Get or create account by pubkey
-    pub fn get_or_create_account(&mut self, key: &pinocchio::pubkey::Pubkey) -> Option<u32> {
+    pub fn get_or_create_account(&mut self, key: &Pubkey) -> Option<u32> {
         // First try to find existing
-        for i in 0..self.header.account_count as usize {
-            if self.accounts[i].active && &self.accounts[i].key == key {
-                return Some(i as u32);
-            }
+        if let Some(idx) = self.find_account(key) {
+            return Some(idx);
         }
         
         // Create new if space available
@@ -373,10 +477,13 @@ impl SlabState {
                 cash: 0,
                 im: 0,
                 mm: 0,
+                funding_paid: 0,
+                funding_received: 0,
                 position_head: Self::INVALID_INDEX,
+                order_head: Self::INVALID_INDEX,
                 index: idx as u32,
                 active: true,
-                _padding: [0; 7],
+                _padding: [0; 3],
             };
             self.header.account_count += 1;
             return Some(idx as u32);
@@ -403,9 +510,106 @@ impl SlabState {
         if acc.active { Some(acc) } else { None }
     }
 
+    // === DLP Registry ===
+
+    /// Check whether a key is a registered designated LP
+    pub fn is_dlp(&self, key: &Pubkey) -> bool {
+        self.dlps[..self.header.dlp_count as usize].contains(key)
+    }
+
+    /// Register a designated LP, returning false if the registry is full
+    pub fn add_dlp(&mut self, key: &Pubkey) -> bool {
+        let count = self.header.dlp_count as usize;
+        if count >= MAX_DLP {
+            return false;
+        }
+        self.dlps[count] = *key;
+        self.header.dlp_count += 1;
+        true
+    }
+
+    /// Unregister a designated LP, returning false if it was not registered
+    pub fn remove_dlp(&mut self, key: &Pubkey) -> bool {
+        let count = self.header.dlp_count as usize;
+        match self.dlps[..count].iter().position(|k| k == key) {
+            Some(i) => {
+                // Swap-remove: registry order does not matter
+                self.dlps[i] = self.dlps[count - 1];
+                self.dlps[count - 1] = Pubkey::default();
+                self.header.dlp_count -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // === Aggressor Ledger ===
+
+    /// Find or create the ledger entry of an account on an instrument for an epoch
+    ///
+    /// Only the instrument's own entries are searched. An entry left over from
+    /// an earlier epoch is reset in place. Returns None if the ledger is full.
+    pub fn get_or_create_aggressor(&mut self, account_idx: u32, instrument_idx: u16, epoch: u16) -> Option<u32> {
+        let head = self.get_instrument(instrument_idx)?.aggressor_head;
+
+        let mut idx = head;
+        while idx != Self::INVALID_INDEX {
+            let entry = &mut self.aggressors[idx as usize];
+            if entry.account_idx == account_idx {
+                if entry.epoch != epoch {
+                    *entry = AggressorEntry {
+                        account_idx,
+                        instrument_idx,
+                        epoch,
+                        next: entry.next,
+                        used: true,
+                        ..AggressorEntry::default()
+                    };
+                }
+                return Some(idx);
+            }
+            idx = entry.next;
+        }
+
+        let idx = self.header.aggressor_freelist_head;
+        if idx == Self::INVALID_INDEX {
+            return None;
+        }
+        self.header.aggressor_freelist_head = self.aggressors[idx as usize].next;
+        self.aggressors[idx as usize] = AggressorEntry {
+            account_idx,
+            instrument_idx,
+            epoch,
+            next: head,
+            used: true,
+            ..AggressorEntry::default()
+        };
+        self.instruments[instrument_idx as usize].aggressor_head = idx;
+        Some(idx)
+    }
+
+    /// Release all ledger entries of an instrument
+    pub fn clear_aggressors(&mut self, instrument_idx: u16) {
+        let mut idx = match self.get_instrument_mut(instrument_idx) {
+            Some(instr) => core::mem::replace(&mut instr.aggressor_head, Self::INVALID_INDEX),
+            None => return,
+        };
+        while idx != Self::INVALID_INDEX {
+            let entry = &mut self.aggressors[idx as usize];
+            let next = entry.next;
+            entry.used = false;
+            entry.next = self.header.aggressor_freelist_head;
+            self.header.aggressor_freelist_head = idx;
+            idx = next;
+        }
+    }
+
     // === Book Operations ===
     
     /// Insert order into book (maintains price-time priority)
+    ///
+    /// The price-level index locates the insertion point: after the newest
+    /// order at the same price, or after the next better price.
     pub fn insert_order_into_book(&mut self, order_idx: u32, instrument_idx: u16) {
         let order = match self.get_order(order_idx) {
             Some(o) => o,
@@ -415,45 +619,25 @@ impl SlabState {
         let price = order.price;
         let state = order.state;
         
-        let instr = match self.get_instrument_mut(instrument_idx) {
+        let instr = match self.get_instrument(instrument_idx) {
             Some(i) => i,
             None => return,
         };
         
         // Get the appropriate book head based on state and side
-        let head_ptr = match (state, side) {
-            (OrderState::LIVE, Side::Buy) => &mut instr.bids_head,
-            (OrderState::LIVE, Side::Sell) => &mut instr.asks_head,
-            (OrderState::PENDING, Side::Buy) => &mut instr.bids_pending_head,
-            (OrderState::PENDING, Side::Sell) => &mut instr.asks_pending_head,
+        let head = match (state, side) {
+            (OrderState::LIVE, Side::Buy) => instr.bids_head,
+            (OrderState::LIVE, Side::Sell) => instr.asks_head,
+            (OrderState::PENDING, Side::Buy) => instr.bids_pending_head,
+            (OrderState::PENDING, Side::Sell) => instr.asks_pending_head,
         };
         
-        let head = *head_ptr;
-        
-        // Insert maintaining price-time priority
         // For bids: descending price (higher better)
         // For asks: ascending price (lower better)
-        let mut prev_idx = Self::INVALID_INDEX;
-        let mut curr_idx = head;
-        
-        while curr_idx != Self::INVALID_INDEX {
-            let curr = match self.get_order(curr_idx) {
-                Some(o) => o,
-                None => break,
-            };
-            
-            let should_insert_before = match side {
-                Side::Buy => price > curr.price,  // Higher price has priority for bids
-                Side::Sell => price < curr.price, // Lower price has priority for asks
-            };
-            
-            if should_insert_before {
-                break;
-            }
-            
-            prev_idx = curr_idx;
-            curr_idx = curr.next;
-        }
+        let (prev_idx, curr_idx) = match self.link_level(instrument_idx, state, side, price, order_idx, head) {
+            Some(links) => links,
+            None => return,
+        };
         
         // Update links
         if let Some(order) = self.get_order_mut(order_idx) {
@@ -481,8 +665,7 @@ impl SlabState {
                 prev.next = order_idx;
             }
         }
-        
-        self.header.increment_seqno();
+
     }
     
     /// Remove order from book
@@ -497,6 +680,8 @@ impl SlabState {
         let side = order.side;
         let state = order.state;
         let instrument_idx = order.instrument_idx;
+
+        self.unlink_level(instrument_idx, state, side, order_idx);
         
         // Update prev's next pointer
         if prev != Self::INVALID_INDEX {
@@ -527,8 +712,7 @@ impl SlabState {
             order.prev = Self::INVALID_INDEX;
             order.next = Self::INVALID_INDEX;
         }
-        
-        self.header.increment_seqno();
+
     }
     
     /// Get best contra order for a side (best ask for buy, best bid for sell)
@@ -554,7 +738,24 @@ impl SlabState {
         }
     }
     
+    /// Whether a price would cross the best live order on the contra side
+    pub fn crosses_book(&self, instrument_idx: u16, side: Side, price: u64) -> bool {
+        let best_contra = self
+            .get_best_contra(instrument_idx, side)
+            .and_then(|idx| self.get_order(idx));
+        match best_contra {
+            Some(best) => match side {
+                Side::Buy => price >= best.price,
+                Side::Sell => price <= best.price,
+            },
+            None => false,
+        }
+    }
+
     /// Promote pending orders to live (called at batch open)
+    ///
+    /// An order that would cross the live contra side stays pending and is
+    /// retried at the next batch, so promotion never crosses the book.
     pub fn promote_pending_orders(&mut self, instrument_idx: u16, current_epoch: u64) {
         let instr = match self.get_instrument_mut(instrument_idx) {
             Some(i) => i,
@@ -571,7 +772,9 @@ impl SlabState {
             
             let next = order.next;
             
-            if order.eligible_epoch as u64 <= current_epoch {
+            if order.eligible_epoch as u64 <= current_epoch
+                && !self.crosses_book(instrument_idx, Side::Buy, order.price)
+            {
                 // Remove from pending queue
                 self.remove_order_from_book(pending_head);
                 
@@ -598,7 +801,9 @@ impl SlabState {
             
             let next = order.next;
             
-            if order.eligible_epoch as u64 <= current_epoch {
+            if order.eligible_epoch as u64 <= current_epoch
+                && !self.crosses_book(instrument_idx, Side::Sell, order.price)
+            {
                 self.remove_order_from_book(pending_head);
                 
                 if let Some(order) = self.get_order_mut(pending_head) {
@@ -643,26 +848,6 @@ mod tests {
         assert_eq!(header.next_order_id, 1);
     }
 
-    #[test]
-    fn test_header_kill_band() {
-        let mut header = SlabHeader::new(
-            Pubkey::default(),
-            Pubkey::default(),
-            Pubkey::default(),
-            500, 250, -5, 20, 100, 255,
-        );
-
-        // Set initial mark price
-        header.update_mark_px(50_000_000_000); // $50,000
-        
-        // Move within kill band (1%)
-        header.update_mark_px(50_400_000_000); // $50,400 (0.8% move)
-        assert!(header.check_kill_band(50_400_000_000));
-        
-        // Move beyond kill band (>1%)
-        assert!(!header.check_kill_band(51_000_000_000)); // $51,000 (2% from prev)
-    }
-
     #[test]
     fn test_jit_detection() {
         let header = SlabHeader::new(
@@ -678,4 +863,33 @@ mod tests {
         // Order created 60ms ago (should not be JIT, min is 50ms)
         assert!(!header.is_jit_order(100, 160));
     }
+
+    #[test]
+    fn test_dlp_registry() {
+        let mut slab = crate::tests::new_test_slab();
+        let a = Pubkey::from([10; 32]);
+        let b = Pubkey::from([20; 32]);
+
+        assert!(slab.add_dlp(&a));
+        assert!(slab.add_dlp(&b));
+        assert!(slab.is_dlp(&a) && slab.is_dlp(&b));
+
+        assert!(slab.remove_dlp(&a));
+        assert!(!slab.remove_dlp(&a));
+        assert!(!slab.is_dlp(&a));
+        assert!(slab.is_dlp(&b));
+        assert_eq!(slab.header.dlp_count, 1);
+
+        for i in 0..MAX_DLP - 1 {
+            assert!(slab.add_dlp(&Pubkey::from([i as u8 + 100; 32])));
+        }
+        assert!(!slab.add_dlp(&a));
+    }
+
+    #[test]
+    fn test_funding_history_offset() {
+        // Clients read the ring at this offset (sdk SLAB_FUNDING_HISTORY_OFFSET)
+        assert_eq!(SlabState::FUNDING_HISTORY_OFFSET, 7_681_680);
+        assert_eq!(core::mem::size_of::<FundingRecord>(), 48);
+    }
 }
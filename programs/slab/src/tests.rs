@@ -3,6 +3,70 @@
 //! NOTE: Old complex design tests removed for v0.
 //! See tests/v0_*.rs for v0-specific tests.
 
+extern crate std;
+
+use std::alloc::{alloc_zeroed, Layout};
+use std::boxed::Box;
+
+use crate::instructions::process_add_instrument;
+use crate::state::{SlabHeader, SlabState};
+use pinocchio::pubkey::Pubkey;
+
+/// Allocate a zeroed, initialized slab on the heap
+///
+/// SlabState is several megabytes, so it cannot be built on the test stack.
+/// The returned slab has one instrument (index 0) with tick 1.0, lot 1.0 and
+/// a mark of $50,000 (1e6 scale).
+pub(crate) fn new_test_slab() -> Box<SlabState> {
+    let layout = Layout::new::<SlabState>();
+    // SAFETY: SlabState is plain-old-data, so an all-zero bit pattern is valid
+    let mut slab = unsafe {
+        let ptr = alloc_zeroed(layout) as *mut SlabState;
+        assert!(!ptr.is_null());
+        Box::from_raw(ptr)
+    };
+
+    slab.header = SlabHeader::new(
+        Pubkey::default(),
+        Pubkey::from([1; 32]),
+        Pubkey::from([2; 32]),
+        500,  // 5% IMR
+        250,  // 2.5% MMR
+        -5,   // -0.05% maker rebate
+        20,   // 0.2% taker fee
+        100,  // 100ms batch
+        255,
+    );
+    slab.initialize_pools();
+
+    process_add_instrument(
+        &mut slab,
+        *b"BTC-PERP",
+        1_000_000,
+        1_000_000,
+        1_000_000,
+        50_000_000_000,
+    )
+    .unwrap();
+
+    slab
+}
+
+/// Create (or look up) a test account keyed by a single repeated byte
+pub(crate) fn test_account(slab: &mut SlabState, key_byte: u8) -> u32 {
+    slab.get_or_create_account(&Pubkey::from([key_byte; 32])).unwrap()
+}
+
+/// Create a test account and register it as a designated LP
+pub(crate) fn test_dlp(slab: &mut SlabState, key_byte: u8) -> u32 {
+    let idx = test_account(slab, key_byte);
+    let key = Pubkey::from([key_byte; 32]);
+    if !slab.is_dlp(&key) {
+        slab.add_dlp(&key);
+    }
+    idx
+}
+
 #[cfg(test)]
 mod slab_v0_tests {
     // v0 unit tests are in tests/v0_*.rs
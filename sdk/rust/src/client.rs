@@ -112,6 +112,47 @@ impl PercolatorClient {
         }
     }
 
+    /// Fetch an instrument's funding history from a slab, newest first
+    pub fn get_funding_history(&self, slab_state: &Pubkey, instrument_idx: u16) -> Result<Vec<FundingRecord>> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(slab_state, self.config.commitment)
+            .map_err(|e| PercolatorSdkError::RpcError(e.to_string()))?
+            .value
+            .ok_or_else(|| PercolatorSdkError::AccountNotFound(slab_state.to_string()))?;
+
+        let record_len = std::mem::size_of::<FundingRecord>();
+        let ring = account
+            .data
+            .get(SLAB_FUNDING_HISTORY_OFFSET..SLAB_FUNDING_HISTORY_OFFSET + SLAB_FUNDING_HISTORY_LEN * record_len)
+            .ok_or_else(|| PercolatorSdkError::DeserializationError("slab account too small".to_string()))?;
+
+        let mut records: Vec<FundingRecord> = ring
+            .chunks_exact(record_len)
+            .map(bytemuck::pod_read_unaligned::<FundingRecord>)
+            .filter(|r| r.ts != 0 && r.instrument_idx == instrument_idx)
+            .collect();
+        records.sort_by(|a, b| b.ts.cmp(&a.ts));
+        Ok(records)
+    }
+
+    /// Fetch an instrument's quote cache from a slab
+    pub fn get_quote_cache(&self, slab_state: &Pubkey, instrument_idx: u16) -> Result<QuoteCache> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(slab_state, self.config.commitment)
+            .map_err(|e| PercolatorSdkError::RpcError(e.to_string()))?
+            .value
+            .ok_or_else(|| PercolatorSdkError::AccountNotFound(slab_state.to_string()))?;
+
+        let offset = SLAB_QUOTE_CACHE_OFFSET + instrument_idx as usize * SLAB_QUOTE_CACHE_LEN;
+        let data = account
+            .data
+            .get(offset..offset + SLAB_QUOTE_CACHE_LEN)
+            .ok_or_else(|| PercolatorSdkError::DeserializationError("slab account too small".to_string()))?;
+        Ok(bytemuck::pod_read_unaligned::<QuoteCache>(data))
+    }
+
     /// Check if portfolio exists
     pub fn portfolio_exists(&self, owner: &Pubkey) -> Result<bool> {
         let portfolio_pda = self.portfolio_pda(owner);
@@ -196,6 +237,11 @@ impl PercolatorClient {
         create_global_liquidation_instruction(liquidator, &target_portfolio, slab_accounts)
     }
 
+    /// Build sweep expired instruction (keeper crank)
+    pub fn build_sweep_expired(&self, slab_state: &Pubkey, keeper: &Pubkey, max_sweep: u8) -> Instruction {
+        create_sweep_expired_instruction(slab_state, keeper, max_sweep.min(MAX_SWEEP))
+    }
+
     // ==========================================================================
     // INSURANCE OPERATIONS
     // ==========================================================================
@@ -85,6 +85,17 @@ pub enum SlabInstruction {
     CompleteInsuranceWithdrawal = 11,
     CancelInsuranceWithdrawal = 12,
     UpdateInsuranceConfig = 13,
+    PlaceOrder = 14,
+    UpdateParam = 15,
+    AmendOrder = 16,
+    MassQuote = 17,
+    AddDlp = 18,
+    RemoveDlp = 19,
+    SetOracle = 20,
+    UpdateMark = 21,
+    SettleFunding = 22,
+    SweepExpired = 23,
+    CancelOrder = 24,
 }
 
 // ============================================================================
@@ -121,6 +132,21 @@ pub const DEFAULT_INSURANCE_RATE_BPS: u64 = 25; // 0.25%
 /// Insurance pool withdrawal timelock (seconds)
 pub const INSURANCE_WITHDRAWAL_TIMELOCK_SECS: u64 = 7 * 24 * 60 * 60; // 7 days
 
+/// Most reservations one SweepExpired instruction releases
+pub const MAX_SWEEP: u8 = 64;
+
+/// Byte offset of the funding history ring in a slab account
+pub const SLAB_FUNDING_HISTORY_OFFSET: usize = 7_681_680;
+
+/// Number of entries in the funding history ring
+pub const SLAB_FUNDING_HISTORY_LEN: usize = 4_096;
+
+/// Byte offset of instrument 0's quote cache in a slab account
+pub const SLAB_QUOTE_CACHE_OFFSET: usize = 392;
+
+/// Size of one instrument's quote cache (caches are stored back to back)
+pub const SLAB_QUOTE_CACHE_LEN: usize = 136;
+
 // ============================================================================
 // ORDER TYPES
 // ============================================================================
@@ -145,6 +171,67 @@ pub enum TimeInForce {
     POST = 3, // Post only (maker only)
 }
 
+/// Maximum number of levels in one mass quote
+pub const MAX_QUOTE_LEVELS: usize = 32;
+
+/// Order flag: reject if the order would cross the book
+pub const ORDER_FLAG_POST_ONLY: u8 = 1 << 0;
+
+/// Order flag: the order may only reduce an existing position
+pub const ORDER_FLAG_REDUCE_ONLY: u8 = 1 << 1;
+
+/// Self-trade prevention mode
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelfTradePrevention {
+    #[default]
+    SlabDefault = 0,   // Use the slab's configured mode
+    Allow = 1,         // Let the orders trade (only if the slab allows it)
+    CancelResting = 2, // Cancel the resting order
+    CancelTaking = 3,  // Stop the taker at the own order
+    DecrementBoth = 4, // Reduce both sides by the overlap
+    Skip = 5,          // Walk past the own order
+}
+
+/// Slab-wide setting ids for `UpdateParam`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlabParam {
+    SelfTradePrevention = 0,  // SelfTradePrevention as u8
+    RequireCommitment = 1,    // 0 or 1
+    ArgEnabled = 2,           // 0 or 1
+    ArgMode = 3,              // ArgMode as u8
+    ArgTaxBps = 4,            // basis points
+    OracleMaxStalenessMs = 5, // milliseconds
+    OracleMaxConfBps = 6,     // basis points of price
+    FundingInterestBps = 7,   // basis points per interval
+    FundingCapBps = 8,        // basis points per interval
+    SweepReward = 9,          // cash units per swept reservation
+    ShortfallPolicy = 10,     // ShortfallPolicy as u8
+    LiqBufferBps = 11,        // basis points of notional above MM
+    LiqBandBps = 12,          // basis points from mark, at most 500
+    MaxTtlMs = 13,            // milliseconds, positive
+}
+
+/// Action taken by the Aggressor Roundtrip Guard
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArgMode {
+    #[default]
+    Tax = 0,    // Fill and charge the ARG tax on the overlap
+    Clip = 1,   // Fill only the part that adds new exposure
+    Reject = 2, // Reject the commit
+}
+
+/// Commit action on reserved slices whose maker order no longer backs them
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShortfallPolicy {
+    #[default]
+    AutoReduce = 0, // Fill what is backed and report the shortfall
+    StrictFail = 1, // Reject the commit
+}
+
 /// Maker class for anti-toxicity
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
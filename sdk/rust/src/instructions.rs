@@ -241,6 +241,30 @@ pub fn create_commit_instruction(
     Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
 }
 
+/// Create commit instruction that fills only part of a reservation
+///
+/// The slab fills up to `qty` and releases the rest of the reservation back
+/// to the book. `salt` opens the reservation's commitment (zeros if none).
+pub fn create_partial_commit_instruction(
+    slab_state: &Pubkey,
+    router: &Pubkey,
+    hold_id: u64,
+    salt: &[u8; 16],
+    qty: u64,
+) -> Instruction {
+    let mut data = vec![SlabInstruction::Commit as u8];
+    data.extend_from_slice(&hold_id.to_le_bytes());
+    data.extend_from_slice(salt);
+    data.extend_from_slice(&qty.to_le_bytes());
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*router, true),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
 /// Create cancel instruction
 pub fn create_cancel_instruction(
     slab_state: &Pubkey,
@@ -258,6 +282,224 @@ pub fn create_cancel_instruction(
     Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
 }
 
+/// Create place order instruction
+///
+/// DLP orders post live immediately; regular orders wait for the next batch.
+/// `TimeInForce::POST` is sent as a GTC order with the post-only flag.
+pub fn create_place_order_instruction(
+    slab_state: &Pubkey,
+    owner: &Pubkey,
+    params: &OrderParams,
+    is_dlp: bool,
+) -> Instruction {
+    let mut data = vec![SlabInstruction::PlaceOrder as u8];
+    data.extend_from_slice(&(params.instrument_index as u16).to_le_bytes());
+    data.push(params.side as u8);
+    data.extend_from_slice(&params.price.to_le_bytes());
+    data.extend_from_slice(&params.qty.to_le_bytes());
+
+    let mut flags = 0u8;
+    let tif = match params.time_in_force {
+        TimeInForce::POST => {
+            flags |= ORDER_FLAG_POST_ONLY;
+            TimeInForce::GTC
+        }
+        tif => tif,
+    };
+    if params.reduce_only {
+        flags |= ORDER_FLAG_REDUCE_ONLY;
+    }
+
+    data.push(tif as u8);
+    data.push(is_dlp as u8);
+    data.push(flags);
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*owner, true),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create cancel order instruction
+///
+/// Orders inside the top-K freeze cannot be cancelled until it expires.
+pub fn create_cancel_order_instruction(
+    slab_state: &Pubkey,
+    owner: &Pubkey,
+    order_idx: u32,
+) -> Instruction {
+    let mut data = vec![SlabInstruction::CancelOrder as u8];
+    data.extend_from_slice(&order_idx.to_le_bytes());
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*owner, true),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create amend order instruction
+///
+/// Reducing only the size keeps queue priority; any other change re-inserts
+/// the order with a new order ID.
+pub fn create_amend_order_instruction(
+    slab_state: &Pubkey,
+    owner: &Pubkey,
+    order_idx: u32,
+    new_price: u64,
+    new_qty: u64,
+) -> Instruction {
+    let mut data = vec![SlabInstruction::AmendOrder as u8];
+    data.extend_from_slice(&order_idx.to_le_bytes());
+    data.extend_from_slice(&new_price.to_le_bytes());
+    data.extend_from_slice(&new_qty.to_le_bytes());
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*owner, true),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create mass quote instruction
+///
+/// Cancels the owner's resting orders on the instrument and posts `levels`
+/// (at most MAX_QUOTE_LEVELS) in their place.
+pub fn create_mass_quote_instruction(
+    slab_state: &Pubkey,
+    owner: &Pubkey,
+    instrument_index: u16,
+    is_dlp: bool,
+    levels: &[QuoteLevel],
+) -> Instruction {
+    let mut data = vec![SlabInstruction::MassQuote as u8];
+    data.extend_from_slice(&instrument_index.to_le_bytes());
+    data.push(is_dlp as u8);
+    data.push(levels.len() as u8);
+    for level in levels {
+        data.push(level.side as u8);
+        data.extend_from_slice(&level.price.to_le_bytes());
+        data.extend_from_slice(&level.qty.to_le_bytes());
+        data.push(level.flags);
+    }
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*owner, true),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create add DLP instruction (LP owner only)
+pub fn create_add_dlp_instruction(slab_state: &Pubkey, lp_owner: &Pubkey, dlp: &Pubkey) -> Instruction {
+    let mut data = vec![SlabInstruction::AddDlp as u8];
+    data.extend_from_slice(dlp.as_ref());
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*lp_owner, true),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create remove DLP instruction (LP owner only)
+pub fn create_remove_dlp_instruction(slab_state: &Pubkey, lp_owner: &Pubkey, dlp: &Pubkey) -> Instruction {
+    let mut data = vec![SlabInstruction::RemoveDlp as u8];
+    data.extend_from_slice(dlp.as_ref());
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*lp_owner, true),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create set oracle instruction (LP owner only)
+pub fn create_set_oracle_instruction(
+    slab_state: &Pubkey,
+    lp_owner: &Pubkey,
+    instrument_idx: u16,
+    oracle: &Pubkey,
+) -> Instruction {
+    let mut data = vec![SlabInstruction::SetOracle as u8];
+    data.extend_from_slice(&instrument_idx.to_le_bytes());
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*lp_owner, true),
+        AccountMeta::new_readonly(*oracle, false),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create update mark instruction (refreshes the mark from the instrument's oracle)
+pub fn create_update_mark_instruction(
+    slab_state: &Pubkey,
+    oracle: &Pubkey,
+    instrument_idx: u16,
+) -> Instruction {
+    let mut data = vec![SlabInstruction::UpdateMark as u8];
+    data.extend_from_slice(&instrument_idx.to_le_bytes());
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*oracle, false),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create settle funding instruction (books an account's accrued funding)
+pub fn create_settle_funding_instruction(slab_state: &Pubkey, account_idx: u32) -> Instruction {
+    let mut data = vec![SlabInstruction::SettleFunding as u8];
+    data.extend_from_slice(&account_idx.to_le_bytes());
+
+    let accounts = vec![AccountMeta::new(*slab_state, false)];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create sweep expired instruction (releases up to `max_sweep` expired reservations)
+///
+/// The keeper signs and is credited with the slab's sweep reward.
+pub fn create_sweep_expired_instruction(slab_state: &Pubkey, keeper: &Pubkey, max_sweep: u8) -> Instruction {
+    let data = vec![SlabInstruction::SweepExpired as u8, max_sweep];
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*keeper, true),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
+/// Create update param instruction (LP owner only)
+pub fn create_update_param_instruction(
+    slab_state: &Pubkey,
+    lp_owner: &Pubkey,
+    param: SlabParam,
+    value: u64,
+) -> Instruction {
+    let mut data = vec![SlabInstruction::UpdateParam as u8];
+    data.push(param as u8);
+    data.extend_from_slice(&value.to_le_bytes());
+
+    let accounts = vec![
+        AccountMeta::new(*slab_state, false),
+        AccountMeta::new_readonly(*lp_owner, true),
+    ];
+
+    Instruction::new_with_bytes(SLAB_PROGRAM_ID, &data, accounts)
+}
+
 // ============================================================================
 // INSURANCE INSTRUCTIONS
 // ============================================================================
@@ -75,14 +75,12 @@ pub struct SlabHeader {
     pub taker_fee_bps: u64,
     /// Batch window (ms)
     pub batch_ms: u64,
-    /// Kill band (bps)
+    /// Default kill band for new instruments (bps)
     pub kill_band_bps: u64,
     /// Freeze levels
     pub freeze_levels: u16,
     /// JIT penalty enabled
     pub jit_penalty_on: bool,
-    /// Mark price
-    pub mark_px: i64,
     /// Instrument count
     pub instrument_count: u16,
     /// Order count
@@ -131,6 +129,52 @@ pub struct InsuranceStats {
     pub last_payout_ts: u64,
 }
 
+/// Funding history entry, as stored in the slab's funding ring
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FundingRecord {
+    /// End of the last interval charged (ms)
+    pub ts: u64,
+    /// Funding rate charged per interval (basis points)
+    pub rate: i64,
+    /// TWAP premium the rate was derived from (basis points)
+    pub premium_bps: i64,
+    /// Mark price at the update (scaled)
+    pub mark_px: i64,
+    /// Index price at the update (scaled)
+    pub index_price: u64,
+    /// Instrument index
+    pub instrument_idx: u16,
+    /// Number of intervals charged at `rate`
+    pub intervals: u16,
+    /// Padding
+    pub _padding: [u8; 4],
+}
+
+/// One cached book level, as stored in a slab's quote cache
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct QuoteCacheLevel {
+    /// Price (scaled)
+    pub px: i64,
+    /// Quantity not locked by reservations (scaled)
+    pub avail_qty: i64,
+}
+
+/// Best levels of an instrument, as stored in the slab's quote caches
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct QuoteCache {
+    /// Book sequence number: slab seqno of the last change to this instrument's book
+    pub seqno_snapshot: u32,
+    /// Padding
+    pub _padding: u32,
+    /// Best 4 bid levels (descending price, empty levels zeroed)
+    pub best_bids: [QuoteCacheLevel; 4],
+    /// Best 4 ask levels (ascending price, empty levels zeroed)
+    pub best_asks: [QuoteCacheLevel; 4],
+}
+
 // ============================================================================
 // ORDER TYPES
 // ============================================================================
@@ -154,6 +198,19 @@ pub struct OrderParams {
     pub reduce_only: bool,
 }
 
+/// One level of a mass quote ladder
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuoteLevel {
+    /// Order side
+    pub side: Side,
+    /// Limit price (scaled by PRICE_SCALE)
+    pub price: u64,
+    /// Quantity (scaled by QTY_SCALE)
+    pub qty: u64,
+    /// Order flags (ORDER_FLAG_*)
+    pub flags: u8,
+}
+
 /// Slab split for multi-slab operations
 #[derive(Debug, Clone, Default)]
 pub struct SlabSplit {
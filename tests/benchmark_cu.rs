@@ -297,7 +297,6 @@ mod benchmark_tests {
                 &ctx.slab_program_id,
                 &slab.pubkey(),
                 0,
-                1704067200000,
             );
             
             let start = Instant::now();
@@ -347,8 +346,6 @@ mod benchmark_tests {
                 &ctx.slab_program_id,
                 &slab.pubkey(),
                 0,
-                50_100_000_000,
-                1704067200000,
             );
             
             let start = Instant::now();
@@ -11,6 +11,7 @@ use solana_sdk::{
     transaction::Transaction,
     instruction::{AccountMeta, Instruction},
     rent::Rent,
+    clock::Clock,
     compute_budget::ComputeBudgetInstruction,
 };
 use std::str::FromStr;
@@ -46,6 +47,9 @@ pub mod slab_ix {
     pub const ADD_INSTRUMENT: u8 = 5;
     pub const UPDATE_FUNDING: u8 = 6;
     pub const LIQUIDATION: u8 = 7;
+    pub const PLACE_ORDER: u8 = 14;
+    pub const ADD_DLP: u8 = 18;
+    pub const CANCEL_ORDER: u8 = 24;
 }
 
 pub mod router_ix {
@@ -141,6 +145,16 @@ impl TestContext {
         slab
     }
     
+    /// Move the Clock sysvar forward by `secs` seconds and one slot
+    pub async fn advance_clock(&mut self, secs: i64) {
+        let slot: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        self.ctx.warp_to_slot(slot.slot + 1).unwrap();
+
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += secs;
+        self.ctx.set_sysvar(&clock);
+    }
+    
     /// Get account data
     pub async fn get_account(&mut self, pubkey: &Pubkey) -> Option<Account> {
         self.ctx.banks_client.get_account(*pubkey).await.unwrap()
@@ -233,11 +247,9 @@ pub fn ix_batch_open(
     program_id: &Pubkey,
     slab: &Pubkey,
     instrument_idx: u16,
-    current_ts: u64,
 ) -> Instruction {
     let mut data = vec![slab_ix::BATCH_OPEN];
     data.extend_from_slice(&instrument_idx.to_le_bytes());
-    data.extend_from_slice(&current_ts.to_le_bytes());
     
     Instruction {
         program_id: *program_id,
@@ -246,10 +258,63 @@ pub fn ix_batch_open(
     }
 }
 
-/// Create reserve instruction
+/// Create add DLP instruction (signed by the LP owner)
+pub fn ix_add_dlp(
+    program_id: &Pubkey,
+    slab: &Pubkey,
+    lp_owner: &Pubkey,
+    dlp: &Pubkey,
+) -> Instruction {
+    let mut data = vec![slab_ix::ADD_DLP];
+    data.extend_from_slice(dlp.as_ref());
+    
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*slab, false),
+            AccountMeta::new_readonly(*lp_owner, true),
+        ],
+        data,
+    }
+}
+
+/// Create place order instruction (signed by the order owner)
+pub fn ix_place_order(
+    program_id: &Pubkey,
+    slab: &Pubkey,
+    owner: &Pubkey,
+    instrument_idx: u16,
+    side: u8,
+    price: u64,
+    qty: u64,
+    tif: u8,
+    maker_class: u8,
+    flags: u8,
+) -> Instruction {
+    let mut data = vec![slab_ix::PLACE_ORDER];
+    data.extend_from_slice(&instrument_idx.to_le_bytes());
+    data.push(side);
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&qty.to_le_bytes());
+    data.push(tif);
+    data.push(maker_class);
+    data.push(flags);
+    
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*slab, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data,
+    }
+}
+
+/// Create reserve instruction (signed by the account owner or router authority)
 pub fn ix_reserve(
     program_id: &Pubkey,
     slab: &Pubkey,
+    authority: &Pubkey,
     account_idx: u32,
     instrument_idx: u16,
     side: u8,
@@ -271,37 +336,60 @@ pub fn ix_reserve(
     
     Instruction {
         program_id: *program_id,
-        accounts: vec![AccountMeta::new(*slab, false)],
+        accounts: vec![
+            AccountMeta::new(*slab, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
         data,
     }
 }
 
-/// Create commit instruction
+/// Create commit instruction (signed by the reservation's account owner or router authority)
 pub fn ix_commit(
     program_id: &Pubkey,
     slab: &Pubkey,
+    authority: &Pubkey,
     hold_id: u64,
-    current_ts: u64,
 ) -> Instruction {
     let mut data = vec![slab_ix::COMMIT];
     data.extend_from_slice(&hold_id.to_le_bytes());
-    data.extend_from_slice(&current_ts.to_le_bytes());
     
     Instruction {
         program_id: *program_id,
-        accounts: vec![AccountMeta::new(*slab, false)],
+        accounts: vec![
+            AccountMeta::new(*slab, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
         data,
     }
 }
 
-/// Create cancel instruction
-pub fn ix_cancel(program_id: &Pubkey, slab: &Pubkey, hold_id: u64) -> Instruction {
+/// Create cancel instruction (signed by the reservation's account owner or router authority)
+pub fn ix_cancel(program_id: &Pubkey, slab: &Pubkey, authority: &Pubkey, hold_id: u64) -> Instruction {
     let mut data = vec![slab_ix::CANCEL];
     data.extend_from_slice(&hold_id.to_le_bytes());
     
     Instruction {
         program_id: *program_id,
-        accounts: vec![AccountMeta::new(*slab, false)],
+        accounts: vec![
+            AccountMeta::new(*slab, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+/// Create cancel order instruction (signed by the order owner)
+pub fn ix_cancel_order(program_id: &Pubkey, slab: &Pubkey, owner: &Pubkey, order_idx: u32) -> Instruction {
+    let mut data = vec![slab_ix::CANCEL_ORDER];
+    data.extend_from_slice(&order_idx.to_le_bytes());
+    
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*slab, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
         data,
     }
 }
@@ -311,13 +399,9 @@ pub fn ix_update_funding(
     program_id: &Pubkey,
     slab: &Pubkey,
     instrument_idx: u16,
-    index_price: u64,
-    current_ts: u64,
 ) -> Instruction {
     let mut data = vec![slab_ix::UPDATE_FUNDING];
     data.extend_from_slice(&instrument_idx.to_le_bytes());
-    data.extend_from_slice(&index_price.to_le_bytes());
-    data.extend_from_slice(&current_ts.to_le_bytes());
     
     Instruction {
         program_id: *program_id,
@@ -332,12 +416,10 @@ pub fn ix_liquidation(
     slab: &Pubkey,
     account_idx: u32,
     deficit_target: i128,
-    current_ts: u64,
 ) -> Instruction {
     let mut data = vec![slab_ix::LIQUIDATION];
     data.extend_from_slice(&account_idx.to_le_bytes());
     data.extend_from_slice(&deficit_target.to_le_bytes());
-    data.extend_from_slice(&current_ts.to_le_bytes());
     
     Instruction {
         program_id: *program_id,
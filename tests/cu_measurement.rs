@@ -212,11 +212,9 @@ pub fn create_batch_open_instruction(
     program_id: &Pubkey,
     slab_account: &Pubkey,
     instrument_idx: u16,
-    current_ts: u64,
 ) -> Instruction {
     let mut data = vec![slab_instruction::BATCH_OPEN];
     data.extend_from_slice(&instrument_idx.to_le_bytes());
-    data.extend_from_slice(&current_ts.to_le_bytes());
 
     Instruction {
         program_id: *program_id,
@@ -337,9 +335,9 @@ mod unit_tests {
         assert_eq!(ix.data[0], slab_instruction::ADD_INSTRUMENT);
         assert_eq!(ix.data.len(), 1 + 40);
         
-        let ix = create_batch_open_instruction(&program_id, &slab, 0, 1704067200);
+        let ix = create_batch_open_instruction(&program_id, &slab, 0);
         assert_eq!(ix.data[0], slab_instruction::BATCH_OPEN);
-        assert_eq!(ix.data.len(), 1 + 10);
+        assert_eq!(ix.data.len(), 1 + 2);
     }
     
     #[test]
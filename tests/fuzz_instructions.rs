@@ -76,7 +76,6 @@ pub struct ReserveData {
 #[derive(Debug, Clone)]
 pub struct CommitData {
     pub hold_id: u64,
-    pub current_ts: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -87,21 +86,17 @@ pub struct CancelData {
 #[derive(Debug, Clone)]
 pub struct BatchOpenData {
     pub instrument_idx: u16,
-    pub current_ts: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct UpdateFundingData {
     pub instrument_idx: u16,
-    pub index_price: u64,
-    pub current_ts: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct LiquidationData {
     pub account_idx: u32,
     pub deficit_target: i128,
-    pub current_ts: u64,
 }
 
 /// Parse error
@@ -258,15 +253,14 @@ fn parse_reserve(data: &[u8]) -> Result<ParsedInstruction, ParseError> {
 }
 
 fn parse_commit(data: &[u8]) -> Result<ParsedInstruction, ParseError> {
-    // Expected: 8 + 8 = 16 bytes
-    if data.len() < 16 {
-        return Err(ParseError::TooShort { expected: 16, actual: data.len() });
+    // Expected: 8 bytes (time comes from the Clock sysvar)
+    if data.len() < 8 {
+        return Err(ParseError::TooShort { expected: 8, actual: data.len() });
     }
     
     let hold_id = u64::from_le_bytes(data[0..8].try_into().unwrap());
-    let current_ts = u64::from_le_bytes(data[8..16].try_into().unwrap());
     
-    Ok(ParsedInstruction::Commit(CommitData { hold_id, current_ts }))
+    Ok(ParsedInstruction::Commit(CommitData { hold_id }))
 }
 
 fn parse_cancel(data: &[u8]) -> Result<ParsedInstruction, ParseError> {
@@ -281,48 +275,39 @@ fn parse_cancel(data: &[u8]) -> Result<ParsedInstruction, ParseError> {
 }
 
 fn parse_batch_open(data: &[u8]) -> Result<ParsedInstruction, ParseError> {
-    // Expected: 2 + 8 = 10 bytes
-    if data.len() < 10 {
-        return Err(ParseError::TooShort { expected: 10, actual: data.len() });
+    // Expected: 2 bytes
+    if data.len() < 2 {
+        return Err(ParseError::TooShort { expected: 2, actual: data.len() });
     }
     
     let instrument_idx = u16::from_le_bytes(data[0..2].try_into().unwrap());
-    let current_ts = u64::from_le_bytes(data[2..10].try_into().unwrap());
     
-    Ok(ParsedInstruction::BatchOpen(BatchOpenData { instrument_idx, current_ts }))
+    Ok(ParsedInstruction::BatchOpen(BatchOpenData { instrument_idx }))
 }
 
 fn parse_update_funding(data: &[u8]) -> Result<ParsedInstruction, ParseError> {
-    // Expected: 2 + 8 + 8 = 18 bytes
-    if data.len() < 18 {
-        return Err(ParseError::TooShort { expected: 18, actual: data.len() });
+    // Expected: 2 bytes
+    if data.len() < 2 {
+        return Err(ParseError::TooShort { expected: 2, actual: data.len() });
     }
     
     let instrument_idx = u16::from_le_bytes(data[0..2].try_into().unwrap());
-    let index_price = u64::from_le_bytes(data[2..10].try_into().unwrap());
-    let current_ts = u64::from_le_bytes(data[10..18].try_into().unwrap());
     
-    Ok(ParsedInstruction::UpdateFunding(UpdateFundingData {
-        instrument_idx,
-        index_price,
-        current_ts,
-    }))
+    Ok(ParsedInstruction::UpdateFunding(UpdateFundingData { instrument_idx }))
 }
 
 fn parse_liquidation(data: &[u8]) -> Result<ParsedInstruction, ParseError> {
-    // Expected: 4 + 16 + 8 = 28 bytes
-    if data.len() < 28 {
-        return Err(ParseError::TooShort { expected: 28, actual: data.len() });
+    // Expected: 4 + 16 = 20 bytes
+    if data.len() < 20 {
+        return Err(ParseError::TooShort { expected: 20, actual: data.len() });
     }
     
     let account_idx = u32::from_le_bytes(data[0..4].try_into().unwrap());
     let deficit_target = i128::from_le_bytes(data[4..20].try_into().unwrap());
-    let current_ts = u64::from_le_bytes(data[20..28].try_into().unwrap());
     
     Ok(ParsedInstruction::Liquidation(LiquidationData {
         account_idx,
         deficit_target,
-        current_ts,
     }))
 }
 
@@ -463,18 +448,15 @@ proptest! {
     #[test]
     fn fuzz_valid_commit(
         hold_id in any::<u64>(),
-        current_ts in any::<u64>(),
     ) {
         let mut data = vec![ix_disc::COMMIT];
         data.extend_from_slice(&hold_id.to_le_bytes());
-        data.extend_from_slice(&current_ts.to_le_bytes());
         
         let result = parse_instruction(&data);
         prop_assert!(result.is_ok());
         
         if let Ok(ParsedInstruction::Commit(commit)) = result {
             prop_assert_eq!(commit.hold_id, hold_id);
-            prop_assert_eq!(commit.current_ts, current_ts);
         } else {
             prop_assert!(false, "Expected Commit instruction");
         }
@@ -583,7 +565,6 @@ mod edge_cases {
             let mut data = vec![ix_disc::LIQUIDATION];
             data.extend(0u32.to_le_bytes()); // account_idx
             data.extend(val.to_le_bytes()); // deficit_target
-            data.extend(0u64.to_le_bytes()); // current_ts
             
             let result = parse_instruction(&data);
             assert!(result.is_ok());
@@ -599,13 +580,13 @@ mod edge_cases {
         // Ensure all valid discriminators are handled
         let test_cases = [
             (ix_disc::RESERVE, 71),
-            (ix_disc::COMMIT, 16),
+            (ix_disc::COMMIT, 8),
             (ix_disc::CANCEL, 8),
-            (ix_disc::BATCH_OPEN, 10),
+            (ix_disc::BATCH_OPEN, 2),
             (ix_disc::INITIALIZE, 136),
             (ix_disc::ADD_INSTRUMENT, 40),
-            (ix_disc::UPDATE_FUNDING, 18),
-            (ix_disc::LIQUIDATION, 28),
+            (ix_disc::UPDATE_FUNDING, 2),
+            (ix_disc::LIQUIDATION, 20),
         ];
         
         for (disc, min_len) in test_cases {
@@ -392,13 +392,12 @@ mod cpi_tests {
 
     #[test]
     fn test_cpi_commit_data_format() {
-        let mut commit_data = [0u8; 17];
+        let mut commit_data = [0u8; 9];
         commit_data[0] = 5; // COMMIT discriminator
         let hold_id: u64 = 123;
-        let current_ts: u64 = 1704067200000;
 
+        // No timestamp: the slab reads the Clock sysvar
         commit_data[1..9].copy_from_slice(&hold_id.to_le_bytes());
-        commit_data[9..17].copy_from_slice(&current_ts.to_le_bytes());
 
         // Verify
         assert_eq!(commit_data[0], 5);
@@ -160,7 +160,6 @@ mod integration_tests {
             &ctx.slab_program_id,
             &slab.pubkey(),
             0,  // instrument index
-            1704067200000, // timestamp
         );
         
         match ctx.send_ix_with_budget(batch_ix, 100_000, &[]).await {
@@ -170,16 +169,15 @@ mod integration_tests {
     }
 
     #[tokio::test]
-    async fn test_reserve_flow() {
+    async fn test_batch_open_follows_clock() {
         if skip_if_no_bpf() { return; }
-        
-        println!("\n🧪 Test: Reserve Flow");
+
+        println!("\n🧪 Test: Batch Open Follows Clock");
         println!("{}", "─".repeat(50));
-        
+
         let mut ctx = TestContext::new_with_slab().await;
         let slab = ctx.create_slab_account().await;
-        
-        // Full setup: init -> add instrument -> batch open
+
         let init_ix = ix_initialize_slab(
             &ctx.slab_program_id,
             &slab.pubkey(),
@@ -189,8 +187,7 @@ mod integration_tests {
             500, 250, -10, 30, 500,
         );
         ctx.send_ix_with_budget(init_ix, 200_000, &[]).await.unwrap();
-        println!("  ✓ Slab initialized");
-        
+
         let add_ix = ix_add_instrument(
             &ctx.slab_program_id,
             &slab.pubkey(),
@@ -198,58 +195,52 @@ mod integration_tests {
             100_000_000, 100_000, 1_000_000, 50_000_000_000,
         );
         ctx.send_ix_with_budget(add_ix, 50_000, &[]).await.unwrap();
-        println!("  ✓ Instrument added");
-        
-        let batch_ix = ix_batch_open(
-            &ctx.slab_program_id,
-            &slab.pubkey(),
-            0,
-            1704067200000,
-        );
-        ctx.send_ix_with_budget(batch_ix, 100_000, &[]).await.unwrap();
-        println!("  ✓ Batch opened");
-        
-        // Reserve liquidity
-        let reserve_ix = ix_reserve(
-            &ctx.slab_program_id,
-            &slab.pubkey(),
-            0,                    // account_idx
-            0,                    // instrument_idx
-            0,                    // side: 0=bid, 1=ask
-            1_000_000,            // qty: 1 contract
-            50_100_000_000,       // limit price: $50,100
-            30_000,               // TTL: 30 seconds
-            [0u8; 32],            // commitment hash
-            0,                    // route_id
-        );
-        
-        match ctx.send_ix_with_budget(reserve_ix, 200_000, &[]).await {
-            Ok(_) => println!("  ✅ Reserve succeeded"),
-            Err(e) => println!("  ❌ Reserve failed (expected if no liquidity): {:?}", e),
-        }
+
+        let batch_ix = ix_batch_open(&ctx.slab_program_id, &slab.pubkey(), 0);
+        ctx.send_ix_with_budget(batch_ix.clone(), 100_000, &[]).await.unwrap();
+        println!("  ✓ First batch opened");
+
+        // Same clock: the batch window has not elapsed
+        let result = ctx.send_ix_with_budget(batch_ix.clone(), 100_001, &[]).await;
+        assert!(result.is_err(), "batch open before the window elapsed should fail");
+        println!("  ✓ Early batch open rejected");
+
+        // A caller can no longer skip ahead, but the clock can
+        ctx.advance_clock(1).await;
+        ctx.send_ix_with_budget(batch_ix, 100_002, &[]).await.unwrap();
+        println!("  ✓ Batch opened after the clock advanced");
     }
 
-    #[tokio::test]
-    async fn test_reserve_commit_cancel_flow() {
-        if skip_if_no_bpf() { return; }
-        
-        println!("\n🧪 Test: Reserve-Commit-Cancel Flow");
-        println!("{}", "─".repeat(50));
-        
-        let mut ctx = TestContext::new_with_slab().await;
+    /// Account slots created by `setup_market`, in creation order
+    const MAKER_IDX: u32 = 0;
+    const TAKER_IDX: u32 = 1;
+
+    /// First hold ID the slab hands out
+    const FIRST_HOLD_ID: u64 = 1;
+
+    /// Order slot of the maker's ask posted by `setup_market`
+    const MAKER_ASK_IDX: u32 = 0;
+
+    /// Initialize a BTC slab with a DLP ask of one lot at $50,000 and give
+    /// the taker an account slot through a resting bid well below it
+    ///
+    /// Returns (slab, maker, taker).
+    async fn setup_market(ctx: &mut TestContext) -> (Keypair, Keypair, Keypair) {
         let slab = ctx.create_slab_account().await;
-        
-        // Setup
+        let lp_owner = ctx.ctx.payer.pubkey();
+        let maker = Keypair::new();
+        let taker = Keypair::new();
+
         let init_ix = ix_initialize_slab(
             &ctx.slab_program_id,
             &slab.pubkey(),
             market_id("BTC-PERP"),
-            &ctx.ctx.payer.pubkey(),
+            &lp_owner,
             &ctx.router_program_id,
             500, 250, -10, 30, 500,
         );
         ctx.send_ix_with_budget(init_ix, 200_000, &[]).await.unwrap();
-        
+
         let add_ix = ix_add_instrument(
             &ctx.slab_program_id,
             &slab.pubkey(),
@@ -257,43 +248,146 @@ mod integration_tests {
             100_000_000, 100_000, 1_000_000, 50_000_000_000,
         );
         ctx.send_ix_with_budget(add_ix, 50_000, &[]).await.unwrap();
-        
-        let batch_ix = ix_batch_open(
+
+        let dlp_ix = ix_add_dlp(&ctx.slab_program_id, &slab.pubkey(), &lp_owner, &maker.pubkey());
+        ctx.send_ix_with_budget(dlp_ix, 50_000, &[]).await.unwrap();
+
+        // Maker: DLP ask posts live (side 1 = ask, tif 0 = GTC, class 1 = DLP)
+        let ask_ix = ix_place_order(
             &ctx.slab_program_id,
             &slab.pubkey(),
-            0,
-            1704067200000,
+            &maker.pubkey(),
+            0, 1, 50_000_000_000, 1_000_000, 0, 1, 0,
         );
-        ctx.send_ix_with_budget(batch_ix, 100_000, &[]).await.unwrap();
-        
-        println!("  ✓ Setup complete");
-        
-        // Test cancel (should fail if no reservation)
-        let cancel_ix = ix_cancel(
+        ctx.send_ix_with_budget(ask_ix, 100_000, &[&maker]).await.unwrap();
+
+        // Taker: a pending REG bid at $40,000 only opens the account slot
+        let bid_ix = ix_place_order(
             &ctx.slab_program_id,
             &slab.pubkey(),
-            0, // hold_id
+            &taker.pubkey(),
+            0, 0, 40_000_000_000, 1_000_000, 0, 0, 0,
         );
-        
-        match ctx.send_ix_with_budget(cancel_ix, 50_000, &[]).await {
-            Ok(_) => println!("  ✓ Cancel succeeded (empty reservation)"),
-            Err(_) => println!("  ✓ Cancel rejected (expected: no reservation)"),
-        }
-        
-        // Test commit (should fail if no reservation)
-        let commit_ix = ix_commit(
+        ctx.send_ix_with_budget(bid_ix, 100_000, &[&taker]).await.unwrap();
+
+        (slab, maker, taker)
+    }
+
+    /// Reserve one lot against the maker's ask on behalf of the taker
+    fn reserve_one_lot(ctx: &TestContext, slab: &Keypair, authority: &Keypair) -> solana_sdk::instruction::Instruction {
+        ix_reserve(
             &ctx.slab_program_id,
             &slab.pubkey(),
-            0, // hold_id
-            1704067200000,
-        );
+            &authority.pubkey(),
+            TAKER_IDX,
+            0,                    // instrument_idx
+            0,                    // side: 0=bid, 1=ask
+            1_000_000,            // qty: 1 lot
+            50_100_000_000,       // limit price: $50,100
+            30_000,               // TTL: 30 seconds
+            [0u8; 32],            // no commitment
+            0,                    // route_id
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reserve_commit_flow() {
+        if skip_if_no_bpf() { return; }
         
-        match ctx.send_ix_with_budget(commit_ix, 100_000, &[]).await {
-            Ok(_) => println!("  ✓ Commit succeeded"),
-            Err(_) => println!("  ✓ Commit rejected (expected: no reservation)"),
-        }
+        println!("\n🧪 Test: Reserve-Commit Flow");
+        println!("{}", "─".repeat(50));
+        
+        let mut ctx = TestContext::new_with_slab().await;
+        let (slab, _maker, taker) = setup_market(&mut ctx).await;
+        println!("  ✓ Market set up (maker #{}, taker #{})", MAKER_IDX, TAKER_IDX);
+        
+        let reserve_ix = reserve_one_lot(&ctx, &slab, &taker);
+        ctx.send_ix_with_budget(reserve_ix, 200_000, &[&taker]).await
+            .expect("owner-signed reserve should succeed");
+        println!("  ✅ Reserve succeeded");
+        
+        let commit_ix = ix_commit(&ctx.slab_program_id, &slab.pubkey(), &taker.pubkey(), FIRST_HOLD_ID);
+        ctx.send_ix_with_budget(commit_ix, 200_000, &[&taker]).await
+            .expect("owner-signed commit should succeed");
+        println!("  ✅ Commit succeeded");
+        
+        // The hold is consumed by the commit
+        let cancel_ix = ix_cancel(&ctx.slab_program_id, &slab.pubkey(), &taker.pubkey(), FIRST_HOLD_ID);
+        let result = ctx.send_ix_with_budget(cancel_ix, 50_000, &[&taker]).await;
+        assert!(result.is_err(), "a committed hold cannot be cancelled");
+        println!("  ✓ Committed hold is gone");
+    }
+
+    #[tokio::test]
+    async fn test_reserve_commit_cancel_require_owner() {
+        if skip_if_no_bpf() { return; }
+        
+        println!("\n🧪 Test: Reserve-Commit-Cancel Require Owner");
+        println!("{}", "─".repeat(50));
+        
+        let mut ctx = TestContext::new_with_slab().await;
+        let (slab, maker, taker) = setup_market(&mut ctx).await;
+        println!("  ✓ Market set up");
+        
+        // The maker cannot reserve against the taker's account
+        let reserve_ix = reserve_one_lot(&ctx, &slab, &maker);
+        let result = ctx.send_ix_with_budget(reserve_ix, 200_000, &[&maker]).await;
+        assert!(result.is_err(), "reserve signed by a non-owner should fail");
+        println!("  ✓ Non-owner reserve rejected");
+        
+        let reserve_ix = reserve_one_lot(&ctx, &slab, &taker);
+        ctx.send_ix_with_budget(reserve_ix, 200_000, &[&taker]).await
+            .expect("owner-signed reserve should succeed");
+        println!("  ✓ Owner reserve succeeded");
+        
+        // Nor commit or cancel the taker's hold
+        let commit_ix = ix_commit(&ctx.slab_program_id, &slab.pubkey(), &maker.pubkey(), FIRST_HOLD_ID);
+        let result = ctx.send_ix_with_budget(commit_ix, 200_000, &[&maker]).await;
+        assert!(result.is_err(), "commit signed by a non-owner should fail");
+        println!("  ✓ Non-owner commit rejected");
+        
+        let cancel_ix = ix_cancel(&ctx.slab_program_id, &slab.pubkey(), &maker.pubkey(), FIRST_HOLD_ID);
+        let result = ctx.send_ix_with_budget(cancel_ix, 50_000, &[&maker]).await;
+        assert!(result.is_err(), "cancel signed by a non-owner should fail");
+        println!("  ✓ Non-owner cancel rejected");
+        
+        let cancel_ix = ix_cancel(&ctx.slab_program_id, &slab.pubkey(), &taker.pubkey(), FIRST_HOLD_ID);
+        ctx.send_ix_with_budget(cancel_ix, 50_000, &[&taker]).await
+            .expect("owner-signed cancel should succeed");
+        println!("  ✅ Owner cancel succeeded");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_order_respects_owner_and_freeze() {
+        if skip_if_no_bpf() { return; }
+        
+        println!("\n🧪 Test: Cancel Order Respects Owner And Freeze");
+        println!("{}", "─".repeat(50));
+        
+        let mut ctx = TestContext::new_with_slab().await;
+        let (slab, maker, taker) = setup_market(&mut ctx).await;
+        
+        // The batch freezes the top levels for half the 500ms window
+        let batch_ix = ix_batch_open(&ctx.slab_program_id, &slab.pubkey(), 0);
+        ctx.send_ix_with_budget(batch_ix, 100_000, &[]).await.unwrap();
+        println!("  ✓ Batch opened, maker ask frozen");
+        
+        let cancel_ix = ix_cancel_order(&ctx.slab_program_id, &slab.pubkey(), &maker.pubkey(), MAKER_ASK_IDX);
+        let result = ctx.send_ix_with_budget(cancel_ix, 50_000, &[&maker]).await;
+        assert!(result.is_err(), "a frozen order cannot be cancelled");
+        println!("  ✓ Frozen cancel rejected");
+        
+        ctx.advance_clock(1).await;
         
-        println!("  ✅ Reserve-Commit-Cancel flow tested");
+        let cancel_ix = ix_cancel_order(&ctx.slab_program_id, &slab.pubkey(), &taker.pubkey(), MAKER_ASK_IDX);
+        let result = ctx.send_ix_with_budget(cancel_ix, 50_001, &[&taker]).await;
+        assert!(result.is_err(), "cancel order signed by a non-owner should fail");
+        println!("  ✓ Non-owner cancel rejected");
+        
+        let cancel_ix = ix_cancel_order(&ctx.slab_program_id, &slab.pubkey(), &maker.pubkey(), MAKER_ASK_IDX);
+        ctx.send_ix_with_budget(cancel_ix, 50_002, &[&maker]).await
+            .expect("owner cancel after the freeze should succeed");
+        println!("  ✅ Owner cancel succeeded after the freeze");
     }
 
     #[tokio::test]
@@ -330,8 +424,6 @@ mod integration_tests {
             &ctx.slab_program_id,
             &slab.pubkey(),
             0,                    // instrument_idx
-            50_100_000_000,       // index price: $50,100
-            1704067200000,        // timestamp
         );
         
         match ctx.send_ix_with_budget(funding_ix, 200_000, &[]).await {
@@ -375,7 +467,6 @@ mod integration_tests {
             &slab.pubkey(),
             0,                    // account_idx
             0,                    // deficit_target
-            1704067200000,        // timestamp
         );
         
         match ctx.send_ix_with_budget(liq_ix, 300_000, &[]).await {
@@ -433,14 +524,20 @@ mod unit_tests {
         let program_id = slab_program_id();
         let slab = Keypair::new();
         
+        let owner = Keypair::new();
+        
         let ix = ix_reserve(
             &program_id,
             &slab.pubkey(),
+            &owner.pubkey(),
             0, 0, 0, 1_000_000, 50_000_000_000, 30_000,
             [0u8; 32], 0,
         );
         
         assert_eq!(ix.data[0], slab_ix::RESERVE);
+        // The owner signs
+        assert_eq!(ix.accounts.len(), 2);
+        assert!(ix.accounts[1].is_signer);
         // 1 + 4 + 2 + 1 + 8 + 8 + 8 + 32 + 8 = 72
         assert_eq!(ix.data.len(), 72);
     }
@@ -450,11 +547,15 @@ mod unit_tests {
         let program_id = slab_program_id();
         let slab = Keypair::new();
         
-        let ix = ix_commit(&program_id, &slab.pubkey(), 123, 1704067200000);
+        let owner = Keypair::new();
+        
+        let ix = ix_commit(&program_id, &slab.pubkey(), &owner.pubkey(), 123);
         
         assert_eq!(ix.data[0], slab_ix::COMMIT);
-        // 1 + 8 + 8 = 17
-        assert_eq!(ix.data.len(), 17);
+        assert_eq!(ix.accounts.len(), 2);
+        assert!(ix.accounts[1].is_signer);
+        // 1 + 8 = 9
+        assert_eq!(ix.data.len(), 9);
         
         // Verify hold_id encoding
         let hold_id = u64::from_le_bytes(ix.data[1..9].try_into().unwrap());
@@ -466,25 +567,46 @@ mod unit_tests {
         let program_id = slab_program_id();
         let slab = Keypair::new();
         
-        let ix = ix_cancel(&program_id, &slab.pubkey(), 456);
+        let owner = Keypair::new();
+        
+        let ix = ix_cancel(&program_id, &slab.pubkey(), &owner.pubkey(), 456);
         
         assert_eq!(ix.data[0], slab_ix::CANCEL);
+        assert_eq!(ix.accounts.len(), 2);
+        assert!(ix.accounts[1].is_signer);
         assert_eq!(ix.data.len(), 9);
         
         let hold_id = u64::from_le_bytes(ix.data[1..9].try_into().unwrap());
         assert_eq!(hold_id, 456);
     }
     
+    #[test]
+    fn test_cancel_order_instruction_encoding() {
+        let program_id = slab_program_id();
+        let slab = Keypair::new();
+        let owner = Keypair::new();
+        
+        let ix = ix_cancel_order(&program_id, &slab.pubkey(), &owner.pubkey(), 7);
+        
+        assert_eq!(ix.data[0], slab_ix::CANCEL_ORDER);
+        assert_eq!(ix.accounts.len(), 2);
+        assert!(ix.accounts[1].is_signer);
+        assert_eq!(ix.data.len(), 5);
+        
+        let order_idx = u32::from_le_bytes(ix.data[1..5].try_into().unwrap());
+        assert_eq!(order_idx, 7);
+    }
+    
     #[test]
     fn test_batch_open_instruction_encoding() {
         let program_id = slab_program_id();
         let slab = Keypair::new();
         
-        let ix = ix_batch_open(&program_id, &slab.pubkey(), 0, 1704067200000);
+        let ix = ix_batch_open(&program_id, &slab.pubkey(), 0);
         
         assert_eq!(ix.data[0], slab_ix::BATCH_OPEN);
-        // 1 + 2 + 8 = 11
-        assert_eq!(ix.data.len(), 11);
+        // 1 + 2 = 3
+        assert_eq!(ix.data.len(), 3);
     }
     
     #[test]
@@ -509,11 +631,11 @@ mod unit_tests {
         let program_id = slab_program_id();
         let slab = Keypair::new();
         
-        let ix = ix_update_funding(&program_id, &slab.pubkey(), 0, 50_000_000_000, 1704067200000);
+        let ix = ix_update_funding(&program_id, &slab.pubkey(), 0);
         
         assert_eq!(ix.data[0], slab_ix::UPDATE_FUNDING);
-        // 1 + 2 + 8 + 8 = 19
-        assert_eq!(ix.data.len(), 19);
+        // 1 + 2 = 3
+        assert_eq!(ix.data.len(), 3);
     }
     
     #[test]
@@ -521,11 +643,11 @@ mod unit_tests {
         let program_id = slab_program_id();
         let slab = Keypair::new();
         
-        let ix = ix_liquidation(&program_id, &slab.pubkey(), 0, 1_000_000, 1704067200000);
+        let ix = ix_liquidation(&program_id, &slab.pubkey(), 0, 1_000_000);
         
         assert_eq!(ix.data[0], slab_ix::LIQUIDATION);
-        // 1 + 4 + 16 + 8 = 29
-        assert_eq!(ix.data.len(), 29);
+        // 1 + 4 + 16 = 21
+        assert_eq!(ix.data.len(), 21);
     }
     
     #[test]